@@ -0,0 +1,98 @@
+use reqwest::header::{HeaderMap, AUTHORIZATION};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// One recorded HTTP exchange.
+#[derive(Debug, Serialize)]
+pub struct Exchange {
+    pub request: RecordedRequest,
+    /// None if the request never got a response.
+    pub response: Option<RecordedResponse>,
+    /// Transport error, if the request failed before a response arrived.
+    pub error: Option<String>,
+    pub elapsed_ms: u64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: serde_json::Map<String, serde_json::Value>,
+    pub body: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    pub headers: serde_json::Map<String, serde_json::Value>,
+    pub body: serde_json::Value,
+}
+
+/// HTTP exchanges made during one test attempt, in order. Clones share the log.
+#[derive(Clone, Default)]
+pub struct RequestLog {
+    exchanges: Arc<Mutex<Vec<Exchange>>>,
+}
+
+impl RequestLog {
+    pub fn push(&self, exchange: Exchange) {
+        self.exchanges.lock().unwrap().push(exchange);
+    }
+
+    /// Write each exchange as `001.json`, `002.json`, ... under
+    /// `<dir>/<group>/<test>/`, replacing files left by an earlier run.
+    /// Returns the directory written to.
+    pub fn write(&self, dir: &Path, group: &str, test: &str) -> std::io::Result<PathBuf> {
+        let target = dir.join(path_component(group)).join(path_component(test));
+        if target.exists() {
+            std::fs::remove_dir_all(&target)?;
+        }
+        std::fs::create_dir_all(&target)?;
+
+        let exchanges = self.exchanges.lock().unwrap();
+        for (i, exchange) in exchanges.iter().enumerate() {
+            let json = serde_json::to_string_pretty(exchange)
+                .expect("recorded exchange is always serializable");
+            std::fs::write(target.join(format!("{:03}.json", i + 1)), json)?;
+        }
+        Ok(target)
+    }
+}
+
+/// Headers as a JSON object, with credentials redacted.
+pub fn headers_json(headers: &HeaderMap) -> serde_json::Map<String, serde_json::Value> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if name == AUTHORIZATION {
+                "<redacted>".to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), serde_json::Value::String(value))
+        })
+        .collect()
+}
+
+/// A body as JSON if it parses, otherwise as a (lossy) string; null if empty.
+pub fn body_json(body: &[u8]) -> serde_json::Value {
+    if body.is_empty() {
+        return serde_json::Value::Null;
+    }
+    serde_json::from_slice(body)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(body).into_owned()))
+}
+
+/// A group or test name made safe for use as a single directory name.
+fn path_component(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' || c == '_' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,286 @@
+use crate::client::OrchestratorClient;
+use crate::console::outln;
+use crate::context;
+use crate::interrupt::Interrupt;
+use crate::stats::millis;
+use crate::tests::util;
+use chrono::{DateTime, Utc};
+use colored::Colorize;
+use serde::Serialize;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Unrecorded cycles run before measuring, so connection setup and worker
+/// start-up stay out of the numbers.
+const WARMUP_CYCLES: usize = 10;
+
+/// Operations of one cycle, in the order they run.
+const OPERATIONS: [&str; 3] = ["create", "get", "delete"];
+
+/// Percentiles reported for each operation, besides min and max.
+const PERCENTILES: [(&str, f64); 3] = [("p50", 50.0), ("p90", 90.0), ("p99", 99.0)];
+
+/// What `steel-tester bench` measures.
+pub struct BenchSettings {
+    /// Create/get/delete cycles measured per phase.
+    pub iterations: usize,
+    /// If set, a second phase runs this many cycles at once.
+    pub concurrency: Option<usize>,
+}
+
+/// Latency summary of a benchmark run, serializable as JSON for trend
+/// tracking.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct BenchReport {
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub url: String,
+    pub iterations: usize,
+    pub warmup: usize,
+    pub phases: Vec<Phase>,
+    /// True if Ctrl-C stopped the run before every cycle was measured.
+    pub interrupted: bool,
+    pub exit_code: i32,
+}
+
+/// Measurements taken at one level of concurrency.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct Phase {
+    pub name: String,
+    pub concurrency: usize,
+    pub operations: Vec<OperationStats>,
+}
+
+/// Latency distribution of one operation. The latency fields are None when
+/// every request failed.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct OperationStats {
+    pub operation: &'static str,
+    pub samples: usize,
+    pub errors: usize,
+    pub min_ms: Option<f64>,
+    pub p50_ms: Option<f64>,
+    pub p90_ms: Option<f64>,
+    pub p99_ms: Option<f64>,
+    pub max_ms: Option<f64>,
+}
+
+impl BenchReport {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("bench report is always serializable")
+    }
+}
+
+/// Latencies of successful requests and the count of failed ones, per
+/// operation.
+#[derive(Default)]
+struct Samples {
+    latencies: [Vec<Duration>; 3],
+    errors: [usize; 3],
+}
+
+impl Samples {
+    fn merge(&mut self, other: Samples) {
+        for (i, latencies) in other.latencies.into_iter().enumerate() {
+            self.latencies[i].extend(latencies);
+            self.errors[i] += other.errors[i];
+        }
+    }
+
+    fn into_stats(self) -> Vec<OperationStats> {
+        OPERATIONS
+            .iter()
+            .zip(self.latencies)
+            .zip(self.errors)
+            .map(|((operation, mut latencies), errors)| {
+                latencies.sort_unstable();
+                let at = |p| util::percentile(&latencies, p).map(millis);
+                let [p50, p90, p99] = PERCENTILES.map(|(_, p)| at(p));
+                OperationStats {
+                    operation,
+                    samples: latencies.len(),
+                    errors,
+                    min_ms: latencies.first().copied().map(millis),
+                    p50_ms: p50,
+                    p90_ms: p90,
+                    p99_ms: p99,
+                    max_ms: latencies.last().copied().map(millis),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Warm up, then measure create/get/delete latencies: first with cycles run
+/// one after another, so queueing never counts as service time, then, if
+/// `settings.concurrency` is set, with that many cycles in flight at once.
+/// Prints a table and returns the report; the exit code is 1 if any request
+/// failed.
+pub async fn run(
+    client: &OrchestratorClient,
+    settings: &BenchSettings,
+    interrupt: &Interrupt,
+) -> BenchReport {
+    let started_at = Utc::now();
+    outln!();
+    outln!(
+        "{}",
+        format!(
+            "⏱ Benchmarking {} cycles ({WARMUP_CYCLES} warm-up)",
+            settings.iterations
+        )
+        .bold()
+    );
+
+    let remaining = AtomicUsize::new(WARMUP_CYCLES);
+    run_cycles(client, &remaining, interrupt).await;
+
+    let mut phases = Vec::new();
+    let remaining = AtomicUsize::new(settings.iterations);
+    let samples = run_cycles(client, &remaining, interrupt).await;
+    phases.push(Phase {
+        name: "sequential".to_string(),
+        concurrency: 1,
+        operations: samples.into_stats(),
+    });
+
+    if let Some(concurrency) = settings.concurrency {
+        let remaining = Arc::new(AtomicUsize::new(settings.iterations));
+        let handles: Vec<_> = (0..concurrency)
+            .map(|_| {
+                let client = client.clone();
+                let remaining = Arc::clone(&remaining);
+                let interrupt = interrupt.clone();
+                context::spawn(async move { run_cycles(&client, &remaining, &interrupt).await })
+            })
+            .collect();
+        let mut samples = Samples::default();
+        for handle in futures::future::join_all(handles)
+            .await
+            .into_iter()
+            .flatten()
+        {
+            samples.merge(handle);
+        }
+        phases.push(Phase {
+            name: "concurrent".to_string(),
+            concurrency,
+            operations: samples.into_stats(),
+        });
+    }
+
+    let interrupted = interrupt.is_set();
+    let failed = phases
+        .iter()
+        .flat_map(|phase| &phase.operations)
+        .any(|op| op.errors > 0);
+    let exit_code = if interrupted {
+        crate::interrupt::INTERRUPTED_EXIT_CODE
+    } else if failed {
+        1
+    } else {
+        0
+    };
+    let report = BenchReport {
+        started_at,
+        duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+        url: client.base_url().to_string(),
+        iterations: settings.iterations,
+        warmup: WARMUP_CYCLES,
+        phases,
+        interrupted,
+        exit_code,
+    };
+    print_table(&report);
+    report
+}
+
+/// Run create → get → delete cycles until `remaining` is used up or the run
+/// is interrupted. Clones sharing `remaining` split the cycles between them.
+async fn run_cycles(
+    client: &OrchestratorClient,
+    remaining: &AtomicUsize,
+    interrupt: &Interrupt,
+) -> Samples {
+    let mut samples = Samples::default();
+    while !interrupt.is_set()
+        && remaining
+            .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |n| n.checked_sub(1))
+            .is_ok()
+    {
+        let data = serde_json::json!({"user": util::unique("bench")});
+        let start = Instant::now();
+        let session = match client.create_session(data).await {
+            Ok(session) => session,
+            Err(_) => {
+                samples.errors[0] += 1;
+                continue;
+            }
+        };
+        samples.latencies[0].push(start.elapsed());
+
+        let start = Instant::now();
+        match client.get_session(&session.id).await {
+            Ok(_) => samples.latencies[1].push(start.elapsed()),
+            Err(_) => samples.errors[1] += 1,
+        }
+
+        let start = Instant::now();
+        match client.delete_session(&session.id).await {
+            Ok(status) if status.is_success() => samples.latencies[2].push(start.elapsed()),
+            _ => samples.errors[2] += 1,
+        }
+    }
+    samples
+}
+
+fn print_table(report: &BenchReport) {
+    let percentiles: String = PERCENTILES
+        .iter()
+        .map(|(name, _)| format!("{name:>9}"))
+        .collect();
+    outln!();
+    outln!(
+        "{}",
+        format!(
+            "{:<18} {:<7} {:>5} {:>6}{:>9}{percentiles}{:>9}",
+            "phase", "op", "n", "errors", "min", "max"
+        )
+        .bold()
+    );
+    for phase in &report.phases {
+        let label = format!("{} (×{})", phase.name, phase.concurrency);
+        for op in &phase.operations {
+            let cells: String = [op.min_ms, op.p50_ms, op.p90_ms, op.p99_ms, op.max_ms]
+                .iter()
+                .map(|ms| match ms {
+                    Some(ms) => format!("{ms:>9.2}"),
+                    None => format!("{:>9}", "-"),
+                })
+                .collect();
+            let errors = format!("{:>6}", op.errors);
+            let errors = if op.errors > 0 {
+                errors.red().to_string()
+            } else {
+                errors
+            };
+            outln!(
+                "{label:<18} {:<7} {:>5} {errors}{cells}",
+                op.operation,
+                op.samples
+            );
+        }
+    }
+    outln!("Latencies in milliseconds.");
+    if report.interrupted {
+        outln!(
+            "{}",
+            "⏹ interrupted: the numbers cover only completed cycles".yellow()
+        );
+    }
+    outln!();
+}
@@ -0,0 +1,107 @@
+//! Optional orchestrator endpoints. Production-like deployments leave some of
+//! them out, so the CLI probes for them before a run and the runner skips
+//! tests that need a missing one instead of failing them.
+
+use crate::client::OrchestratorClient;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use std::fmt;
+
+/// An endpoint some tests depend on but the orchestrator need not expose.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// POST /debug/crash-worker, used to kill workers on purpose.
+    CrashWorker,
+    /// GET /metrics in the Prometheus text format.
+    Metrics,
+    /// GET /sessions listing the live sessions.
+    ListSessions,
+}
+
+impl Capability {
+    pub const ALL: [Capability; 3] = [
+        Capability::CrashWorker,
+        Capability::Metrics,
+        Capability::ListSessions,
+    ];
+
+    /// The request this capability stands for.
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            Capability::CrashWorker => "POST /debug/crash-worker",
+            Capability::Metrics => "GET /metrics",
+            Capability::ListSessions => "GET /sessions",
+        }
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.endpoint())
+    }
+}
+
+/// Which optional endpoints the orchestrator serves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[non_exhaustive]
+pub struct Capabilities {
+    pub crash_worker: bool,
+    pub metrics: bool,
+    pub list_sessions: bool,
+}
+
+impl Default for Capabilities {
+    /// Everything available, for runs that don't probe.
+    fn default() -> Self {
+        Self {
+            crash_worker: true,
+            metrics: true,
+            list_sessions: true,
+        }
+    }
+}
+
+impl Capabilities {
+    /// Probe each optional endpoint with a request that has no side effects.
+    /// An endpoint counts as missing when it answers 404 or 405, or doesn't
+    /// answer at all.
+    pub async fn probe(client: &OrchestratorClient) -> Self {
+        // Without a session_id the orchestrator rejects the crash with a 400
+        // before touching any worker.
+        let crash_worker = answers(client, Method::POST, "/debug/crash-worker").await;
+        let metrics = answers(client, Method::GET, "/metrics").await;
+        let list_sessions = answers(client, Method::GET, "/sessions").await;
+        Self {
+            crash_worker,
+            metrics,
+            list_sessions,
+        }
+    }
+
+    pub fn has(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::CrashWorker => self.crash_worker,
+            Capability::Metrics => self.metrics,
+            Capability::ListSessions => self.list_sessions,
+        }
+    }
+
+    /// Capabilities the orchestrator lacks, in `Capability::ALL` order.
+    pub fn missing(&self) -> Vec<Capability> {
+        Capability::ALL
+            .into_iter()
+            .filter(|capability| !self.has(*capability))
+            .collect()
+    }
+}
+
+async fn answers(client: &OrchestratorClient, method: Method, path: &str) -> bool {
+    match client.send_raw(client.request(method, path)).await {
+        Ok(resp) => !matches!(
+            resp.status,
+            StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+        ),
+        Err(_) => false,
+    }
+}
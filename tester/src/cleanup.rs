@@ -0,0 +1,128 @@
+use crate::client::{OrchestratorClient, Session};
+use crate::console::outln;
+use colored::Colorize;
+use futures::stream::{self, StreamExt};
+
+/// Prefixes of the `user` markers the suite's tests put in their session
+/// data, as `util::unique` suffixes them.
+pub const TEST_PREFIXES: &[&str] = &[
+    "bench_",
+    "capacity_",
+    "chaos_",
+    "churn_",
+    "cloned_",
+    "concurrent_",
+    "crash_",
+    "errors_",
+    "id_",
+    "inflight_",
+    "isolation_",
+    "metrics_",
+    "payload_",
+    "post_crash_",
+    "protocol_",
+    "race_",
+    "restart_",
+    "shutdown_",
+    "soak_",
+    "stress_",
+    "test_",
+    "ttl_",
+];
+
+/// What `steel-tester cleanup` deletes, and how.
+#[derive(Debug, Clone)]
+pub struct CleanupSettings {
+    /// Only delete sessions whose `user` starts with one of `TEST_PREFIXES`.
+    pub only_test_sessions: bool,
+    /// Print what would be deleted without deleting it.
+    pub dry_run: bool,
+    /// Deletes in flight at once.
+    pub concurrency: usize,
+}
+
+impl Default for CleanupSettings {
+    fn default() -> Self {
+        Self {
+            only_test_sessions: false,
+            dry_run: false,
+            concurrency: 8,
+        }
+    }
+}
+
+/// Whether a session was created by the suite, judging by its `user` marker.
+pub fn is_test_session(session: &Session) -> bool {
+    session
+        .data
+        .get("user")
+        .and_then(|user| user.as_str())
+        .is_some_and(|user| TEST_PREFIXES.iter().any(|prefix| user.starts_with(prefix)))
+}
+
+/// Delete the sessions GET /sessions lists, printing each one removed.
+/// Returns the exit code: 1 if the sessions could not be listed or any
+/// delete failed.
+pub async fn run(client: &OrchestratorClient, settings: &CleanupSettings) -> i32 {
+    let listed = match client.list_sessions(None, None).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            outln!("{} GET /sessions failed: {e}", "✗".red());
+            return 1;
+        }
+    };
+    let total = listed.len();
+    let sessions: Vec<Session> = listed
+        .into_iter()
+        .filter(|session| !settings.only_test_sessions || is_test_session(session))
+        .collect();
+    if sessions.is_empty() {
+        outln!("🧹 No sessions to delete ({total} listed)");
+        return 0;
+    }
+
+    if settings.dry_run {
+        outln!(
+            "{}",
+            format!("🧹 Would delete {} of {total} session(s)", sessions.len()).bold()
+        );
+        for session in &sessions {
+            outln!("  - {} {}", session.id, session.data);
+        }
+        return 0;
+    }
+
+    outln!(
+        "{}",
+        format!("🧹 Deleting {} of {total} session(s)", sessions.len()).bold()
+    );
+    let mut deletes = stream::iter(&sessions)
+        .map(|session| async move { (session, client.delete_session(&session.id).await) })
+        .buffer_unordered(settings.concurrency.max(1));
+    let mut removed = 0;
+    let mut failures = Vec::new();
+    while let Some((session, result)) = deletes.next().await {
+        match result {
+            Ok(status) if status.is_success() => {
+                removed += 1;
+                outln!("  {} {}", "✓".green(), session.id);
+            }
+            // Expired or deleted since it was listed.
+            Ok(status) if status == reqwest::StatusCode::NOT_FOUND => {
+                outln!("{}", format!("  - {} (already gone)", session.id).dimmed());
+            }
+            Ok(status) => failures.push(format!("{}: HTTP {status}", session.id)),
+            Err(e) => failures.push(format!("{}: {e}", session.id)),
+        }
+    }
+
+    outln!("Removed {removed} of {} session(s)", sessions.len());
+    if failures.is_empty() {
+        return 0;
+    }
+    outln!("{}", format!("{} delete(s) failed:", failures.len()).red());
+    for failure in &failures {
+        outln!("  {} {failure}", "✗".red());
+    }
+    1
+}
@@ -1,123 +1,1227 @@
-use reqwest::{Client, StatusCode};
+use chrono::{DateTime, Utc};
+use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::{Certificate, Client, Method, RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::time::{Duration, Instant};
+
+use crate::artifacts::{self, Exchange, RecordedRequest, RecordedResponse};
+use crate::context;
+use crate::exposition::Exposition;
+use crate::retry::{self, RetryPolicy};
+use crate::stats::{self, Reply, Stats};
+use crate::tests::util;
+use crate::tracker::SessionTracker;
 
 /// Response from POST /sessions and GET /sessions/:id
 #[derive(Debug, Deserialize, Serialize)]
+#[non_exhaustive]
 pub struct Session {
     pub id: String,
     pub created_at: serde_json::Value,
     pub data: serde_json::Value,
 }
 
-/// Typed client for the orchestrator HTTP API.
+/// Encoding of a `created_at` value, as detected by `Session::created_time`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampFormat {
+    /// A string such as "2024-05-01T12:00:00Z".
+    Rfc3339,
+    /// Milliseconds since the Unix epoch, as a number or a numeric string.
+    EpochMillis,
+}
+
+impl fmt::Display for TimestampFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimestampFormat::Rfc3339 => write!(f, "RFC 3339"),
+            TimestampFormat::EpochMillis => write!(f, "epoch milliseconds"),
+        }
+    }
+}
+
+impl Session {
+    /// `created_at` as a point in time with the format it was sent in, or None
+    /// if it is neither RFC 3339 nor epoch milliseconds. The raw value stays in
+    /// `created_at`.
+    pub fn created_time(&self) -> Option<(DateTime<Utc>, TimestampFormat)> {
+        let millis = match &self.created_at {
+            serde_json::Value::String(text) => {
+                if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+                    return Some((time.with_timezone(&Utc), TimestampFormat::Rfc3339));
+                }
+                text.parse::<i64>().ok()?
+            }
+            serde_json::Value::Number(n) => n.as_i64()?,
+            _ => return None,
+        };
+        let time = DateTime::from_timestamp_millis(millis)?;
+        Some((time, TimestampFormat::EpochMillis))
+    }
+}
+
+/// Body of GET /health when the orchestrator answers with JSON. Unknown
+/// fields are ignored and missing ones left as None.
+#[derive(Debug, Default, Deserialize)]
+#[non_exhaustive]
+pub struct HealthStatus {
+    pub status: Option<String>,
+    pub workers_total: Option<u64>,
+    pub workers_available: Option<u64>,
+}
+
+/// Response from GET /status: pool state, including which worker holds
+/// which session. Only the fields tests use are decoded.
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct PoolStatus {
+    /// Ceiling the pool may scale up to.
+    pub max_workers: usize,
+    pub workers: Vec<WorkerStatus>,
+}
+
+#[derive(Debug, Deserialize)]
+#[non_exhaustive]
+pub struct WorkerStatus {
+    pub id: u64,
+    /// Empty when the worker is idle.
+    pub session_id: String,
+}
+
+impl PoolStatus {
+    /// ID of the worker holding `session_id`, if any.
+    pub fn worker_for(&self, session_id: &str) -> Option<u64> {
+        self.workers
+            .iter()
+            .find(|w| w.session_id == session_id)
+            .map(|w| w.id)
+    }
+}
+
+/// Response from GET /sessions: either a bare array or an object wrapping one.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum SessionList {
+    Bare(Vec<Session>),
+    Wrapped {
+        #[serde(alias = "sessions")]
+        items: Vec<Session>,
+    },
+}
+
+/// Status, headers, and body of a response, kept verbatim for negative tests
+/// and failure messages.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct RawResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+    /// The request ID the client sent; see `ClientConfig::request_id_header`.
+    pub request_id: Option<String>,
+}
+
+impl RawResponse {
+    /// The wait its `Retry-After` header asks for.
+    pub fn retry_after(&self) -> Option<Duration> {
+        retry::retry_after(&self.headers)
+    }
+}
+
+impl fmt::Display for RawResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {}: {}", self.status, self.body)?;
+        write_request_id(f, &self.request_id)
+    }
+}
+
+/// A typed call's result with how long it took: until the response headers
+/// arrived, its time to first byte, and until the body was decoded.
+#[derive(Debug)]
+#[non_exhaustive]
+pub struct Timed<T> {
+    pub value: T,
+    pub first_byte: Duration,
+    pub total: Duration,
+}
+
+/// What the orchestrator did with a create whose body stopped halfway.
+#[derive(Debug)]
+pub enum Stalled {
+    /// It answered with `status` after waiting this long.
+    Answered { status: StatusCode, after: Duration },
+    /// It closed the connection without answering after this long.
+    Closed { after: Duration },
+    /// It did neither within the wait.
+    Held,
+}
+
+/// How `get_session_raw` puts a session ID into the request path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IdEncoding {
+    /// Percent-encoded as a single path segment.
+    Encoded,
+    /// Inserted as-is, so `../` and `%2e` reach the orchestrator untouched
+    /// instead of being resolved by the URL parser. Plain HTTP only; redirects
+    /// are not followed and the exchange is not recorded in `--artifacts`.
+    Verbatim,
+}
+
+/// Errors returned by `OrchestratorClient`. Those from a sent request carry
+/// the request ID the client put on it, which their message ends with, to
+/// find the request in the orchestrator's logs.
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ClientError {
+    /// The orchestrator answered 404 Not Found.
+    NotFound { request_id: Option<String> },
+    /// The orchestrator answered with any other non-success status.
+    Http {
+        status: StatusCode,
+        body: String,
+        request_id: Option<String>,
+        /// The wait its `Retry-After` header asked for, e.g. on a 429.
+        retry_after: Option<Duration>,
+    },
+    /// The request never got a response (connection refused, timeout, ...).
+    Transport {
+        source: reqwest::Error,
+        request_id: Option<String>,
+    },
+    /// The response body could not be read or parsed.
+    Decode {
+        message: String,
+        request_id: Option<String>,
+    },
+    /// A request sent around reqwest, with `IdEncoding::Verbatim` or by
+    /// `create_session_stalled`, got no response.
+    Verbatim(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::NotFound { .. } => write!(f, "404 Not Found"),
+            ClientError::Http { status, body, .. } => write!(f, "HTTP {status}: {body}"),
+            ClientError::Transport { source, .. } => write!(f, "request failed: {source}"),
+            ClientError::Decode { message, .. } => {
+                write!(f, "failed to decode response: {message}")
+            }
+            ClientError::Verbatim(msg) => write!(f, "verbatim request failed: {msg}"),
+        }?;
+        write_request_id(f, &self.request_id().map(str::to_string))
+    }
+}
+
+/// End an error message with the request ID to grep the server logs for.
+fn write_request_id(f: &mut fmt::Formatter<'_>, request_id: &Option<String>) -> fmt::Result {
+    match request_id {
+        Some(id) => write!(f, " [request id {id}]"),
+        None => Ok(()),
+    }
+}
+
+impl ClientError {
+    /// The ID of the request that failed, if it was sent with one.
+    pub fn request_id(&self) -> Option<&str> {
+        match self {
+            ClientError::NotFound { request_id }
+            | ClientError::Http { request_id, .. }
+            | ClientError::Transport { request_id, .. }
+            | ClientError::Decode { request_id, .. } => request_id.as_deref(),
+            ClientError::Verbatim(_) => None,
+        }
+    }
+
+    /// The wait an error response's `Retry-After` header asked for.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            ClientError::Http { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// A decoding failure of the response to request `request_id`.
+    fn decode(message: impl fmt::Display, request_id: Option<String>) -> Self {
+        ClientError::Decode {
+            message: message.to_string(),
+            request_id,
+        }
+    }
+
+    /// The connection failed during the TLS handshake, e.g. on an untrusted certificate.
+    pub fn is_tls(&self) -> bool {
+        self.transport_causes().any(|cause| {
+            let msg = cause.to_string().to_lowercase();
+            msg.contains("certificate") || msg.contains("tls") || msg.contains("ssl")
+        })
+    }
+
+    /// Nothing was listening at the target address.
+    pub fn is_connection_refused(&self) -> bool {
+        self.transport_causes().any(|cause| {
+            cause
+                .downcast_ref::<std::io::Error>()
+                .is_some_and(|e| e.kind() == std::io::ErrorKind::ConnectionRefused)
+        })
+    }
+
+    /// The chain of underlying errors of a transport failure. reqwest does not
+    /// expose the TLS backend's error types, so callers inspect the chain.
+    fn transport_causes(&self) -> impl Iterator<Item = &(dyn std::error::Error + 'static)> {
+        let first = match self {
+            ClientError::Transport { source, .. } => std::error::Error::source(source),
+            _ => None,
+        };
+        std::iter::successors(first, |cause| cause.source())
+    }
+}
+
+impl std::error::Error for ClientError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ClientError::Transport { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<reqwest::Error> for ClientError {
+    fn from(e: reqwest::Error) -> Self {
+        ClientError::Transport {
+            source: e,
+            request_id: None,
+        }
+    }
+}
+
+/// Lets test cases, which report failures as strings, use `?` on client calls.
+impl From<ClientError> for String {
+    fn from(e: ClientError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Percent-encode everything but RFC 3986 unreserved characters, so `id`
+/// stays a single path segment.
+fn encode_segment(id: &str) -> String {
+    id.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{b:02X}"),
+        })
+        .collect()
+}
+
+/// The ID `send` put on the request this is the response to.
+#[derive(Clone)]
+struct RequestId(String);
+
+fn request_id(resp: &Response) -> Option<String> {
+    resp.extensions().get::<RequestId>().map(|id| id.0.clone())
+}
+
+fn new_request_id() -> String {
+    uuid::Uuid::new_v4().to_string()
+}
+
+/// Map 404 to `NotFound` and any other non-success status to `Http`.
+async fn check_status(resp: Response) -> Result<Response, ClientError> {
+    let status = resp.status();
+    let request_id = request_id(&resp);
+    if status == StatusCode::NOT_FOUND {
+        return Err(ClientError::NotFound { request_id });
+    }
+    if !status.is_success() {
+        let retry_after = retry::retry_after(resp.headers());
+        let body = resp.text().await.unwrap_or_default();
+        return Err(ClientError::Http {
+            status,
+            body,
+            request_id,
+            retry_after,
+        });
+    }
+    Ok(resp)
+}
+
+/// `check_status`, then decode the body as JSON.
+/// Read `resp`'s body, returning it with a response that hands it out again.
+async fn buffered(resp: Response) -> reqwest::Result<(Response, hyper::body::Bytes)> {
+    let status = resp.status();
+    let version = resp.version();
+    let headers = resp.headers().clone();
+    let body = resp.bytes().await?;
+    let mut rebuilt = http::Response::new(body.clone());
+    *rebuilt.status_mut() = status;
+    *rebuilt.version_mut() = version;
+    *rebuilt.headers_mut() = headers;
+    Ok((Response::from(rebuilt), body))
+}
+
+async fn json<T: DeserializeOwned>(resp: Response) -> Result<T, ClientError> {
+    let resp = check_status(resp).await?;
+    let request_id = request_id(&resp);
+    resp.json::<T>()
+        .await
+        .map_err(|e| ClientError::decode(e, request_id))
+}
+
+/// The body as text, whatever the status.
+async fn text(resp: Response) -> Result<String, ClientError> {
+    let request_id = request_id(&resp);
+    resp.text()
+        .await
+        .map_err(|e| ClientError::decode(e, request_id))
+}
+
+/// HTTP settings for `OrchestratorClient`.
+#[derive(Clone)]
+pub struct ClientConfig {
+    /// Limit on a whole request, from connecting to reading the body.
+    pub request_timeout: Duration,
+    /// Limit on establishing the TCP connection; None leaves it to the OS.
+    pub connect_timeout: Option<Duration>,
+    /// Sent as `Authorization: Bearer <token>` on every request.
+    pub token: Option<String>,
+    /// Extra root certificates to trust, e.g. an internal CA.
+    pub ca_certs: Vec<Certificate>,
+    /// Skip TLS certificate verification entirely.
+    pub insecure: bool,
+    /// Header carrying a fresh UUID on every request, for correlating it
+    /// with the orchestrator's logs.
+    pub request_id_header: HeaderName,
+    /// Retries of repeatable requests that fail transiently; off by default.
+    pub retry: RetryPolicy,
+}
+
+impl Default for ClientConfig {
+    fn default() -> Self {
+        Self {
+            request_timeout: Duration::from_secs(300),
+            connect_timeout: None,
+            token: None,
+            ca_certs: Vec::new(),
+            insecure: false,
+            request_id_header: HeaderName::from_static("x-request-id"),
+            retry: RetryPolicy::default(),
+        }
+    }
+}
+
+/// Boxed future returned by `ApiClient` methods.
+pub type ApiFuture<'a, T> = Pin<Box<dyn Future<Output = Result<T, ClientError>> + Send + 'a>>;
+
+/// The session API calls the runner needs, implemented by `OrchestratorClient`.
+/// Implement it on a scripted fake to exercise `TestRunner` without a live
+/// orchestrator.
+pub trait ApiClient: Send + Sync {
+    fn base_url(&self) -> &str;
+    fn create_session(&self, data: serde_json::Value) -> ApiFuture<'_, Session>;
+    fn get_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, Session>;
+    /// A missing session is `Ok(404)`, not an error.
+    fn delete_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, StatusCode>;
+    fn health(&self) -> ApiFuture<'_, String>;
+    fn crash_worker<'a>(&'a self, session_id: &'a str) -> ApiFuture<'a, ()>;
+    /// Sessions created through this client and not deleted since, as
+    /// (session ID, creating test) pairs. The runner sweeps them after a run.
+    fn outstanding_sessions(&self) -> Vec<(String, String)>;
+    /// Requests this client sent again after a transient failure; see
+    /// `ClientConfig::retry`. Clients that never retry keep the default.
+    fn retried_requests(&self) -> u64 {
+        0
+    }
+}
+
+/// Typed client for the orchestrator HTTP API. Clones are cheap and share the
+/// connection pool, session tracker and stats, so tasks can each hold their
+/// own.
+#[derive(Clone)]
 pub struct OrchestratorClient {
     base_url: String,
     http: Client,
+    token: Option<String>,
+    /// Configured limit on a whole request, applied by reqwest itself.
+    default_timeout: Duration,
+    /// Overrides the configured request timeout, see `with_request_timeout`.
+    request_timeout: Option<Duration>,
+    tracker: SessionTracker,
+    request_id_header: HeaderName,
+    retry: RetryPolicy,
+    stats: Stats,
 }
 
+// Concurrent tests move clones into spawned tasks.
+const _: fn() = || {
+    fn assert_send_sync<T: Send + Sync + Clone + 'static>() {}
+    assert_send_sync::<OrchestratorClient>();
+};
+
 impl OrchestratorClient {
+    /// A client with the default configuration.
     pub fn new(base_url: &str) -> Self {
+        Self::with_config(base_url, &ClientConfig::default())
+    }
+
+    pub fn with_config(base_url: &str, config: &ClientConfig) -> Self {
+        let mut http = Client::builder().timeout(config.request_timeout);
+        if let Some(connect_timeout) = config.connect_timeout {
+            http = http.connect_timeout(connect_timeout);
+        }
+        for cert in &config.ca_certs {
+            http = http.add_root_certificate(cert.clone());
+        }
+        if config.insecure {
+            http = http.danger_accept_invalid_certs(true);
+        }
         Self {
             base_url: base_url.trim_end_matches('/').to_string(),
-            http: Client::builder()
-                .timeout(std::time::Duration::from_secs(300))
-                .build()
-                .expect("failed to build HTTP client"),
+            http: http.build().expect("failed to build HTTP client"),
+            token: config.token.clone(),
+            default_timeout: config.request_timeout,
+            request_timeout: None,
+            tracker: SessionTracker::default(),
+            request_id_header: config.request_id_header.clone(),
+            retry: config.retry.clone(),
+            stats: Stats::default(),
         }
     }
 
-    /// POST /sessions — create a new session with arbitrary JSON data.
-    pub async fn create_session(&self, data: serde_json::Value) -> Result<Session, String> {
-        let resp = self
+    /// Start a request to `path` on the orchestrator, tagged with a fresh
+    /// request ID and authenticated if a token is configured. Used by the
+    /// typed methods and by tests that need a raw response.
+    pub fn request(&self, method: Method, path: &str) -> RequestBuilder {
+        let mut req = self
             .http
-            .post(format!("{}/sessions", self.base_url))
-            .json(&data)
-            .send()
-            .await
-            .map_err(|e| format!("POST /sessions request failed: {e}"))?;
+            .request(method, format!("{}{path}", self.base_url))
+            .header(&self.request_id_header, new_request_id());
+        if let Some(timeout) = self.request_timeout {
+            req = req.timeout(timeout);
+        }
+        match &self.token {
+            Some(token) => req.bearer_auth(token),
+            None => req,
+        }
+    }
 
+    /// Send a request built with `request`, logging it at DEBUG and retrying
+    /// it per `ClientConfig::retry`. While a test is being recorded
+    /// (`--artifacts`), every attempt is appended to its request log.
+    pub async fn send(&self, req: RequestBuilder) -> Result<Response, ClientError> {
+        let request = req.build()?;
+        let method = request.method().clone();
+        let path = request.url().path().to_string();
+        let request_id = request
+            .headers()
+            .get(&self.request_id_header)
+            .and_then(|id| id.to_str().ok())
+            .map(str::to_string);
+        let start = Instant::now();
+        let result = match self.execute_with_retries(request).await {
+            Ok(mut resp) => {
+                if let Some(id) = &request_id {
+                    resp.extensions_mut().insert(RequestId(id.clone()));
+                }
+                Ok(resp)
+            }
+            Err(ClientError::Transport { source, .. }) => Err(ClientError::Transport {
+                source,
+                request_id: request_id.clone(),
+            }),
+            Err(e) => Err(e),
+        };
+        let id = request_id.as_deref().unwrap_or_default();
+        match &result {
+            Ok(resp) => tracing::debug!(
+                %method,
+                %path,
+                request_id = id,
+                status = resp.status().as_u16(),
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                "request"
+            ),
+            Err(e) => tracing::debug!(
+                %method,
+                %path,
+                request_id = id,
+                elapsed_ms = start.elapsed().as_millis() as u64,
+                error = %e,
+                "request failed"
+            ),
+        }
+        result
+    }
+
+    /// `execute`, sending a repeatable request again while it fails
+    /// transiently and retries are left, after a jittered backoff.
+    async fn execute_with_retries(
+        &self,
+        mut request: reqwest::Request,
+    ) -> Result<Response, ClientError> {
+        let repeatable = retry::is_repeatable(
+            request.method(),
+            request.url().path(),
+            request.headers().contains_key("Idempotency-Key"),
+        );
+        let mut attempt = 0;
+        loop {
+            let again = (repeatable && attempt < self.retry.max_retries)
+                .then(|| request.try_clone())
+                .flatten();
+            let Some(again) = again else {
+                return self.execute(request).await;
+            };
+            let failure = match self.execute(request).await {
+                Ok(resp) if retry::is_transient_status(resp.status()) => {
+                    format!("HTTP {}", resp.status())
+                }
+                Err(ClientError::Transport { source, .. })
+                    if retry::is_transient_error(&source) =>
+                {
+                    source.to_string()
+                }
+                result => return result,
+            };
+            let jitter = util::Rng::new(util::random_seed()).unit();
+            let delay = self.retry.delay(attempt, jitter);
+            attempt += 1;
+            self.stats
+                .record_retry(&stats::endpoint(again.method(), again.url().path()));
+            tracing::info!(
+                method = %again.method(),
+                path = again.url().path(),
+                retry = attempt,
+                delay_ms = delay.as_millis() as u64,
+                %failure,
+                "retrying request"
+            );
+            tokio::time::sleep(delay).await;
+            request = again;
+        }
+    }
+
+    async fn execute(&self, request: reqwest::Request) -> Result<Response, ClientError> {
+        let Some(log) = context::request_log() else {
+            return Ok(self.attempt(request).await?);
+        };
+
+        let recorded = RecordedRequest {
+            method: request.method().to_string(),
+            url: request.url().to_string(),
+            headers: artifacts::headers_json(request.headers()),
+            body: artifacts::body_json(
+                request
+                    .body()
+                    .and_then(|b| b.as_bytes())
+                    .unwrap_or_default(),
+            ),
+        };
+
+        let start = Instant::now();
+        let result = self.attempt(request).await;
+        let resp = match result {
+            Ok(resp) => resp,
+            Err(e) => {
+                log.push(Exchange {
+                    request: recorded,
+                    response: None,
+                    error: Some(e.to_string()),
+                    elapsed_ms: start.elapsed().as_millis() as u64,
+                });
+                return Err(e.into());
+            }
+        };
+
+        // `attempt` buffered the body, so it can be both recorded and handed
+        // back.
+        let (resp, body) = buffered(resp).await?;
+        log.push(Exchange {
+            request: recorded,
+            response: Some(RecordedResponse {
+                status: resp.status().as_u16(),
+                headers: artifacts::headers_json(resp.headers()),
+                body: artifacts::body_json(&body),
+            }),
+            error: None,
+            elapsed_ms: start.elapsed().as_millis() as u64,
+        });
+        Ok(resp)
+    }
+
+    /// One exchange with the orchestrator, counted in `stats` under its
+    /// endpoint. The body is read here, so the bytes received are those that
+    /// arrived, chunked or not, and a body that fails to arrive fails the
+    /// exchange.
+    async fn attempt(&self, request: reqwest::Request) -> reqwest::Result<Response> {
+        let endpoint = stats::endpoint(request.method(), request.url().path());
+        let sent = request
+            .body()
+            .and_then(|b| b.as_bytes())
+            .map_or(0, |b| b.len() as u64);
+        let start = Instant::now();
+        let resp = match self.http.execute(request).await {
+            Ok(resp) => resp,
+            Err(e) => {
+                let reply = Reply::failed(&e);
+                self.stats
+                    .record(&endpoint, reply, start.elapsed(), sent, 0);
+                return Err(e);
+            }
+        };
+        let latency = start.elapsed();
         let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("POST /sessions returned {status}: {body}"));
+        match buffered(resp).await {
+            Ok((resp, body)) => {
+                let received = body.len() as u64;
+                self.stats
+                    .record(&endpoint, Reply::Status(status), latency, sent, received);
+                Ok(resp)
+            }
+            Err(e) => {
+                self.stats
+                    .record(&endpoint, Reply::failed(&e), latency, sent, 0);
+                Err(e)
+            }
         }
+    }
+
+    /// Send a request built with `request` and return the response as-is,
+    /// whatever its status.
+    pub async fn send_raw(&self, req: RequestBuilder) -> Result<RawResponse, ClientError> {
+        let resp = self.send(req).await?;
+        let status = resp.status();
+        let headers = resp.headers().clone();
+        let request_id = request_id(&resp);
+        let body = text(resp).await?;
+        Ok(RawResponse {
+            status,
+            headers,
+            body,
+            request_id,
+        })
+    }
 
-        resp.json::<Session>()
+    /// Send `method` to `path`, with `body` as JSON if given, and return the
+    /// response whatever its status. The escape hatch for protocol tests that
+    /// use methods the typed calls don't.
+    pub async fn request_raw(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<RawResponse, ClientError> {
+        self.request_raw_with(method, path, body, HeaderMap::new())
             .await
-            .map_err(|e| format!("failed to parse session response: {e}"))
+    }
+
+    /// `request_raw` with `headers` replacing any the client would send,
+    /// e.g. an `Authorization` carrying a bad token.
+    pub async fn request_raw_with(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+        headers: HeaderMap,
+    ) -> Result<RawResponse, ClientError> {
+        let mut req = self.request(method, path);
+        if let Some(body) = body {
+            req = req.json(body);
+        }
+        self.send_raw(req.headers(headers)).await
+    }
+
+    /// A copy of this client that sends no `Authorization` header.
+    pub fn without_token(&self) -> Self {
+        Self {
+            token: None,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this client that counts its exchanges in `stats`, e.g. the
+    /// runner's, instead of its own.
+    pub fn with_stats(&self, stats: Stats) -> Self {
+        Self {
+            stats,
+            ..self.clone()
+        }
+    }
+
+    /// A copy of this client whose requests may take up to `timeout`, e.g. for
+    /// large payloads.
+    pub fn with_request_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            request_timeout: Some(timeout),
+            ..self.clone()
+        }
+    }
+
+    /// POST /sessions — create a new session with arbitrary JSON data.
+    pub async fn create_session(&self, data: serde_json::Value) -> Result<Session, ClientError> {
+        self.create_session_with(data, None).await
+    }
+
+    /// POST /sessions, sending `idempotency_key` as an `Idempotency-Key`
+    /// header when given, so a retried create can be recognised as one.
+    pub async fn create_session_with(
+        &self,
+        data: serde_json::Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<Session, ClientError> {
+        Ok(self.create(data, idempotency_key).await?.value)
+    }
+
+    /// `create_session`, returning how long the create took along with the
+    /// session, e.g. to tell a cold worker start from a warm one.
+    pub async fn create_session_timed(
+        &self,
+        data: serde_json::Value,
+    ) -> Result<Timed<Session>, ClientError> {
+        self.create(data, None).await
+    }
+
+    async fn create(
+        &self,
+        data: serde_json::Value,
+        idempotency_key: Option<&str>,
+    ) -> Result<Timed<Session>, ClientError> {
+        let mut req = self.request(Method::POST, "/sessions").json(&data);
+        if let Some(key) = idempotency_key {
+            req = req.header("Idempotency-Key", key);
+        }
+        let start = Instant::now();
+        let resp = self.send(req).await?;
+        let first_byte = start.elapsed();
+
+        let session = json::<Session>(resp).await?;
+        let total = start.elapsed();
+
+        let test = context::current_test().unwrap_or_else(|| "<unknown test>".to_string());
+        self.tracker.record(&session.id, &test);
+        Ok(Timed {
+            value: session,
+            first_byte,
+            total,
+        })
+    }
+
+    /// POST /sessions with an arbitrary body and content type, for negative
+    /// tests. Returns the raw response; a session created anyway is tracked
+    /// like any other.
+    pub async fn create_session_raw(
+        &self,
+        body: &[u8],
+        content_type: &str,
+    ) -> Result<RawResponse, ClientError> {
+        let req = self
+            .request(Method::POST, "/sessions")
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .body(body.to_vec());
+        self.send_create_raw(req).await
+    }
+
+    /// POST /sessions with `data` as gzip-compressed JSON, sent with
+    /// `Content-Encoding: gzip`. Returns the raw response; a session created
+    /// is tracked like any other.
+    pub async fn create_session_gzip(
+        &self,
+        data: &serde_json::Value,
+    ) -> Result<RawResponse, ClientError> {
+        let body = util::gzip(data.to_string().as_bytes());
+        let req = self
+            .request(Method::POST, "/sessions")
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(reqwest::header::CONTENT_ENCODING, "gzip")
+            .body(body);
+        self.send_create_raw(req).await
+    }
+
+    /// Send a create built by a raw helper, tracking the session if one was
+    /// made.
+    async fn send_create_raw(&self, req: RequestBuilder) -> Result<RawResponse, ClientError> {
+        let resp = self.send_raw(req).await?;
+        self.track_created(&resp);
+        Ok(resp)
+    }
+
+    /// Track the session a raw create's response describes, if any.
+    fn track_created(&self, resp: &RawResponse) {
+        if resp.status.is_success() {
+            if let Ok(session) = serde_json::from_str::<Session>(&resp.body) {
+                let test = context::current_test().unwrap_or_else(|| "<unknown test>".to_string());
+                self.tracker.record(&session.id, &test);
+            }
+        }
     }
 
     /// GET /sessions/:id — retrieve a session by ID.
-    pub async fn get_session(&self, id: &str) -> Result<Session, String> {
+    pub async fn get_session(&self, id: &str) -> Result<Session, ClientError> {
         let resp = self
-            .http
-            .get(format!("{}/sessions/{}", self.base_url, id))
-            .send()
-            .await
-            .map_err(|e| format!("GET /sessions/{id} request failed: {e}"))?;
+            .send(self.request(Method::GET, &format!("/sessions/{id}")))
+            .await?;
 
-        let status = resp.status();
-        if status == StatusCode::NOT_FOUND {
-            return Err("404".to_string());
+        json(resp).await
+    }
+
+    /// GET /sessions/:id returning the response whatever its status, for
+    /// IDs that are hostile to URL paths.
+    pub async fn get_session_raw(
+        &self,
+        id: &str,
+        encoding: IdEncoding,
+    ) -> Result<RawResponse, ClientError> {
+        match encoding {
+            IdEncoding::Encoded => {
+                let path = format!("/sessions/{}", encode_segment(id));
+                self.send_raw(self.request(Method::GET, &path)).await
+            }
+            IdEncoding::Verbatim => {
+                self.request_verbatim(Method::GET, &format!("/sessions/{id}"), None)
+                    .await
+            }
+        }
+    }
+
+    /// Send `method` to `path` exactly as written, with `body` as JSON if
+    /// given, and return the response whatever its status. reqwest resolves
+    /// dot segments before sending, so this goes through hyper directly; it
+    /// is for paths a proxy might pass through unnormalized. A session
+    /// created is tracked like any other.
+    pub async fn request_verbatim(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<&serde_json::Value>,
+    ) -> Result<RawResponse, ClientError> {
+        if !self.base_url.starts_with("http://") {
+            return Err(ClientError::Verbatim(
+                "verbatim paths need a plain http:// --url".to_string(),
+            ));
+        }
+        let request_id = new_request_id();
+        let mut req = hyper::Request::builder()
+            .method(method.clone())
+            .uri(format!("{}{path}", self.base_url))
+            .header(&self.request_id_header, &request_id);
+        if let Some(token) = &self.token {
+            req = req.header(reqwest::header::AUTHORIZATION, format!("Bearer {token}"));
         }
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("GET /sessions/{id} returned {status}: {body}"));
+        let req = match body {
+            Some(body) => req
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(hyper::Body::from(body.to_string())),
+            None => req.body(hyper::Body::empty()),
         }
+        .map_err(|e| ClientError::Verbatim(format!("invalid request for {path:?}: {e}")))?;
 
-        resp.json::<Session>()
+        let timeout = self.request_timeout.unwrap_or(self.default_timeout);
+        let exchange = async {
+            let resp = hyper::Client::new().request(req).await?;
+            let status = resp.status();
+            let headers = resp.headers().clone();
+            let body = hyper::body::to_bytes(resp.into_body()).await?;
+            Ok::<_, hyper::Error>((status, headers, body))
+        };
+        let start = Instant::now();
+        let (status, headers, body) = tokio::time::timeout(timeout, exchange)
             .await
-            .map_err(|e| format!("failed to parse session response: {e}"))
+            .map_err(|_| ClientError::Verbatim(format!("timed out after {}s", timeout.as_secs())))?
+            .map_err(|e| ClientError::Verbatim(e.to_string()))?;
+        tracing::debug!(
+            %method,
+            path,
+            request_id,
+            status = status.as_u16(),
+            elapsed_ms = start.elapsed().as_millis() as u64,
+            "request (verbatim)"
+        );
+        let resp = RawResponse {
+            status,
+            headers,
+            body: String::from_utf8_lossy(&body).into_owned(),
+            request_id: Some(request_id),
+        };
+        if method == Method::POST {
+            self.track_created(&resp);
+        }
+        Ok(resp)
+    }
+
+    /// POST /sessions over a bare TCP connection, announcing all of `body`
+    /// in Content-Length but sending only its first half, then waiting up to
+    /// `wait` for the orchestrator to answer or hang up. reqwest can't stop
+    /// halfway through a body. Plain HTTP only, and not recorded in
+    /// `--artifacts`.
+    pub async fn create_session_stalled(
+        &self,
+        body: &[u8],
+        wait: Duration,
+    ) -> Result<Stalled, ClientError> {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let url = reqwest::Url::parse(&self.base_url)
+            .ok()
+            .filter(|url| url.scheme() == "http")
+            .ok_or_else(|| {
+                ClientError::Verbatim("a stalled request needs a plain http:// --url".to_string())
+            })?;
+        let host = url.host_str().unwrap_or_default();
+        let port = url.port_or_known_default().unwrap_or(80);
+        let request_id = new_request_id();
+        let mut head = format!(
+            "POST {}/sessions HTTP/1.1\r\nHost: {host}:{port}\r\n\
+             Content-Type: application/json\r\nContent-Length: {}\r\n{}: {request_id}\r\n",
+            url.path().trim_end_matches('/'),
+            body.len(),
+            self.request_id_header
+        );
+        if let Some(token) = &self.token {
+            head.push_str(&format!("Authorization: Bearer {token}\r\n"));
+        }
+        head.push_str("\r\n");
+
+        let failed = |e: std::io::Error| ClientError::Verbatim(format!("stalled create: {e}"));
+        let mut stream = tokio::net::TcpStream::connect((host, port))
+            .await
+            .map_err(failed)?;
+        stream.write_all(head.as_bytes()).await.map_err(failed)?;
+        stream
+            .write_all(&body[..body.len() / 2])
+            .await
+            .map_err(failed)?;
+        tracing::debug!(request_id, "stalled create sent");
+
+        let start = Instant::now();
+        let mut reply = [0; 64];
+        let outcome = match tokio::time::timeout(wait, stream.read(&mut reply)).await {
+            Err(_) => Stalled::Held,
+            Ok(Ok(0)) | Ok(Err(_)) => Stalled::Closed {
+                after: start.elapsed(),
+            },
+            Ok(Ok(n)) => {
+                // "HTTP/1.1 408 Request Timeout": the code is the second word.
+                let line = String::from_utf8_lossy(&reply[..n]);
+                let status = line
+                    .split_whitespace()
+                    .nth(1)
+                    .and_then(|code| code.parse::<u16>().ok())
+                    .and_then(|code| StatusCode::from_u16(code).ok())
+                    .ok_or_else(|| {
+                        ClientError::Verbatim(format!(
+                            "stalled create: not an HTTP reply: {line:?}"
+                        ))
+                    })?;
+                Stalled::Answered {
+                    status,
+                    after: start.elapsed(),
+                }
+            }
+        };
+        Ok(outcome)
+    }
+
+    /// GET /sessions — list sessions, optionally paginated with `limit`/`offset`.
+    pub async fn list_sessions(
+        &self,
+        limit: Option<usize>,
+        offset: Option<usize>,
+    ) -> Result<Vec<Session>, ClientError> {
+        let mut query = Vec::new();
+        if let Some(limit) = limit {
+            query.push(("limit", limit));
+        }
+        if let Some(offset) = offset {
+            query.push(("offset", offset));
+        }
+        let resp = self
+            .send(self.request(Method::GET, "/sessions").query(&query))
+            .await?;
+
+        let list = json::<SessionList>(resp).await?;
+        Ok(match list {
+            SessionList::Bare(items) | SessionList::Wrapped { items } => items,
+        })
+    }
+
+    /// PUT /sessions/:id — replace a session's data.
+    pub async fn update_session(
+        &self,
+        id: &str,
+        data: serde_json::Value,
+    ) -> Result<Session, ClientError> {
+        let resp = self
+            .send(
+                self.request(Method::PUT, &format!("/sessions/{id}"))
+                    .json(&data),
+            )
+            .await?;
+
+        json(resp).await
     }
 
     /// DELETE /sessions/:id — delete a session. Returns the HTTP status code.
-    pub async fn delete_session(&self, id: &str) -> Result<StatusCode, String> {
+    pub async fn delete_session(&self, id: &str) -> Result<StatusCode, ClientError> {
         let resp = self
-            .http
-            .delete(format!("{}/sessions/{}", self.base_url, id))
-            .send()
-            .await
-            .map_err(|e| format!("DELETE /sessions/{id} request failed: {e}"))?;
+            .send(self.request(Method::DELETE, &format!("/sessions/{id}")))
+            .await?;
 
-        Ok(resp.status())
+        let status = resp.status();
+        if status.is_success() || status == StatusCode::NOT_FOUND {
+            self.tracker.forget(id);
+        }
+        Ok(status)
     }
 
-    /// GET /health — simple health check.
-    pub async fn health(&self) -> Result<String, String> {
+    /// DELETE /sessions/:id returning the response as-is, headers included.
+    pub async fn delete_session_raw(&self, id: &str) -> Result<RawResponse, ClientError> {
         let resp = self
-            .http
-            .get(format!("{}/health", self.base_url))
-            .send()
-            .await
-            .map_err(|e| format!("GET /health request failed: {e}"))?;
+            .send_raw(self.request(Method::DELETE, &format!("/sessions/{id}")))
+            .await?;
+        if resp.status.is_success() || resp.status == StatusCode::NOT_FOUND {
+            self.tracker.forget(id);
+        }
+        Ok(resp)
+    }
 
-        resp.text()
-            .await
-            .map_err(|e| format!("failed to read health response: {e}"))
+    /// GET /health — simple health check.
+    pub async fn health(&self) -> Result<String, ClientError> {
+        let resp = self.send(self.request(Method::GET, "/health")).await?;
+
+        text(resp).await
+    }
+
+    /// GET /health, failing on any non-2xx status. A JSON body is decoded
+    /// into `HealthStatus`; a plain-text one, like "ok", gives None.
+    pub async fn health_detailed(&self) -> Result<Option<HealthStatus>, ClientError> {
+        let resp = self.send(self.request(Method::GET, "/health")).await?;
+        let resp = check_status(resp).await?;
+        let request_id = request_id(&resp);
+        let body = text(resp).await?;
+        match serde_json::from_str::<serde_json::Value>(&body) {
+            Ok(value @ serde_json::Value::Object(_)) => serde_json::from_value(value)
+                .map(Some)
+                .map_err(|e| ClientError::decode(format!("health body {body:?}: {e}"), request_id)),
+            _ => Ok(None),
+        }
+    }
+
+    /// GET /status — pool and worker state (debugging aid).
+    pub async fn status(&self) -> Result<PoolStatus, ClientError> {
+        let resp = self.send(self.request(Method::GET, "/status")).await?;
+
+        json(resp).await
+    }
+
+    /// GET /metrics — Prometheus text exposition, parsed into samples.
+    pub async fn metrics(&self) -> Result<Exposition, ClientError> {
+        let resp = self.send(self.request(Method::GET, "/metrics")).await?;
+        let resp = check_status(resp).await?;
+        let request_id = request_id(&resp);
+        let text = text(resp).await?;
+        Exposition::parse(&text).map_err(|e| ClientError::decode(e, request_id))
     }
 
     /// POST /debug/crash-worker?session_id=:id — kills the worker holding the session (testing only).
-    pub async fn crash_worker(&self, session_id: &str) -> Result<(), String> {
+    pub async fn crash_worker(&self, session_id: &str) -> Result<(), ClientError> {
         let resp = self
-            .http
-            .post(format!(
-                "{}/debug/crash-worker?session_id={}",
-                self.base_url, session_id
+            .send(
+                self.request(Method::POST, "/debug/crash-worker")
+                    .query(&[("session_id", session_id)]),
+            )
+            .await?;
+
+        check_status(resp).await?;
+        Ok(())
+    }
+
+    /// POST /debug/restart — restarts the orchestrator in place (testing only).
+    /// The connection may drop before a response arrives, which shows up as a
+    /// transport error.
+    pub async fn restart(&self) -> Result<(), ClientError> {
+        let resp = self
+            .send(self.request(Method::POST, "/debug/restart"))
+            .await?;
+
+        check_status(resp).await?;
+        Ok(())
+    }
+
+    /// POST /debug/shutdown?graceful=… — stops the orchestrator (testing only),
+    /// letting in-flight requests finish first if `graceful`. As with
+    /// `restart`, the connection may drop before a response arrives.
+    pub async fn shutdown(&self, graceful: bool) -> Result<(), ClientError> {
+        let resp = self
+            .send(self.request(
+                Method::POST,
+                &format!("/debug/shutdown?graceful={graceful}"),
             ))
-            .send()
-            .await
-            .map_err(|e| format!("POST /debug/crash-worker request failed: {e}"))?;
+            .await?;
 
-        let status = resp.status();
-        if !status.is_success() {
-            let body = resp.text().await.unwrap_or_default();
-            return Err(format!("crash-worker returned {status}: {body}"));
-        }
+        check_status(resp).await?;
         Ok(())
     }
 
+    /// Registry of sessions created through this client.
+    pub fn tracker(&self) -> &SessionTracker {
+        &self.tracker
+    }
+
     /// Returns the base URL for building custom requests.
     pub fn base_url(&self) -> &str {
         &self.base_url
     }
+
+    /// Requests sent again after a transient failure, across all clones.
+    pub fn retried_requests(&self) -> u64 {
+        self.stats.retries()
+    }
+
+    /// Per-endpoint counts of every exchange made through this client.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// The header each request's ID is sent in.
+    pub fn request_id_header(&self) -> &HeaderName {
+        &self.request_id_header
+    }
+}
+
+impl ApiClient for OrchestratorClient {
+    fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn create_session(&self, data: serde_json::Value) -> ApiFuture<'_, Session> {
+        Box::pin(OrchestratorClient::create_session(self, data))
+    }
+
+    fn get_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, Session> {
+        Box::pin(OrchestratorClient::get_session(self, id))
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, StatusCode> {
+        Box::pin(OrchestratorClient::delete_session(self, id))
+    }
+
+    fn health(&self) -> ApiFuture<'_, String> {
+        Box::pin(OrchestratorClient::health(self))
+    }
+
+    fn crash_worker<'a>(&'a self, session_id: &'a str) -> ApiFuture<'a, ()> {
+        Box::pin(OrchestratorClient::crash_worker(self, session_id))
+    }
+
+    fn outstanding_sessions(&self) -> Vec<(String, String)> {
+        self.tracker.outstanding()
+    }
+
+    fn retried_requests(&self) -> u64 {
+        OrchestratorClient::retried_requests(self)
+    }
 }
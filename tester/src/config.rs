@@ -0,0 +1,329 @@
+//! `steel-tester.toml` and `STEEL_*` variables: per-environment settings
+//! for the CLI's flags.
+//!
+//! Top-level keys name the global and `run` flags, by field or long name
+//! (`request_timeout`, `skip-group`, ...); `[bench]`, `[chaos]` and
+//! `[cleanup]` tables hold those subcommands' flags. Each value becomes its
+//! flag's default, so the command line and environment variables still win:
+//!
+//! ```toml
+//! url = "https://staging.example.com"
+//! request_timeout = 10
+//! skip_groups = ["Soak", "Chaos"]
+//! html = "reports/staging.html"
+//!
+//! [bench]
+//! iterations = 500
+//! ```
+//!
+//! Every flag can also be set through an environment variable (see
+//! [`with_env`]), which beats the file but not the command line.
+
+use clap::builder::BoolishValueParser;
+use clap::error::{ContextKind, ContextValue, ErrorKind};
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+
+/// Read from the current directory when `--config` isn't given.
+pub const DEFAULT_PATH: &str = "steel-tester.toml";
+
+/// Names the config file when `--config` isn't given.
+pub const CONFIG_ENV: &str = "STEEL_CONFIG";
+
+/// Prefix of the environment variables behind the flags.
+pub const ENV_PREFIX: &str = "STEEL_";
+
+/// Arguments that configure the file itself, or aren't settings at all.
+const NOT_SETTINGS: &[&str] = &["config", "print_config", "help", "version"];
+
+/// Arguments without an environment variable.
+const NO_ENV: &[&str] = &["print_config", "help", "version"];
+
+/// Subcommands whose flags live at the top level of the file with `run`'s.
+const RUN_COMMANDS: &[&str] = &["run", "list"];
+
+/// A parsed config file.
+#[derive(Debug)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    table: toml::Table,
+}
+
+impl ConfigFile {
+    /// The file `--config PATH` names in `args`, else the one `STEEL_CONFIG`
+    /// names, else `DEFAULT_PATH` if it exists. A missing named file is an
+    /// error; a missing default file is not.
+    pub fn locate(args: &[OsString]) -> Result<Option<Self>, String> {
+        let mut args = args.iter().skip(1).map(|arg| arg.to_string_lossy());
+        let mut named = None;
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                break;
+            } else if arg == "--config" {
+                named = args.next().map(|path| PathBuf::from(path.as_ref()));
+            } else if let Some(path) = arg.strip_prefix("--config=") {
+                named = Some(PathBuf::from(path));
+            }
+        }
+        let named = named.or_else(|| std::env::var_os(CONFIG_ENV).map(PathBuf::from));
+        match named {
+            Some(path) => Self::load(&path).map(Some),
+            None if Path::new(DEFAULT_PATH).exists() => {
+                Self::load(Path::new(DEFAULT_PATH)).map(Some)
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        let table = text
+            .parse::<toml::Table>()
+            .map_err(|e| format!("{}: {e}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            table,
+        })
+    }
+
+    /// Make the file's values the defaults of `cmd`'s flags. Returns the
+    /// command and a warning for each key that sets no flag.
+    pub fn apply(&self, mut cmd: Command) -> (Command, Vec<String>) {
+        let mut warnings = Vec::new();
+        for (key, value) in &self.table {
+            if let toml::Value::Table(section) = value {
+                if cmd.find_subcommand(key).is_none() || RUN_COMMANDS.contains(&key.as_str()) {
+                    warnings.push(format!("unknown table [{key}]"));
+                    continue;
+                }
+                for (key_in, value) in section {
+                    let name = format!("{key}.{key_in}");
+                    cmd = cmd.mut_subcommand(key, |sub| {
+                        set_default(sub, key_in, value, &name, &mut warnings)
+                    });
+                }
+                continue;
+            }
+
+            // `run`'s flags are also accepted, deprecated, without a subcommand.
+            cmd = set_default(cmd, key, value, key, &mut warnings);
+            for run in RUN_COMMANDS {
+                if cmd.find_subcommand(run).is_none() {
+                    continue;
+                }
+                cmd = cmd.mut_subcommand(run, |sub| match find(&sub, key) {
+                    Some(_) => set_default(sub, key, value, key, &mut Vec::new()),
+                    None => sub,
+                });
+            }
+        }
+        (cmd, warnings)
+    }
+}
+
+/// Back every flag of `cmd` with an environment variable: `STEEL_<FLAG>` for
+/// the global and `run` flags, `STEEL_<SUBCOMMAND>_<FLAG>` for the other
+/// subcommands' (`STEEL_REQUEST_TIMEOUT`, `STEEL_BENCH_ITERATIONS`). Boolean
+/// flags take 1/0, true/false, yes/no or on/off. Flags that already name a
+/// variable keep it.
+pub fn with_env(cmd: Command) -> Command {
+    let mut cmd = cmd.mut_args(|arg| env_var(arg, ENV_PREFIX));
+    let names: Vec<String> = cmd
+        .get_subcommands()
+        .map(|sub| sub.get_name().to_string())
+        .collect();
+    for name in names {
+        let prefix = if RUN_COMMANDS.contains(&name.as_str()) {
+            ENV_PREFIX.to_string()
+        } else {
+            format!("{ENV_PREFIX}{}_", name.to_uppercase())
+        };
+        cmd = cmd.mut_subcommand(&name, |sub| sub.mut_args(|arg| env_var(arg, &prefix)));
+    }
+    cmd
+}
+
+fn env_var(arg: Arg, prefix: &str) -> Arg {
+    let id = arg.get_id().as_str();
+    if arg.get_env().is_some() || arg.is_positional() || NO_ENV.contains(&id) {
+        return arg;
+    }
+    let name = format!("{prefix}{}", id.to_uppercase());
+    let arg = arg.env(name);
+    if matches!(arg.get_action(), ArgAction::SetTrue) {
+        arg.value_parser(BoolishValueParser::new())
+    } else {
+        arg
+    }
+}
+
+/// Name the variable behind an invalid value, which clap's message leaves
+/// out: a bad `STEEL_TTL` otherwise reads as a bad `--ttl`. `args` is the
+/// command line; a flag given there is left to clap's message.
+pub fn explain_env_error(cmd: &Command, args: &[OsString], e: clap::Error) -> clap::Error {
+    if !matches!(
+        e.kind(),
+        ErrorKind::InvalidValue | ErrorKind::ValueValidation
+    ) {
+        return e;
+    }
+    let (Some(ContextValue::String(flag)), Some(ContextValue::String(value))) = (
+        e.get(ContextKind::InvalidArg),
+        e.get(ContextKind::InvalidValue),
+    ) else {
+        return e;
+    };
+    let long = flag.split(' ').next().unwrap_or_default();
+    let given = args.iter().map(|arg| arg.to_string_lossy()).any(|arg| {
+        arg == long
+            || arg
+                .strip_prefix(long)
+                .is_some_and(|rest| rest.starts_with('='))
+    });
+    if given {
+        return e;
+    }
+    let var = cmd
+        .get_arguments()
+        .chain(cmd.get_subcommands().flat_map(|sub| sub.get_arguments()))
+        .filter(|arg| arg.get_long().is_some_and(|l| format!("--{l}") == long))
+        .filter_map(|arg| arg.get_env())
+        .find(|var| std::env::var_os(var).is_some_and(|v| v.to_string_lossy() == *value));
+    let Some(var) = var else {
+        return e;
+    };
+
+    let reason = match e.get(ContextKind::ValidValue) {
+        Some(ContextValue::Strings(valid)) => format!("expected one of {}", valid.join(", ")),
+        _ => std::error::Error::source(&e)
+            .map(|source| source.to_string())
+            .unwrap_or_else(|| "invalid value".to_string()),
+    };
+    cmd.clone().error(
+        ErrorKind::ValueValidation,
+        format!(
+            "invalid value '{value}' in {} (for {flag}): {reason}",
+            var.to_string_lossy()
+        ),
+    )
+}
+
+/// The argument a file key refers to, by id or long name.
+fn find<'a>(cmd: &'a Command, key: &str) -> Option<&'a Arg> {
+    let key = key.replace('-', "_");
+    cmd.get_arguments()
+        .filter(|arg| !NOT_SETTINGS.contains(&arg.get_id().as_str()))
+        .find(|arg| {
+            arg.get_id() == key.as_str()
+                || arg
+                    .get_long()
+                    .is_some_and(|long| long.replace('-', "_") == key)
+        })
+}
+
+fn set_default(
+    cmd: Command,
+    key: &str,
+    value: &toml::Value,
+    name: &str,
+    warnings: &mut Vec<String>,
+) -> Command {
+    let Some(arg) = find(&cmd, key) else {
+        warnings.push(format!("unknown key `{name}`"));
+        return cmd;
+    };
+    let values = match value {
+        toml::Value::Array(items) if takes_many(arg) => items.iter().map(scalar).collect(),
+        toml::Value::Array(_) => Err(format!("`{name}` takes a single value")),
+        value => scalar(value).map(|value| vec![value]),
+    };
+    match values {
+        Ok(values) => {
+            let id = arg.get_id().clone();
+            cmd.mut_arg(id, |arg| arg.default_values(values))
+        }
+        Err(e) => {
+            warnings.push(e);
+            cmd
+        }
+    }
+}
+
+fn scalar(value: &toml::Value) -> Result<String, String> {
+    match value {
+        toml::Value::String(s) => Ok(s.clone()),
+        toml::Value::Integer(n) => Ok(n.to_string()),
+        toml::Value::Float(x) => Ok(x.to_string()),
+        toml::Value::Boolean(b) => Ok(b.to_string()),
+        other => Err(format!("unsupported value {other}")),
+    }
+}
+
+fn takes_many(arg: &Arg) -> bool {
+    matches!(arg.get_action(), ArgAction::Append)
+}
+
+/// The settings `matches` resolved to, laid out as in a config file.
+/// Secrets (flags hiding their environment values, like the token) are
+/// redacted. With a subcommand, only the global flags come from the top level.
+pub fn resolved(cmd: &Command, matches: &ArgMatches) -> toml::Table {
+    let globals_only = matches.subcommand().is_some();
+    let mut table = section(cmd, matches, |arg| !globals_only || arg.is_global_set());
+    if let Some((name, sub_matches)) = matches.subcommand() {
+        if let Some(sub) = cmd.find_subcommand(name) {
+            let settings = section(sub, sub_matches, |arg| !arg.is_global_set());
+            if RUN_COMMANDS.contains(&name) {
+                table.extend(settings);
+            } else if !settings.is_empty() {
+                table.insert(name.to_string(), toml::Value::Table(settings));
+            }
+        }
+    }
+    table
+}
+
+fn section(cmd: &Command, matches: &ArgMatches, include: impl Fn(&Arg) -> bool) -> toml::Table {
+    let mut table = toml::Table::new();
+    for arg in cmd.get_arguments() {
+        let id = arg.get_id().as_str();
+        if NOT_SETTINGS.contains(&id) || !include(arg) {
+            continue;
+        }
+        let Some(raw) = matches.get_raw(id) else {
+            continue;
+        };
+        let values: Vec<toml::Value> = if arg.is_hide_env_values_set() {
+            vec![toml::Value::String("<redacted>".to_string())]
+        } else if matches!(arg.get_action(), ArgAction::SetTrue) {
+            // Raw values of flags set through the environment may be "1" or "on".
+            vec![toml::Value::Boolean(matches.get_flag(id))]
+        } else {
+            raw.map(|value| typed(&value.to_string_lossy())).collect()
+        };
+        let value = if takes_many(arg) {
+            toml::Value::Array(values)
+        } else {
+            match values.into_iter().next() {
+                Some(value) => value,
+                None => continue,
+            }
+        };
+        table.insert(id.to_string(), value);
+    }
+    table
+}
+
+/// A flag's value as the TOML type it would be written as.
+fn typed(raw: &str) -> toml::Value {
+    if let Ok(n) = raw.parse::<i64>() {
+        toml::Value::Integer(n)
+    } else if let Ok(b) = raw.parse::<bool>() {
+        toml::Value::Boolean(b)
+    } else if let Some(x) = raw.parse::<f64>().ok().filter(|x| x.is_finite()) {
+        toml::Value::Float(x)
+    } else {
+        toml::Value::String(raw.to_string())
+    }
+}
@@ -0,0 +1,391 @@
+use std::borrow::Cow;
+use std::ffi::OsStr;
+use std::fmt;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// When set, human-readable output goes to stderr so stdout stays clean for
+/// machine-readable reports.
+static TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+/// Route all human-readable output to stderr.
+pub fn use_stderr() {
+    TO_STDERR.store(true, Ordering::Relaxed);
+}
+
+/// Returns true if human-readable output is routed to stderr.
+pub fn to_stderr() -> bool {
+    TO_STDERR.load(Ordering::Relaxed)
+}
+
+/// Returns true if the human-readable output goes to a terminal.
+pub fn is_terminal() -> bool {
+    if to_stderr() {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    }
+}
+
+/// Whether to color the human-readable output.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum ColorChoice {
+    /// Color on a terminal, unless NO_COLOR is set
+    #[default]
+    Auto,
+    /// Always color, even when piped
+    Always,
+    /// Never color
+    Never,
+}
+
+/// How the human-readable output is written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Style {
+    /// ANSI colors and attributes.
+    pub color: bool,
+    /// ASCII stand-ins for check marks, arrows and emoji; see `asciify`.
+    pub ascii: bool,
+}
+
+/// The style for a `--color` choice and `--ascii`, given the `NO_COLOR`
+/// variable and whether the output is a terminal. An explicit `always` or
+/// `never` wins; `auto` colors a terminal unless `NO_COLOR` is set to
+/// anything but the empty string (<https://no-color.org>).
+pub fn output_style(
+    color: ColorChoice,
+    ascii: bool,
+    no_color: Option<&OsStr>,
+    terminal: bool,
+) -> Style {
+    let color = match color {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => terminal && no_color.is_none_or(|value| value.is_empty()),
+    };
+    Style { color, ascii }
+}
+
+static ASCII: AtomicBool = AtomicBool::new(false);
+
+/// Write the human-readable output, including the final banner and result
+/// lines, in `style`. Call after `use_stderr`, since colors are decided for
+/// the stream the output goes to.
+pub fn set_style(style: Style) {
+    colored::control::set_override(style.color);
+    ASCII.store(style.ascii, Ordering::Relaxed);
+}
+
+/// Returns true if output is limited to ASCII.
+pub fn ascii() -> bool {
+    ASCII.load(Ordering::Relaxed)
+}
+
+/// Stand-ins for the symbols the output uses.
+const ASCII_GLYPHS: &[(char, &str)] = &[
+    ('✓', "+"),
+    ('✗', "x"),
+    ('⚠', "!"),
+    ('▸', ">"),
+    ('⏹', "#"),
+    ('⏳', "~"),
+    ('━', "="),
+    ('─', "-"),
+    ('—', "-"),
+    ('·', "-"),
+    ('×', "x"),
+    ('→', "->"),
+    ('…', "..."),
+];
+
+/// `line` in ASCII: symbols get the stand-ins above, emoji are dropped
+/// with the space after them, and anything else outside ASCII becomes `?`.
+pub fn asciify(line: &str) -> Cow<'_, str> {
+    if line.is_ascii() {
+        return Cow::Borrowed(line);
+    }
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii() {
+            out.push(c);
+        } else if let Some((_, glyph)) = ASCII_GLYPHS.iter().find(|(g, _)| *g == c) {
+            out.push_str(glyph);
+        } else if is_emoji(c) {
+            // Variation selectors and joiners belong to the emoji.
+            while chars.next_if(|&c| is_emoji(c)).is_some() {}
+            chars.next_if_eq(&' ');
+        } else {
+            out.push('?');
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Pictographs, plus the variation selector and joiner that combine them.
+fn is_emoji(c: char) -> bool {
+    matches!(c, '\u{1f000}'..='\u{1faff}' | '\u{2300}'..='\u{23ff}' | '\u{fe0f}' | '\u{200d}')
+}
+
+/// Compact human-readable duration: "84ms" below a second, "2.31s" above.
+pub fn format_duration(duration: Duration) -> String {
+    let ms = duration.as_millis();
+    if ms < 1000 {
+        format!("{ms}ms")
+    } else {
+        format!("{:.2}s", duration.as_secs_f64())
+    }
+}
+
+/// Like `println!`, but honours the stdout/stderr routing and style chosen
+/// above, and keeps the progress line, when one is shown, below the
+/// printed line.
+#[macro_export]
+macro_rules! outln {
+    () => {
+        $crate::console::write_line(false, format_args!(""))
+    };
+    ($($arg:tt)*) => {
+        $crate::console::write_line(false, format_args!($($arg)*))
+    };
+}
+
+/// Like `outln!`, but always to stderr: for errors and warnings that must
+/// not end up in a machine-readable report on stdout.
+#[macro_export]
+macro_rules! errln {
+    ($($arg:tt)*) => {
+        $crate::console::write_line(true, format_args!($($arg)*))
+    };
+}
+
+pub use crate::{errln, outln};
+
+/// Print one line of output, to stderr if `error`; see `outln!`.
+pub fn write_line(error: bool, line: fmt::Arguments<'_>) {
+    let mut progress = progress::STATE.lock().unwrap_or_else(|e| e.into_inner());
+    let shown = progress.as_ref().is_some_and(|p| p.shown);
+    if shown {
+        let mut out = human_output();
+        let _ = write!(out, "{CLEAR_LINE}");
+        let _ = out.flush();
+    }
+    let line = line.to_string();
+    let line = if ascii() {
+        asciify(&line)
+    } else {
+        Cow::Borrowed(line.as_str())
+    };
+    let mut out: Box<dyn Write> = if error || to_stderr() {
+        Box::new(std::io::stderr().lock())
+    } else {
+        Box::new(std::io::stdout().lock())
+    };
+    let _ = writeln!(out, "{line}");
+    let _ = out.flush();
+    drop(out);
+    if let Some(progress) = progress.as_mut().filter(|_| shown) {
+        let mut out = human_output();
+        progress.draw(&mut out);
+        let _ = out.flush();
+    }
+}
+
+/// Return to the start of the line and erase it.
+const CLEAR_LINE: &str = "\r\x1b[2K";
+
+fn human_output() -> Box<dyn Write> {
+    if to_stderr() {
+        Box::new(std::io::stderr().lock())
+    } else {
+        Box::new(std::io::stdout().lock())
+    }
+}
+
+/// A live status line under the test results: the test running, how long
+/// it has been running, tests finished out of those planned, and an ETA.
+/// Off unless `enable` is called and the human-readable output is a
+/// terminal, so piped output stays line-by-line.
+pub mod progress {
+    use super::{format_duration, human_output, CLEAR_LINE};
+    use colored::Colorize;
+    use std::io::Write;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    static ENABLED: AtomicBool = AtomicBool::new(false);
+
+    pub(super) static STATE: Mutex<Option<Progress>> = Mutex::new(None);
+
+    /// How often the elapsed time is redrawn.
+    const TICK: Duration = Duration::from_millis(250);
+
+    /// Show the progress line in runs that follow, if the human-readable
+    /// output is a terminal. Call after `console::use_stderr`.
+    pub fn enable() {
+        ENABLED.store(super::is_terminal(), Ordering::Relaxed);
+    }
+
+    pub(super) struct Progress {
+        planned: usize,
+        /// Planned tests that won't run after all (setup failures, stops).
+        dropped: usize,
+        done: usize,
+        /// Sum of the finished tests' durations, for the ETA.
+        spent: Duration,
+        parallel: usize,
+        running: Vec<(String, Instant)>,
+        /// Whether the line is on screen, so the next output must clear it.
+        pub(super) shown: bool,
+    }
+
+    impl Progress {
+        pub(super) fn draw(&mut self, out: &mut dyn Write) {
+            let total = self.planned - self.dropped;
+            // The test name goes last: it's what truncation can spare.
+            let mut line = format!("⏳ {}/{total}", self.done);
+            if self.done > 0 && total > self.done {
+                let remaining = total - self.done;
+                let eta = self.spent / self.done as u32 * remaining as u32
+                    / remaining.min(self.parallel) as u32;
+                line.push_str(&format!(" · ETA {}", format_eta(eta)));
+            }
+            if let Some((name, since)) = self.running.iter().min_by_key(|(_, since)| *since) {
+                line.push_str(&format!(" · {}", format_duration(since.elapsed())));
+                if self.running.len() > 1 {
+                    line.push_str(&format!(" (+{} running)", self.running.len() - 1));
+                }
+                line.push_str(&format!(" {name}"));
+            }
+            let line = if super::ascii() {
+                super::asciify(&line).into_owned()
+            } else {
+                line
+            };
+            let _ = write!(out, "{CLEAR_LINE}{}", truncate(&line, width()).dimmed());
+            self.shown = true;
+        }
+    }
+
+    /// "45s", "3m05s" or "1h02m": seconds are noise in long estimates.
+    fn format_eta(eta: Duration) -> String {
+        let secs = eta.as_secs();
+        match secs {
+            0..=59 => format!("{secs}s"),
+            60..=3599 => format!("{}m{:02}s", secs / 60, secs % 60),
+            _ => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+        }
+    }
+
+    /// A line longer than the terminal wraps, and clearing it then only
+    /// clears the last row.
+    fn width() -> usize {
+        std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(80)
+    }
+
+    fn truncate(line: &str, width: usize) -> String {
+        // The hourglass is two columns wide.
+        if line.chars().count() + 1 < width {
+            return line.to_string();
+        }
+        let mut line: String = line.chars().take(width.saturating_sub(3)).collect();
+        line.push('…');
+        line
+    }
+
+    fn with(update: impl FnOnce(&mut Progress)) {
+        let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(progress) = state.as_mut() {
+            update(progress);
+            let mut out = human_output();
+            progress.draw(&mut out);
+            let _ = out.flush();
+        }
+    }
+
+    /// The progress line of one run; dropping it erases the line.
+    pub struct Run {
+        ticker: Option<(Arc<AtomicBool>, std::thread::JoinHandle<()>)>,
+    }
+
+    /// Start the progress line for a run of `planned` tests, up to
+    /// `parallel` at a time. Does nothing unless `enable` turned it on.
+    pub fn begin(planned: usize, parallel: usize) -> Run {
+        if !ENABLED.load(Ordering::Relaxed) {
+            return Run { ticker: None };
+        }
+        *STATE.lock().unwrap_or_else(|e| e.into_inner()) = Some(Progress {
+            planned,
+            dropped: 0,
+            done: 0,
+            spent: Duration::ZERO,
+            parallel: parallel.max(1),
+            running: Vec::new(),
+            shown: false,
+        });
+        let stop = Arc::new(AtomicBool::new(false));
+        let ticker = std::thread::spawn({
+            let stop = Arc::clone(&stop);
+            move || {
+                while !stop.load(Ordering::Relaxed) {
+                    std::thread::sleep(TICK);
+                    with(|_| {});
+                }
+            }
+        });
+        Run {
+            ticker: Some((stop, ticker)),
+        }
+    }
+
+    impl Drop for Run {
+        fn drop(&mut self) {
+            let Some((stop, ticker)) = self.ticker.take() else {
+                return;
+            };
+            stop.store(true, Ordering::Relaxed);
+            let _ = ticker.join();
+            let shown = STATE
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .take()
+                .is_some_and(|progress| progress.shown);
+            if shown {
+                let mut out = human_output();
+                let _ = write!(out, "{CLEAR_LINE}");
+                let _ = out.flush();
+            }
+        }
+    }
+
+    /// A planned test started; `name` is what the line shows for it.
+    pub fn started(name: &str) {
+        with(|progress| progress.running.push((name.to_string(), Instant::now())));
+    }
+
+    /// A started test finished after `took`.
+    pub fn finished(name: &str, took: Duration) {
+        with(|progress| {
+            if let Some(i) = progress.running.iter().position(|(n, _)| n == name) {
+                progress.running.remove(i);
+            }
+            progress.done += 1;
+            progress.spent += took;
+        });
+    }
+
+    /// The first `planned` tests of the run are settled: any of them not
+    /// finished by now (a skipped group, a stopped run) won't run.
+    pub fn settle(planned: usize) {
+        with(|progress| {
+            progress.running.clear();
+            progress.dropped = planned.saturating_sub(progress.done);
+        });
+    }
+}
@@ -0,0 +1,144 @@
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tracing::Instrument;
+
+use crate::artifacts::RequestLog;
+
+/// Per-test state visible to the client while a test case runs.
+#[derive(Clone)]
+struct TestContext {
+    /// "Group / Test" name of the test case.
+    name: String,
+    /// Where the client records HTTP exchanges, when `--artifacts` is set.
+    requests: Option<RequestLog>,
+    /// Where the test records measurements, warnings and notes for the report.
+    observations: Option<Observations>,
+}
+
+/// Measurements, warnings and notes recorded by one test attempt. Clones
+/// share the same values.
+#[derive(Clone, Default)]
+pub struct Observations {
+    metrics: Arc<Mutex<BTreeMap<String, f64>>>,
+    warnings: Arc<Mutex<Vec<String>>>,
+    notes: Arc<Mutex<Vec<String>>>,
+    unsupported: Arc<Mutex<Option<String>>>,
+}
+
+impl Observations {
+    /// All recorded metrics, by name.
+    pub fn metrics(&self) -> BTreeMap<String, f64> {
+        self.metrics.lock().unwrap().clone()
+    }
+
+    /// All recorded warnings, in the order they were raised.
+    pub fn warnings(&self) -> Vec<String> {
+        self.warnings.lock().unwrap().clone()
+    }
+
+    /// All recorded notes, in the order they were made.
+    pub fn notes(&self) -> Vec<String> {
+        self.notes.lock().unwrap().clone()
+    }
+
+    /// Why the attempt found its feature unsupported, if it did.
+    pub fn unsupported(&self) -> Option<String> {
+        self.unsupported.lock().unwrap().clone()
+    }
+}
+
+tokio::task_local! {
+    static CURRENT: TestContext;
+}
+
+/// Run `fut` as test `name`, recording HTTP exchanges into `requests` and
+/// measurements, warnings and notes into `observations` if given.
+pub async fn scope<F: Future>(
+    name: String,
+    requests: Option<RequestLog>,
+    observations: Option<Observations>,
+    fut: F,
+) -> F::Output {
+    let ctx = TestContext {
+        name,
+        requests,
+        observations,
+    };
+    CURRENT.scope(ctx, fut).await
+}
+
+/// Name of the test case driving the current task, if any.
+pub fn current_test() -> Option<String> {
+    CURRENT.try_with(|ctx| ctx.name.clone()).ok()
+}
+
+/// Request log of the test case driving the current task, if recording.
+pub fn request_log() -> Option<RequestLog> {
+    CURRENT.try_with(|ctx| ctx.requests.clone()).ok().flatten()
+}
+
+/// Record `value` under `name` for the current test; it shows up in the test's
+/// output and in the JSON report. A later value replaces an earlier one.
+pub fn record_metric(name: &str, value: f64) {
+    let _ = CURRENT.try_with(|ctx| {
+        if let Some(observations) = &ctx.observations {
+            observations
+                .metrics
+                .lock()
+                .unwrap()
+                .insert(name.to_string(), value);
+        }
+    });
+}
+
+/// Record a problem that does not fail the current test, e.g. a mismatch the
+/// user chose to tolerate; it shows up in the test's output and in the JSON
+/// report.
+pub fn warn(message: impl Into<String>) {
+    let message = message.into();
+    let _ = CURRENT.try_with(|ctx| {
+        if let Some(observations) = &ctx.observations {
+            observations.warnings.lock().unwrap().push(message);
+        }
+    });
+}
+
+/// Record which of several acceptable behaviours the orchestrator showed,
+/// so a passing test still makes the contract visible; it shows up in the
+/// test's output and in the JSON report.
+pub fn note(message: impl Into<String>) {
+    let message = message.into();
+    let _ = CURRENT.try_with(|ctx| {
+        if let Some(observations) = &ctx.observations {
+            observations.notes.lock().unwrap().push(message);
+        }
+    });
+}
+
+/// Mark the current test as unsupported: the orchestrator turned out to lack
+/// a feature it checks, which no capability probe could tell up front. If
+/// the attempt then passes, it is reported as unsupported rather than passed.
+pub fn unsupported(reason: impl Into<String>) {
+    let reason = reason.into();
+    let _ = CURRENT.try_with(|ctx| {
+        if let Some(observations) = &ctx.observations {
+            *observations.unsupported.lock().unwrap() = Some(reason);
+        }
+    });
+}
+
+/// `tokio::spawn` that keeps the current test context and tracing span, so
+/// sessions, requests and logs of the spawned task are attributed to the
+/// test that spawned it.
+pub fn spawn<F>(fut: F) -> tokio::task::JoinHandle<F::Output>
+where
+    F: Future + Send + 'static,
+    F::Output: Send + 'static,
+{
+    let fut = fut.instrument(tracing::Span::current());
+    match CURRENT.try_with(|ctx| ctx.clone()) {
+        Ok(ctx) => tokio::spawn(CURRENT.scope(ctx, fut)),
+        Err(_) => tokio::spawn(fut),
+    }
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeMap;
+
+/// One sample line of a Prometheus text exposition.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub struct Sample {
+    pub labels: BTreeMap<String, String>,
+    pub value: f64,
+}
+
+/// Samples of a Prometheus text exposition (GET /metrics), by metric name.
+/// Only what tests need is parsed: comments, `# HELP`, and `# TYPE` lines are
+/// skipped, and timestamps are ignored.
+#[derive(Debug, Default)]
+#[non_exhaustive]
+pub struct Exposition {
+    pub samples: BTreeMap<String, Vec<Sample>>,
+}
+
+impl Exposition {
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut samples: BTreeMap<String, Vec<Sample>> = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (name, sample) =
+                parse_line(line).map_err(|e| format!("line {}: {e}: {line:?}", number + 1))?;
+            samples.entry(name).or_default().push(sample);
+        }
+        Ok(Self { samples })
+    }
+
+    /// Sum of `name` over all its label sets, or None if it isn't exposed.
+    pub fn total(&self, name: &str) -> Option<f64> {
+        self.samples
+            .get(name)
+            .map(|samples| samples.iter().map(|s| s.value).sum())
+    }
+}
+
+/// `name{label="value",...} value [timestamp]`
+fn parse_line(line: &str) -> Result<(String, Sample), String> {
+    let name_end = line
+        .find(|c: char| c == '{' || c.is_whitespace())
+        .ok_or("no value")?;
+    let name = &line[..name_end];
+    if name.is_empty() {
+        return Err("no metric name".to_string());
+    }
+
+    let mut rest = &line[name_end..];
+    let mut labels = BTreeMap::new();
+    if let Some(after_brace) = rest.strip_prefix('{') {
+        rest = parse_labels(after_brace, &mut labels)?;
+    }
+
+    let mut fields = rest.split_whitespace();
+    let value = fields.next().ok_or("no value")?;
+    let value = parse_value(value).ok_or_else(|| format!("invalid value {value:?}"))?;
+    Ok((name.to_string(), Sample { labels, value }))
+}
+
+/// Parse labels up to the closing brace into `labels`, returning what follows it.
+fn parse_labels<'a>(
+    mut rest: &'a str,
+    labels: &mut BTreeMap<String, String>,
+) -> Result<&'a str, String> {
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix('}') {
+            return Ok(after);
+        }
+        let (key, after_key) = rest.split_once('=').ok_or("unterminated labels")?;
+        let after_quote = after_key
+            .trim_start()
+            .strip_prefix('"')
+            .ok_or("label value is not quoted")?;
+
+        let mut value = String::new();
+        let mut chars = after_quote.char_indices();
+        let end = loop {
+            match chars.next().ok_or("unterminated label value")? {
+                (i, '"') => break i,
+                (_, '\\') => match chars.next().ok_or("unterminated label value")?.1 {
+                    'n' => value.push('\n'),
+                    other => value.push(other),
+                },
+                (_, c) => value.push(c),
+            }
+        };
+        labels.insert(key.trim().to_string(), value);
+
+        rest = after_quote[end + 1..].trim_start();
+        rest = rest.strip_prefix(',').unwrap_or(rest);
+    }
+}
+
+/// A sample value: a float, or `+Inf`, `-Inf`, or `NaN`.
+fn parse_value(text: &str) -> Option<f64> {
+    match text {
+        "+Inf" => Some(f64::INFINITY),
+        "-Inf" => Some(f64::NEG_INFINITY),
+        "NaN" => Some(f64::NAN),
+        _ => text.parse().ok(),
+    }
+}
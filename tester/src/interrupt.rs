@@ -0,0 +1,54 @@
+use crate::console::errln;
+use tokio::sync::watch;
+
+/// Exit code for a run stopped by Ctrl-C (128 + SIGINT), as shells report it.
+pub const INTERRUPTED_EXIT_CODE: i32 = 130;
+
+/// Cooperative stop request raised by Ctrl-C. Clones observe the same signal.
+#[derive(Clone)]
+pub struct Interrupt {
+    rx: watch::Receiver<bool>,
+}
+
+impl Interrupt {
+    /// Listen for Ctrl-C. The first press asks the run to stop gracefully so
+    /// sessions can be cleaned up; a second press exits immediately.
+    pub fn install() -> Self {
+        let (tx, rx) = watch::channel(false);
+        tokio::spawn(async move {
+            if tokio::signal::ctrl_c().await.is_err() {
+                // No signal support: keep the sender alive so nobody sees a stop.
+                std::future::pending::<()>().await;
+            }
+            errln!("\n⏹ interrupted — cleaning up sessions (press Ctrl-C again to force exit)");
+            let _ = tx.send(true);
+
+            if tokio::signal::ctrl_c().await.is_ok() {
+                errln!("⏹ forced exit; sessions created by this run may still be alive");
+                std::process::exit(INTERRUPTED_EXIT_CODE);
+            }
+        });
+        Self { rx }
+    }
+
+    /// A signal that never fires, for embedding the runner in a host that
+    /// handles Ctrl-C itself.
+    pub fn never() -> Self {
+        let (_tx, rx) = watch::channel(false);
+        Self { rx }
+    }
+
+    /// Whether a stop has been requested.
+    pub fn is_set(&self) -> bool {
+        *self.rx.borrow()
+    }
+
+    /// Resolves once a stop has been requested.
+    pub async fn wait(&self) {
+        let mut rx = self.rx.clone();
+        if rx.wait_for(|stop| *stop).await.is_err() {
+            // The listener is gone, so no stop can arrive any more.
+            std::future::pending::<()>().await;
+        }
+    }
+}
@@ -0,0 +1,173 @@
+//! Latencies of the client calls made during a run, per operation, and the
+//! p95 budgets (`--slo`) that turn the suite into a performance gate. The
+//! samples are the run's `Stats`, read through the endpoint each operation
+//! calls.
+
+use crate::console::outln;
+use crate::stats::{millis, Stats};
+use crate::tests::util;
+use colored::Colorize;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+/// Operations the client times, named as in an SLO file.
+pub const OPERATIONS: [&str; 6] = ["create", "get", "list", "update", "delete", "health"];
+
+/// The `Stats` endpoint each of `OPERATIONS` is measured at.
+const ENDPOINTS: [&str; 6] = [
+    "POST /sessions",
+    "GET /sessions/{id}",
+    "GET /sessions",
+    "PUT /sessions/{id}",
+    "DELETE /sessions/{id}",
+    "GET /health",
+];
+
+/// The percentile budgets apply to.
+pub const BUDGET_PERCENTILE: f64 = 95.0;
+
+/// p95 budget per operation.
+pub type Budgets = BTreeMap<String, Duration>;
+
+/// The `Stats` endpoint an operation is measured at, e.g. "POST /sessions"
+/// for "create".
+pub fn endpoint(operation: &str) -> Option<&'static str> {
+    let i = OPERATIONS.iter().position(|op| *op == operation)?;
+    Some(ENDPOINTS[i])
+}
+
+/// Budgets from an SLO file's contents: operation names mapped to p95
+/// budgets in milliseconds, e.g. `{"create": 50, "get": 20}`.
+pub fn budgets(value: &serde_json::Value) -> Result<Budgets, String> {
+    let Some(table) = value.as_object() else {
+        return Err("expected operation names mapped to milliseconds".to_string());
+    };
+    table
+        .iter()
+        .map(|(operation, budget)| {
+            if !OPERATIONS.contains(&operation.as_str()) {
+                return Err(format!(
+                    "unknown operation `{operation}`; expected one of {}",
+                    OPERATIONS.join(", ")
+                ));
+            }
+            let ms = budget
+                .as_f64()
+                .filter(|ms| *ms > 0.0 && ms.is_finite())
+                .ok_or_else(|| {
+                    format!("`{operation}` needs a positive number of milliseconds, got {budget}")
+                })?;
+            Ok((operation.clone(), Duration::from_secs_f64(ms / 1000.0)))
+        })
+        .collect()
+}
+
+/// Latency distribution of one operation over a run.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct OperationLatency {
+    pub operation: String,
+    pub samples: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+    /// The operation's `--slo` budget for p95, if it has one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget_p95_ms: Option<f64>,
+}
+
+impl OperationLatency {
+    pub fn over_budget(&self) -> bool {
+        self.budget_p95_ms
+            .is_some_and(|budget| self.p95_ms > budget)
+    }
+}
+
+/// Percentiles of each operation with samples, in `OPERATIONS` order, with
+/// its budget attached.
+pub fn summarize(stats: &Stats, budgets: &Budgets) -> Vec<OperationLatency> {
+    OPERATIONS
+        .iter()
+        .zip(ENDPOINTS)
+        .filter_map(|(operation, endpoint)| {
+            let latencies = stats.latencies(endpoint);
+            if latencies.is_empty() {
+                return None;
+            }
+            let at = |p| {
+                util::percentile(&latencies, p)
+                    .map(millis)
+                    .unwrap_or_default()
+            };
+            Some(OperationLatency {
+                operation: operation.to_string(),
+                samples: latencies.len(),
+                p50_ms: at(50.0),
+                p95_ms: at(BUDGET_PERCENTILE),
+                p99_ms: at(99.0),
+                max_ms: latencies.last().copied().map(millis).unwrap_or_default(),
+                budget_p95_ms: budgets.get(*operation).copied().map(millis),
+            })
+        })
+        .collect()
+}
+
+/// Print the latency table, marking operations over budget, and a line per
+/// budget that no call exercised.
+pub fn print_table(latencies: &[OperationLatency], budgets: &Budgets) {
+    if latencies.is_empty() && budgets.is_empty() {
+        return;
+    }
+    outln!();
+    outln!(
+        "{}",
+        format!(
+            "{:<8} {:>6} {:>9} {:>9} {:>9} {:>9} {:>11}",
+            "op", "n", "p50", "p95", "p99", "max", "p95 budget"
+        )
+        .bold()
+    );
+    for op in latencies {
+        let budget = match op.budget_p95_ms {
+            Some(ms) => format!("{ms:>11.2}"),
+            None => format!("{:>11}", "-"),
+        };
+        let line = format!(
+            "{:<8} {:>6} {:>9.2} {:>9.2} {:>9.2} {:>9.2} {budget}",
+            op.operation, op.samples, op.p50_ms, op.p95_ms, op.p99_ms, op.max_ms
+        );
+        if op.over_budget() {
+            outln!("{} {}", line.red(), "✗ over budget".red().bold());
+        } else {
+            outln!("{line}");
+        }
+    }
+    outln!("Latencies in milliseconds, until the response headers arrived.");
+    for operation in budgets.keys() {
+        if !latencies.iter().any(|op| &op.operation == operation) {
+            outln!(
+                "{}",
+                format!("⚠ `{operation}` has a budget but no call measured it").yellow()
+            );
+        }
+    }
+}
+
+/// "create p95 81.20ms exceeds its 50.00ms budget" for each operation over
+/// budget.
+pub fn violations(latencies: &[OperationLatency]) -> Vec<String> {
+    latencies
+        .iter()
+        .filter(|op| op.over_budget())
+        .map(|op| {
+            format!(
+                "{} p95 {:.2}ms exceeds its {:.2}ms budget",
+                op.operation,
+                op.p95_ms,
+                op.budget_p95_ms.unwrap_or_default()
+            )
+        })
+        .collect()
+}
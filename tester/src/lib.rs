@@ -0,0 +1,58 @@
+//! Test suite for the Steel orchestrator, usable as a library.
+//!
+//! The `steel-tester` binary is a thin CLI over this crate. To run checks
+//! from Rust instead, build an [`OrchestratorClient`](client::OrchestratorClient),
+//! register the groups you want on a [`TestRunner`](runner::TestRunner), and
+//! inspect the [`RunReport`](report::RunReport) it returns:
+//!
+//! ```no_run
+//! use steel_tester::client::OrchestratorClient;
+//! use steel_tester::interrupt::Interrupt;
+//! use steel_tester::report::Outcome;
+//! use steel_tester::runner::{RunnerConfig, TestRunner};
+//! use steel_tester::tests::crud::{self, CrudSettings};
+//!
+//! # async fn example() {
+//! let mut runner = TestRunner::new(RunnerConfig::default());
+//! runner.add_group("CRUD Operations", crud::tests(&CrudSettings::default()));
+//! // Count the client's requests in the run's stats table.
+//! let client = OrchestratorClient::new("http://localhost:8080")
+//!     .with_stats(runner.stats().clone());
+//!
+//! // The host owns Ctrl-C, so give the runner a stop signal that never fires.
+//! let report = runner.run(&client, &Interrupt::never()).await;
+//! if report.outcome != Outcome::Passed {
+//!     eprintln!("{}", report.to_json());
+//! }
+//! # }
+//! ```
+//!
+//! The CLI, and [`config`] for its config file and [`logging`] for its
+//! `-v` logs, need the default `cli` feature; the library builds without it.
+//! Without it, the library still emits `tracing` events for a subscriber
+//! the host installs.
+//! The `mock` feature adds [`mock_server`], an in-memory orchestrator that
+//! the suite's own integration tests run against.
+
+pub mod artifacts;
+pub mod bench;
+pub mod capabilities;
+pub mod cleanup;
+pub mod client;
+#[cfg(feature = "cli")]
+pub mod config;
+pub mod console;
+pub mod context;
+pub mod exposition;
+pub mod interrupt;
+pub mod latency;
+#[cfg(feature = "cli")]
+pub mod logging;
+#[cfg(feature = "mock")]
+pub mod mock_server;
+pub mod report;
+pub mod retry;
+pub mod runner;
+pub mod stats;
+pub mod tests;
+pub mod tracker;
@@ -0,0 +1,88 @@
+//! Diagnostic logs behind `-v`: INFO spans per test and hook, DEBUG events
+//! for every HTTP request. They go to stderr, through the console so the
+//! progress line stays intact, and a copy can go to a file.
+
+use crate::console;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::filter::Targets;
+use tracing_subscriber::fmt::MakeWriter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::Layer;
+
+/// The filter for a `-v` count: the suite's own warnings by default, test
+/// spans with `-v`, HTTP requests with `-vv`, and everything, the HTTP
+/// stack's own logs included, with `-vvv`.
+pub fn filter(verbosity: u8) -> Targets {
+    let (own, others) = match verbosity {
+        0 => (LevelFilter::WARN, LevelFilter::WARN),
+        1 => (LevelFilter::INFO, LevelFilter::WARN),
+        2 => (LevelFilter::DEBUG, LevelFilter::WARN),
+        _ => (LevelFilter::TRACE, LevelFilter::DEBUG),
+    };
+    Targets::new()
+        .with_target(env!("CARGO_CRATE_NAME"), own)
+        .with_default(others)
+}
+
+/// Install the global subscriber: logs at `verbosity` to stderr, colored
+/// if `color`, and the same logs uncolored to `file` if given.
+pub fn init(verbosity: u8, file: Option<&Path>, color: bool) -> Result<(), String> {
+    let file = file
+        .map(|path| {
+            File::create(path)
+                .map(Mutex::new)
+                .map_err(|e| format!("cannot create log file {}: {e}", path.display()))
+        })
+        .transpose()?;
+
+    let stderr = tracing_subscriber::fmt::layer()
+        .with_writer(ConsoleWriter)
+        .with_ansi(color)
+        .with_filter(filter(verbosity));
+    let file = file.map(|file| {
+        tracing_subscriber::fmt::layer()
+            .with_writer(file)
+            .with_ansi(false)
+            .with_filter(filter(verbosity))
+    });
+    let subscriber = tracing_subscriber::registry().with(stderr).with(file);
+    tracing::subscriber::set_global_default(subscriber).map_err(|e| e.to_string())
+}
+
+/// Hands each formatted event to `errln!` once it is complete.
+struct ConsoleWriter;
+
+impl<'a> MakeWriter<'a> for ConsoleWriter {
+    type Writer = EventBuffer;
+
+    fn make_writer(&'a self) -> Self::Writer {
+        EventBuffer(Vec::new())
+    }
+}
+
+struct EventBuffer(Vec<u8>);
+
+impl io::Write for EventBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for EventBuffer {
+    fn drop(&mut self) {
+        let text = String::from_utf8_lossy(&self.0);
+        let text = text.trim_end_matches('\n');
+        if !text.is_empty() {
+            console::errln!("{text}");
+        }
+    }
+}
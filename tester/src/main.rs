@@ -1,72 +1,1032 @@
-mod client;
-mod runner;
-mod tests;
-
-use clap::Parser;
+use clap::parser::ValueSource;
+use clap::{Args as _, CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
 use colored::Colorize;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::IsTerminal;
+use std::time::Duration;
+
+use steel_tester::capabilities::Capabilities;
+use steel_tester::client::{ClientConfig, ClientError, OrchestratorClient};
+use steel_tester::config::{self, ConfigFile};
+use steel_tester::console::ColorChoice;
+use steel_tester::console::{errln, outln};
+use steel_tester::report::RunReport;
+use steel_tester::retry::RetryPolicy;
+use steel_tester::runner::{RunnerConfig, TestRunner};
+use steel_tester::{bench, cleanup, console, interrupt, latency, logging, report, runner, tests};
 
-use client::OrchestratorClient;
-use runner::TestRunner;
+/// Format of the final results written to stdout.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    /// Human-readable console output
+    Text,
+    /// A JSON run report on stdout; human-readable output moves to stderr
+    Json,
+}
 
 #[derive(Parser)]
-#[command(name = "steel-tester", about = "Test suite for the Steel orchestrator")]
-struct Args {
+#[command(
+    name = "steel-tester",
+    about = "Test suite for the Steel orchestrator",
+    after_help = report::EXIT_CODES_HELP
+)]
+struct Cli {
+    #[command(flatten)]
+    connection: ConnectionArgs,
+
+    /// TOML file of flag defaults [default: steel-tester.toml, if present]
+    #[arg(long, global = true, value_name = "PATH")]
+    config: Option<std::path::PathBuf>,
+
+    /// Print the resolved settings as TOML and exit
+    #[arg(long, global = true)]
+    print_config: bool,
+
+    /// Results output format
+    #[arg(long, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    /// When to color the human-readable output
+    #[arg(long, value_enum, global = true, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Same as --color never
+    #[arg(long, global = true, conflicts_with = "color")]
+    no_color: bool,
+
+    /// Write check marks, arrows and emoji as ASCII, for logs that mangle Unicode
+    #[arg(long, global = true)]
+    ascii: bool,
+
+    /// Log to stderr: -v for test spans, -vv for every HTTP request, -vvv
+    /// for the HTTP stack too
+    #[arg(short, long, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Also write the logs to this file
+    #[arg(long, global = true, value_name = "PATH")]
+    log_file: Option<std::path::PathBuf>,
+
+    /// Print GitHub Actions annotations for failures, flaky passes and leaked
+    /// sessions [default: true when GITHUB_ACTIONS=true]
+    #[arg(long, global = true, value_name = "BOOL", num_args = 0..=1, default_missing_value = "true")]
+    github_annotations: Option<bool>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    /// Flags of `run`, still accepted without a subcommand
+    #[command(flatten, next_help_heading = "Run options (deprecated without `run`)")]
+    run: RunArgs,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Run the test suite (the default, for now, when no subcommand is given)
+    Run(RunArgs),
+    /// Print the tests `run` would run with the same flags, then exit
+    List(RunArgs),
+    /// Measure create/get/delete latencies instead of running the tests
+    Bench(BenchArgs),
+    /// Delete the sessions the orchestrator lists, e.g. after an aborted run
+    Cleanup(CleanupArgs),
+    /// Crash random workers during a mixed workload and check the pool copes
+    Chaos(ChaosArgs),
+}
+
+/// How to reach the orchestrator; accepted before or after any subcommand.
+#[derive(clap::Args)]
+struct ConnectionArgs {
     /// Orchestrator base URL
-    #[arg(long, default_value = "http://localhost:8080")]
+    #[arg(long, global = true, default_value = "http://localhost:8080")]
     url: String,
+
+    /// Per-request HTTP timeout in seconds
+    #[arg(long, global = true, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+    request_timeout: u64,
+
+    /// TCP connect timeout in seconds (defaults to the OS limit)
+    #[arg(long, global = true, value_parser = clap::value_parser!(u64).range(1..))]
+    connect_timeout: Option<u64>,
+
+    /// Bearer token sent with every request; also enables the Auth group
+    #[arg(long, global = true, env = "STEEL_TOKEN", hide_env_values = true)]
+    token: Option<String>,
+
+    /// Skip TLS certificate verification (self-signed certificates)
+    #[arg(long, global = true, conflicts_with = "ca_cert")]
+    insecure: bool,
+
+    /// Trust the CA certificate(s) in this PEM file, e.g. an internal CA bundle
+    #[arg(long, global = true, value_name = "PATH")]
+    ca_cert: Option<std::path::PathBuf>,
+
+    /// Header carrying each request's generated ID, to find it in the
+    /// orchestrator's logs
+    #[arg(
+        long,
+        global = true,
+        value_name = "NAME",
+        default_value = "X-Request-Id"
+    )]
+    request_id_header: reqwest::header::HeaderName,
+
+    /// Retry GET, DELETE, and keyed creates up to N times on connect errors,
+    /// timeouts, and 502/503/504, with exponential backoff
+    #[arg(long, global = true, value_name = "N", default_value_t = 0)]
+    client_retries: u32,
+}
+
+#[derive(clap::Args)]
+struct RunArgs {
+    /// Maximum number of test cases to run concurrently within a group
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u16).range(1..))]
+    parallel: u16,
+
+    /// Default per-test timeout in seconds
+    #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u64).range(1..))]
+    test_timeout: u64,
+
+    /// Session TTL the orchestrator is configured with, in seconds
+    #[arg(long, default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    ttl: u64,
+
+    /// Orchestrator TTL sweep interval in seconds
+    #[arg(long, default_value_t = 5, value_parser = clap::value_parser!(u64).range(1..))]
+    sweep_interval: u64,
+
+    /// Whether session activity extends the TTL on the orchestrator under test
+    #[arg(long, value_enum, default_value_t = tests::ttl::TtlMode::Sliding)]
+    ttl_mode: tests::ttl::TtlMode,
+
+    /// Status an expired session must get, for orchestrators that tell it
+    /// apart from one that never existed, e.g. 410 (default: report only)
+    #[arg(long, value_name = "CODE", value_parser = clap::value_parser!(u16).range(400..500))]
+    expect_expired_status: Option<u16>,
+
+    /// Sessions the bulk TTL test expires at once; the orchestrator needs at least
+    /// this many workers (-max-workers)
+    #[arg(long, default_value_t = 50, value_parser = clap::value_parser!(u64).range(1..))]
+    bulk_sessions: u64,
+
+    /// Seconds to wait for the orchestrator to clean up after a worker crash,
+    /// or to come back after a restart or shutdown
+    #[arg(long, value_name = "SECS", default_value_t = 15, value_parser = clap::value_parser!(u64).range(1..))]
+    recovery_timeout: u64,
+
+    /// Fail the recovery time test if a crashed worker takes longer than this to
+    /// be replaced (default: report the time only)
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    recovery_slo: Option<Duration>,
+
+    /// What the restart test expects to happen to sessions across an
+    /// orchestrator restart
+    #[arg(long, value_enum, default_value_t = tests::restart::PersistenceMode::Drop)]
+    persistence_mode: tests::restart::PersistenceMode,
+
+    /// Workers the multi-crash recovery test kills at once
+    #[arg(long, default_value_t = 3, value_parser = clap::value_parser!(u64).range(1..))]
+    crash_workers: u64,
+
+    /// Simultaneous creates fired by the stress test; repeat it for a stress
+    /// test per count, e.g. --stress-concurrency 10 --stress-concurrency 100
+    #[arg(long, value_name = "N", default_value = "50", value_parser = clap::value_parser!(u64).range(1..))]
+    stress_concurrency: Vec<u64>,
+
+    /// Largest request body the orchestrator accepts, in bytes or with a K/M
+    /// suffix; the payload tests expect anything bigger to be rejected
+    #[arg(long, value_name = "SIZE", default_value = "10M", value_parser = parse_size)]
+    max_payload: usize,
+
+    /// Largest document the fuzz test generates, in bytes or with a K/M suffix
+    #[arg(long, value_name = "SIZE", default_value = "16K", value_parser = parse_size)]
+    fuzz_max_size: usize,
+
+    /// Which top-level JSON types a create body may have on this deployment
+    #[arg(long, value_enum, default_value_t = tests::validation::PayloadPolicy::ObjectOnly)]
+    payload_policy: tests::validation::PayloadPolicy,
+
+    /// Report numbers that come back as the same float, e.g. u64::MAX as
+    /// 1.8446744073709552e19 or -0.0 as 0, as warnings instead of failures
+    #[arg(long)]
+    allow_numeric_lossy: bool,
+
+    /// How far a session's created_at may be from this machine's clock, in seconds
+    #[arg(long, value_name = "SECS", default_value = "60", value_parser = parse_seconds)]
+    clock_skew_tolerance: Duration,
+
+    /// Fail the cold-start test if the run's first create takes longer than
+    /// this to its first byte, e.g. --cold-start-budget 2.5 (default: report
+    /// the time only)
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    cold_start_budget: Option<Duration>,
+
+    /// Longest the orchestrator may wait on a request body that stopped
+    /// arriving before answering 408 or closing the connection
+    #[arg(long, value_name = "SECS", default_value = "60", value_parser = parse_seconds)]
+    stall_timeout: Duration,
+
+    /// Create/delete cycles in the churn test
+    #[arg(long, value_name = "N", default_value_t = 200, value_parser = clap::value_parser!(u64).range(1..))]
+    churn_iterations: u64,
+
+    /// Run the soak test for this many seconds (skipped if not given)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    soak_duration: Option<u64>,
+
+    /// Target create/get/delete cycles per second during the soak test
+    #[arg(long, value_name = "N", default_value_t = 5, value_parser = clap::value_parser!(u32).range(1..))]
+    soak_rps: u32,
+
+    /// Requests per second the orchestrator limits a client to; enables the
+    /// Rate Limiting group, which sends three times as many
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..))]
+    rate_limit_rps: Option<u32>,
+
+    /// Origin of a browser dashboard that calls the orchestrator, e.g.
+    /// https://dash.example.com; enables the CORS group
+    #[arg(long, value_name = "ORIGIN")]
+    cors_origin: Option<String>,
+
+    /// Seed for all randomized tests (default: random; printed at the start
+    /// and recorded in the JSON report)
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Worker pool capacity for the Capacity group (default: max_workers from /status)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pool_size: Option<u64>,
+
+    /// Re-run a failing test up to N more times before counting it as failed
+    #[arg(long, default_value_t = 0)]
+    retries: u32,
+
+    /// Stop the run at the first failing test
+    #[arg(long)]
+    fail_fast: bool,
+
+    /// Run the selected suite N times and report per-test pass/fail counts
+    #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    repeat: u32,
+
+    /// Only run tests whose name contains this substring (case-insensitive)
+    #[arg(long)]
+    filter: Option<String>,
+
+    /// Only run this group (repeatable), e.g. --group "CRUD Operations"
+    #[arg(long = "group", value_name = "NAME")]
+    groups: Vec<String>,
+
+    /// Skip this group (repeatable), e.g. --skip-group "TTL Expiration"
+    #[arg(long = "skip-group", value_name = "NAME")]
+    skip_groups: Vec<String>,
+
+    /// Only run tests carrying this tag (repeatable), e.g. --tag slow
+    #[arg(long = "tag", value_name = "TAG")]
+    tags: Vec<String>,
+
+    /// Skip tests carrying this tag (repeatable), e.g. --exclude-tag destructive
+    #[arg(long = "exclude-tag", value_name = "TAG")]
+    exclude_tags: Vec<String>,
+
+    /// Exit with code 3 if tests left sessions behind, even if they were swept
+    #[arg(long)]
+    strict_leaks: bool,
+
+    /// Fail tests needing an optional endpoint the orchestrator lacks instead of skipping them
+    #[arg(long)]
+    require_all: bool,
+
+    /// Fail the idempotent-create test, instead of reporting it unsupported,
+    /// if the orchestrator ignores the Idempotency-Key header
+    #[arg(long)]
+    require_idempotency: bool,
+
+    /// Run each group's tests in a random order drawn from the run seed
+    #[arg(long)]
+    shuffle: bool,
+
+    /// Run the groups in a random order drawn from the run seed
+    #[arg(long)]
+    shuffle_groups: bool,
+
+    /// Stop starting tests after this many seconds; tests in flight finish, remaining ones are not run (exit code 4)
+    #[arg(long, value_name = "SECS", value_parser = clap::value_parser!(u64).range(1..))]
+    max_duration: Option<u64>,
+
+    /// Print results line by line without the live status line, which is
+    /// only shown on a terminal anyway
+    #[arg(long)]
+    no_progress: bool,
+
+    /// TOML or JSON file of p95 latency budgets in milliseconds per operation
+    /// (create, get, list, update, delete, health); fails the run (exit code 5)
+    /// if one is exceeded
+    #[arg(long, value_name = "PATH", value_parser = parse_slo)]
+    slo: Option<latency::Budgets>,
+
+    /// Compare the run with this earlier JSON report and list regressions:
+    /// newly failing, newly flaky, or more than 50% slower tests
+    #[arg(long, value_name = "PATH")]
+    baseline: Option<std::path::PathBuf>,
+
+    /// Exit with code 6 if an otherwise passing run regressed against --baseline
+    #[arg(long, requires = "baseline")]
+    fail_on_regression: bool,
+
+    /// Write the run's JSON report to this path, for a later --baseline
+    #[arg(long, value_name = "PATH")]
+    save_baseline: Option<std::path::PathBuf>,
+
+    /// Highlight tests slower than this many seconds, e.g. --slow-threshold 2.5
+    #[arg(long, value_name = "SECS", value_parser = parse_seconds)]
+    slow_threshold: Option<Duration>,
+
+    /// Save the HTTP requests and responses of failed tests under this directory
+    #[arg(long, value_name = "DIR")]
+    artifacts: Option<std::path::PathBuf>,
+
+    /// Also write a self-contained HTML report to this path
+    #[arg(long, value_name = "PATH")]
+    html: Option<std::path::PathBuf>,
+}
+
+#[derive(clap::Args)]
+struct BenchArgs {
+    /// Cycles measured per benchmark phase
+    #[arg(long, value_name = "N", default_value_t = 100, value_parser = clap::value_parser!(u64).range(1..))]
+    iterations: u64,
+
+    /// Also benchmark with this many cycles in flight at once, reported separately
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    concurrency: Option<u64>,
+}
+
+#[derive(clap::Args)]
+struct CleanupArgs {
+    /// Only delete sessions whose `user` marker has a prefix the tests use (test_, ttl_, ...)
+    #[arg(long)]
+    only_test_sessions: bool,
+
+    /// List the sessions that would be deleted without deleting them
+    #[arg(long)]
+    dry_run: bool,
+
+    /// Deletes in flight at once
+    #[arg(long, value_name = "N", default_value_t = 8, value_parser = clap::value_parser!(u64).range(1..))]
+    concurrency: u64,
+}
+
+#[derive(clap::Args)]
+struct ChaosArgs {
+    /// Seconds the chaos workload runs
+    #[arg(long, value_name = "SECS", default_value_t = 60, value_parser = clap::value_parser!(u64).range(1..))]
+    duration: u64,
+
+    /// Requests started per second during the chaos test
+    #[arg(long, value_name = "N", default_value_t = 10, value_parser = clap::value_parser!(u32).range(1..))]
+    rps: u32,
+
+    /// Relative weights of creates, GETs, and deletes in the chaos workload
+    #[arg(long, value_name = "CREATE:GET:DELETE", default_value = "1:3:1")]
+    mix: tests::chaos::Mix,
+
+    /// Seed for the workload and crash schedule (default: random; printed at
+    /// the start and recorded in the JSON report)
+    #[arg(long, value_name = "N")]
+    seed: Option<u64>,
+
+    /// Creates in the final burst, which must all succeed (default: max_workers
+    /// from /status)
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+    pool_size: Option<u64>,
+
+    /// Also write a self-contained HTML report to this path
+    #[arg(long, value_name = "PATH")]
+    html: Option<std::path::PathBuf>,
 }
 
 #[tokio::main]
 async fn main() {
-    let args = Args::parse();
-    let client = OrchestratorClient::new(&args.url);
-
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
-    println!("{}", "🧪 ORCHESTRATOR TEST SUITE".bold());
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
-
-    // Ensure orchestrator is reachable before running tests
-    match client.health().await {
-        Ok(_) => {}
-        Err(e) => {
-            eprintln!(
-                "\n{} Cannot reach orchestrator at {}: {e}",
+    let Cli {
+        connection,
+        output,
+        command,
+        run,
+        color,
+        no_color,
+        ascii,
+        verbose,
+        log_file,
+        github_annotations,
+        ..
+    } = parse_cli();
+    if output == OutputFormat::Json {
+        console::use_stderr();
+    }
+    let color = if no_color { ColorChoice::Never } else { color };
+    let no_color_env = std::env::var_os("NO_COLOR");
+    let style = |terminal| console::output_style(color, ascii, no_color_env.as_deref(), terminal);
+    console::set_style(style(console::is_terminal()));
+    // Logs always go to stderr, which may be redirected when stdout isn't.
+    let log_color = style(std::io::stderr().is_terminal()).color;
+    if let Err(e) = logging::init(verbose, log_file.as_deref(), log_color) {
+        errln!("{} {e}", "✗".red());
+        std::process::exit(2);
+    }
+    let command = command.unwrap_or_else(|| {
+        errln!(
+            "{}",
+            "⚠ running without a subcommand is deprecated; use `steel-tester run`".yellow()
+        );
+        Command::Run(run)
+    });
+    let client = connection.client();
+    let authenticated = connection.token.is_some();
+    let reports = Reports {
+        output,
+        github_annotations: github_annotations.unwrap_or_else(report::github::detected),
+    };
+
+    match command {
+        Command::Run(args) => run_suite(&client, &args, &reports, authenticated).await,
+        Command::List(args) => {
+            let seed = args.seed.unwrap_or_else(tests::util::random_seed);
+            let runner = suite(&args, seed, authenticated);
+            print_test_list(&runner.selected(), output);
+        }
+        Command::Bench(args) => {
+            if preflight(&client).await.is_err() {
+                std::process::exit(report::Outcome::Unreachable.exit_code());
+            }
+            let interrupt = interrupt::Interrupt::install();
+            let settings = bench::BenchSettings {
+                iterations: args.iterations as usize,
+                concurrency: args.concurrency.map(|n| n as usize),
+            };
+            let report = bench::run(&client, &settings, &interrupt).await;
+            if output == OutputFormat::Json {
+                println!("{}", report.to_json());
+            }
+            std::process::exit(report.exit_code);
+        }
+        Command::Cleanup(args) => {
+            if preflight(&client).await.is_err() {
+                std::process::exit(report::Outcome::Unreachable.exit_code());
+            }
+            let settings = cleanup::CleanupSettings {
+                only_test_sessions: args.only_test_sessions,
+                dry_run: args.dry_run,
+                concurrency: args.concurrency as usize,
+            };
+            std::process::exit(cleanup::run(&client, &settings).await);
+        }
+        Command::Chaos(args) => run_chaos(&client, &args, &reports).await,
+    }
+}
+
+/// Parse the command line on top of the environment and config file. Flags of
+/// `run` before a subcommand would be silently ignored, so they are a usage
+/// error. Exits after `--print-config`.
+fn parse_cli() -> Cli {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let mut cmd = config::with_env(Cli::command());
+    match ConfigFile::locate(&args) {
+        Ok(Some(file)) => {
+            let (configured, warnings) = file.apply(cmd);
+            cmd = configured;
+            for warning in warnings {
+                errln!(
+                    "{}",
+                    format!("⚠ {}: {warning}, ignored", file.path.display()).yellow()
+                );
+            }
+        }
+        Ok(None) => {}
+        Err(e) => cmd.error(clap::error::ErrorKind::Io, e).exit(),
+    }
+
+    let matches = match cmd.try_get_matches_from_mut(&args) {
+        Ok(matches) => matches,
+        Err(e) => config::explain_env_error(&cmd, &args, e).exit(),
+    };
+    if matches.subcommand().is_some() {
+        let run_flags = RunArgs::augment_args(clap::Command::new("run"));
+        let misplaced = run_flags.get_arguments().find(|arg| {
+            matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+        });
+        if let Some(arg) = misplaced {
+            cmd.error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!(
+                    "--{} belongs to `run`; pass it after the subcommand",
+                    arg.get_long().unwrap_or_default()
+                ),
+            )
+            .exit();
+        }
+    }
+    if matches.get_flag("print_config") {
+        print!("{}", config::resolved(&cmd, &matches));
+        std::process::exit(0);
+    }
+    Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit())
+}
+
+impl ConnectionArgs {
+    fn client(&self) -> OrchestratorClient {
+        let config = ClientConfig {
+            request_timeout: Duration::from_secs(self.request_timeout),
+            connect_timeout: self.connect_timeout.map(Duration::from_secs),
+            token: self.token.clone(),
+            ca_certs: self
+                .ca_cert
+                .as_deref()
+                .map(load_ca_certs)
+                .unwrap_or_default(),
+            insecure: self.insecure,
+            request_id_header: self.request_id_header.clone(),
+            retry: RetryPolicy::new(self.client_retries),
+        };
+        OrchestratorClient::with_config(&self.url, &config)
+    }
+}
+
+/// Build the runner with every test group, configured from `args`; the Auth
+/// group needs to know whether a token is sent. Exits with a usage error if
+/// the flags are inconsistent or select no tests.
+fn suite(args: &RunArgs, seed: u64, authenticated: bool) -> TestRunner {
+    let crud_settings = tests::crud::CrudSettings {
+        allow_numeric_lossy: args.allow_numeric_lossy,
+        clock_skew_tolerance: args.clock_skew_tolerance,
+        seed,
+        cold_start_budget: args.cold_start_budget,
+    };
+    let ttl_settings = tests::ttl::TtlSettings {
+        ttl: Duration::from_secs(args.ttl),
+        sweep_interval: Duration::from_secs(args.sweep_interval),
+        mode: args.ttl_mode,
+        bulk_sessions: args.bulk_sessions as usize,
+        expired_status: args
+            .expect_expired_status
+            .map(|code| reqwest::StatusCode::from_u16(code).expect("range-checked by clap")),
+    };
+    let recovery_settings = tests::recovery::RecoverySettings {
+        crash_workers: args.crash_workers as usize,
+        timeout: Duration::from_secs(args.recovery_timeout),
+        slo: args.recovery_slo,
+    };
+    let restart_settings = tests::restart::RestartSettings {
+        mode: args.persistence_mode,
+        timeout: Duration::from_secs(args.recovery_timeout),
+    };
+
+    let soak_settings = tests::soak::SoakSettings {
+        duration: args.soak_duration.map(Duration::from_secs),
+        rps: args.soak_rps,
+    };
+
+    let ttl_wait = ttl_settings.longest_test();
+    if ttl_wait >= Duration::from_secs(args.test_timeout) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ArgumentConflict,
+                format!(
+                    "TTL tests need up to {}s with this --ttl/--sweep-interval, which does not \
+                     fit in --test-timeout {}s; raise --test-timeout",
+                    ttl_wait.as_secs(),
+                    args.test_timeout
+                ),
+            )
+            .exit();
+    }
+
+    let mut runner = TestRunner::new(RunnerConfig {
+        parallel: args.parallel as usize,
+        test_timeout: Duration::from_secs(args.test_timeout),
+        retries: args.retries,
+        fail_fast: args.fail_fast,
+        repeat: args.repeat,
+        filter: args.filter.clone(),
+        groups: args.groups.clone(),
+        skip_groups: args.skip_groups.clone(),
+        tags: args.tags.clone(),
+        exclude_tags: args.exclude_tags.clone(),
+        strict_leaks: args.strict_leaks,
+        slow_threshold: args.slow_threshold,
+        artifacts: args.artifacts.clone(),
+        seed,
+        capabilities: Capabilities::default(),
+        require_all: args.require_all,
+        max_duration: args.max_duration.map(Duration::from_secs),
+        shuffle: args.shuffle,
+        shuffle_groups: args.shuffle_groups,
+        slo: args.slo.clone().unwrap_or_default(),
+    });
+    runner.add_group("CRUD Operations", tests::crud::tests(&crud_settings));
+    runner.add_group(
+        "Validation",
+        tests::validation::tests(
+            args.max_payload,
+            args.fuzz_max_size,
+            args.payload_policy,
+            seed,
+        ),
+    );
+    runner.add_group(
+        "Protocol",
+        tests::protocol::tests(args.require_idempotency, args.stall_timeout),
+    );
+    runner.add_group(
+        "Error Responses",
+        tests::errors::tests(args.pool_size.map(|n| n as usize)),
+    );
+    runner.add_group("Metrics", tests::metrics::tests());
+    runner.add_group_with_hooks("Health", tests::health::tests(), tests::health::hooks());
+    let stress_concurrency: Vec<usize> = args
+        .stress_concurrency
+        .iter()
+        .map(|&n| n as usize)
+        .collect();
+    // Each count names a test; a repeated one would name two alike.
+    let mut counts = HashSet::new();
+    if let Some(n) = stress_concurrency.iter().find(|&&n| !counts.insert(n)) {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                format!("--stress-concurrency {n} is given more than once"),
+            )
+            .exit();
+    }
+    runner.add_group("Concurrency", tests::concurrent::tests(&stress_concurrency));
+    runner.add_group("TTL Expiration", tests::ttl::tests(&ttl_settings));
+    runner.add_group("Recovery", tests::recovery::tests(&recovery_settings));
+    // Recovery times assume a settled pool; the crash storm leaves it respawning.
+    runner.keep_order("Recovery");
+    runner.add_group_with_hooks(
+        "Restart",
+        tests::restart::tests(&restart_settings),
+        tests::restart::hooks(),
+    );
+    runner.add_group_with_hooks(
+        "Shutdown",
+        tests::shutdown::tests(&tests::shutdown::ShutdownSettings {
+            pool_size: args.pool_size.map(|n| n as usize),
+            timeout: Duration::from_secs(args.recovery_timeout),
+        }),
+        tests::shutdown::hooks(),
+    );
+    runner.add_group_with_hooks(
+        tests::capacity::GROUP,
+        tests::capacity::tests(
+            args.pool_size.map(|n| n as usize),
+            args.churn_iterations as usize,
+        ),
+        tests::capacity::hooks(),
+    );
+    runner.add_group("Soak", tests::soak::tests(&soak_settings));
+    runner.add_group("Timeouts", tests::timeouts::tests());
+    runner.add_group("Auth", tests::auth::tests(authenticated));
+    runner.add_group(
+        "Rate Limiting",
+        tests::rate_limit::tests(&tests::rate_limit::RateLimitSettings {
+            rps: args.rate_limit_rps,
+        }),
+    );
+    runner.add_group("CORS", tests::cors::tests(args.cors_origin.as_deref()));
+
+    if let Err(e) = runner.validate_groups() {
+        Cli::command()
+            .error(clap::error::ErrorKind::InvalidValue, e)
+            .exit();
+    }
+    if runner.selected().is_empty() {
+        Cli::command()
+            .error(
+                clap::error::ErrorKind::ValueValidation,
+                "no tests match the given selection",
+            )
+            .exit();
+    }
+    runner
+}
+
+/// `steel-tester run`: the whole suite, with a summary and reports at the end.
+async fn run_suite(
+    client: &OrchestratorClient,
+    args: &RunArgs,
+    reports: &Reports,
+    authenticated: bool,
+) -> ! {
+    let started_at = chrono::Utc::now();
+    if !args.no_progress {
+        console::progress::enable();
+    }
+    let seed = args.seed.unwrap_or_else(tests::util::random_seed);
+    let mut runner = suite(args, seed, authenticated);
+    let baseline = args.baseline.as_deref().map(|path| {
+        report::baseline::Baseline::load(path).unwrap_or_else(|e| {
+            Cli::command()
+                .error(
+                    clap::error::ErrorKind::InvalidValue,
+                    format!("invalid --baseline: {e}"),
+                )
+                .exit()
+        })
+    });
+
+    print_banner("🧪 ORCHESTRATOR TEST SUITE", seed);
+    if let Err(e) = preflight(client).await {
+        let report = RunReport::unreachable(started_at, client.base_url(), seed, e.to_string());
+        finish(reports, args.html.as_deref(), &report);
+    }
+    runner.set_capabilities(probe_capabilities(client).await);
+
+    // Ctrl-C stops the run but still cleans up sessions
+    let interrupt = interrupt::Interrupt::install();
+    let client = client.with_stats(runner.stats().clone());
+    let mut report = runner.run(&client, &interrupt).await;
+    if let Some(baseline) = &baseline {
+        let comparison = report::baseline::compare(&report, baseline);
+        report::baseline::print(&comparison);
+        report.set_regressions(comparison, args.fail_on_regression);
+    }
+    print_summary(&report);
+    runner::print_slowest(&report, 5);
+    if let Some(path) = &args.save_baseline {
+        match report::baseline::save(&report, path) {
+            Ok(()) => outln!("💾 Baseline written to {}", path.display()),
+            Err(e) => errln!(
+                "{} failed to write baseline to {}: {e}",
                 "✗".red(),
-                args.url
-            );
-            std::process::exit(1);
+                path.display()
+            ),
         }
     }
+    finish(reports, args.html.as_deref(), &report);
+}
 
-    // Build the test runner with all test groups
-    let mut runner = TestRunner::new();
-    runner.add_group("CRUD Operations", tests::crud::tests());
-    runner.add_group("Concurrency", tests::concurrent::tests());
-    runner.add_group("TTL Expiration", tests::ttl::tests());
-    runner.add_group("Recovery", tests::recovery::tests());
+/// `steel-tester chaos`: the chaos test on its own, reported like a run.
+async fn run_chaos(client: &OrchestratorClient, args: &ChaosArgs, reports: &Reports) -> ! {
+    let started_at = chrono::Utc::now();
+    let seed = args.seed.unwrap_or_else(tests::util::random_seed);
+    // Without the crash endpoint there is nothing to test; fail, don't skip.
+    let mut runner = TestRunner::new(RunnerConfig {
+        seed,
+        require_all: true,
+        ..RunnerConfig::default()
+    });
+    runner.add_group(
+        "Chaos",
+        tests::chaos::tests(&tests::chaos::ChaosSettings {
+            duration: Duration::from_secs(args.duration),
+            rps: args.rps,
+            mix: args.mix,
+            seed,
+            pool_size: args.pool_size.map(|n| n as usize),
+        }),
+    );
 
-    // Run all tests
-    let (passed, total) = runner.run(&client).await;
+    print_banner("🐒 ORCHESTRATOR CHAOS TEST", seed);
+    if let Err(e) = preflight(client).await {
+        let report = RunReport::unreachable(started_at, client.base_url(), seed, e.to_string());
+        finish(reports, args.html.as_deref(), &report);
+    }
+    runner.set_capabilities(probe_capabilities(client).await);
+
+    let interrupt = interrupt::Interrupt::install();
+    let client = client.with_stats(runner.stats().clone());
+    let report = runner.run(&client, &interrupt).await;
+    print_summary(&report);
+    finish(reports, args.html.as_deref(), &report);
+}
+
+fn print_banner(title: &str, seed: u64) {
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
+    outln!("{}", title.bold());
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
+    outln!(
+        "{}",
+        format!("🎲 Seed {seed} (replay with --seed {seed})").bold()
+    );
+}
+
+/// Ensure the orchestrator is reachable and healthy before doing anything,
+/// explaining the failure if not.
+async fn preflight(client: &OrchestratorClient) -> Result<(), ClientError> {
+    let Err(e) = client.health_detailed().await else {
+        return Ok(());
+    };
+    let problem = match e {
+        ClientError::NotFound { .. } | ClientError::Http { .. } => "Orchestrator is unhealthy",
+        _ => "Cannot reach orchestrator",
+    };
+    errln!("\n{} {problem} at {}: {e}", "✗".red(), client.base_url());
+    if e.is_tls() {
+        errln!(
+            "  TLS handshake failed: pass --ca-cert <PATH> to trust the server's CA, \
+             or --insecure to skip certificate verification"
+        );
+    } else if e.is_connection_refused() {
+        errln!("  Connection refused: is the orchestrator running at that address?");
+    }
+    Err(e)
+}
+
+/// Probe the optional endpoints and say which are missing.
+async fn probe_capabilities(client: &OrchestratorClient) -> Capabilities {
+    let capabilities = Capabilities::probe(client).await;
+    let missing = capabilities.missing();
+    if !missing.is_empty() {
+        let missing: Vec<&str> = missing.iter().map(|c| c.endpoint()).collect();
+        outln!(
+            "{}",
+            format!(
+                "⚠ Not available: {} — tests needing them are unsupported",
+                missing.join(", ")
+            )
+            .yellow()
+        );
+    }
+    capabilities
+}
+
+fn print_summary(report: &RunReport) {
+    let (passed, total) = (report.totals.passed, report.totals.total);
+    let mut notes = Vec::new();
+    if report.totals.skipped > 0 {
+        notes.push(format!("{} skipped", report.totals.skipped));
+    }
+    if report.totals.unsupported > 0 {
+        notes.push(format!("{} unsupported", report.totals.unsupported));
+    }
+    if report.totals.expected_failures > 0 {
+        notes.push(format!(
+            "{} expected failures",
+            report.totals.expected_failures
+        ));
+    }
+    if report.totals.unexpected_passes > 0 {
+        notes.push(format!("{} XPASS", report.totals.unexpected_passes));
+    }
+    if report.totals.not_run > 0 {
+        notes.push(format!("{} not run", report.totals.not_run));
+    }
+    let over_budget = report.latency.iter().filter(|op| op.over_budget()).count();
+    if over_budget > 0 {
+        notes.push(format!("{over_budget} over latency budget"));
+    }
+    let regressions = report
+        .regressions
+        .as_ref()
+        .map_or(0, |comparison| comparison.regression_count());
+    if regressions > 0 {
+        notes.push(format!("{regressions} regressed"));
+    }
+    if report.client_retries > 0 {
+        notes.push(format!("{} requests retried", report.client_retries));
+    }
+    let skipped_note = if notes.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", notes.join(", "))
+    };
 
     // Print final summary
-    println!();
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
-    if passed == total {
-        println!(
+    outln!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
+    if passed == total && over_budget == 0 && report.outcome != report::Outcome::Regressed {
+        outln!(
             "{}",
-            format!("📊 RESULTS: {passed}/{total} passed").green().bold()
+            format!("📊 RESULTS: {passed}/{total} passed{skipped_note}")
+                .green()
+                .bold()
         );
     } else {
-        println!(
+        outln!(
             "{}",
-            format!("📊 RESULTS: {passed}/{total} passed").red().bold()
+            format!("📊 RESULTS: {passed}/{total} passed{skipped_note}")
+                .red()
+                .bold()
         );
     }
-    println!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
-    println!();
+    outln!("{}", "━━━━━━━━━━━━━━━━━━━━━━━━━━━━".bold());
+    outln!();
+}
 
-    if passed != total {
-        std::process::exit(1);
+/// Read every certificate from a PEM file, exiting with a usage error if the
+/// file is unreadable or holds no certificates.
+fn load_ca_certs(path: &std::path::Path) -> Vec<reqwest::Certificate> {
+    let loaded = std::fs::read(path)
+        .map_err(|e| format!("cannot read --ca-cert {}: {e}", path.display()))
+        .and_then(|pem| {
+            reqwest::Certificate::from_pem_bundle(&pem)
+                .map_err(|e| format!("invalid PEM in --ca-cert {}: {e}", path.display()))
+        });
+    match loaded {
+        Ok(certs) if !certs.is_empty() => certs,
+        Ok(_) => Cli::command()
+            .error(
+                clap::error::ErrorKind::InvalidValue,
+                format!("no certificates found in --ca-cert {}", path.display()),
+            )
+            .exit(),
+        Err(e) => Cli::command()
+            .error(clap::error::ErrorKind::InvalidValue, e)
+            .exit(),
+    }
+}
+
+/// Parse a byte count with an optional K or M (binary) suffix, e.g. "64K".
+fn parse_size(value: &str) -> Result<usize, String> {
+    let (digits, unit) = match value.char_indices().last() {
+        Some((i, 'k' | 'K')) => (&value[..i], 1024),
+        Some((i, 'm' | 'M')) => (&value[..i], 1024 * 1024),
+        _ => (value, 1),
+    };
+    let count: usize = digits
+        .parse()
+        .map_err(|_| format!("invalid size: {value}"))?;
+    count
+        .checked_mul(unit)
+        .filter(|&bytes| bytes > 0)
+        .ok_or_else(|| format!("size out of range: {value}"))
+}
+
+/// Parse a non-negative, possibly fractional number of seconds.
+fn parse_seconds(value: &str) -> Result<Duration, String> {
+    let secs: f64 = value
+        .parse()
+        .map_err(|_| format!("invalid number of seconds: {value}"))?;
+    Duration::try_from_secs_f64(secs)
+        .map_err(|_| format!("expected a non-negative duration, got {value}"))
+}
+
+/// Where a finished run's results go, besides the console.
+struct Reports {
+    output: OutputFormat,
+    github_annotations: bool,
+}
+
+/// Budgets from an SLO file: JSON if it ends in `.json`, TOML otherwise.
+fn parse_slo(path: &str) -> Result<latency::Budgets, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("cannot read {path}: {e}"))?;
+    let value = if path.ends_with(".json") {
+        serde_json::from_str(&text).map_err(|e| e.to_string())?
+    } else {
+        let table: toml::Table = text.parse().map_err(|e: toml::de::Error| e.to_string())?;
+        serde_json::to_value(table).map_err(|e| e.to_string())?
+    };
+    latency::budgets(&value)
+}
+
+/// Emit the requested reports and exit with the report's code.
+fn finish(reports: &Reports, html: Option<&std::path::Path>, report: &RunReport) -> ! {
+    if reports.github_annotations {
+        // Workflow commands are parsed from either stream; keep stdout for JSON.
+        for line in report::github::annotations(report) {
+            if console::to_stderr() {
+                eprintln!("{line}");
+            } else {
+                println!("{line}");
+            }
+        }
+    }
+    if reports.output == OutputFormat::Json {
+        println!("{}", report.to_json());
+    }
+    if let Some(path) = html {
+        match report::html::write(report, path) {
+            Ok(()) => outln!("📄 HTML report written to {}", path.display()),
+            Err(e) => errln!(
+                "{} failed to write HTML report to {}: {e}",
+                "✗".red(),
+                path.display()
+            ),
+        }
+    }
+    std::process::exit(report.exit_code);
+}
+
+/// Print the selected test inventory, one test per line or as a JSON array.
+fn print_test_list(selected: &[(&str, &str)], format: OutputFormat) {
+    match format {
+        OutputFormat::Text => {
+            for (group, name) in selected {
+                println!("{group} / {name}");
+            }
+        }
+        OutputFormat::Json => {
+            let entries: Vec<_> = selected
+                .iter()
+                .map(|(group, name)| serde_json::json!({"group": group, "name": name}))
+                .collect();
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&entries).expect("test list is always serializable")
+            );
+        }
     }
 }
@@ -0,0 +1,358 @@
+//! In-memory stand-in for the orchestrator, for testing the suite itself.
+//!
+//! It implements the API the tests expect — including the gaps they mark as
+//! expected failures, such as plain-text 404s and no PUT or listing — so a
+//! run against it passes. Workers are slots in a fixed pool: a session holds
+//! one until it is deleted, expires, or its worker is crashed through
+//! POST /debug/crash-worker, and creates beyond the pool queue for a slot.
+
+use crate::tests::util::{self, Rng};
+use axum::body::Bytes;
+use axum::extract::rejection::BytesRejection;
+use axum::extract::{DefaultBodyLimit, Path, Query, State};
+use axum::http::{header, HeaderMap, Method, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::any;
+use axum::{Json, Router};
+use chrono::{SecondsFormat, Utc};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::net::{SocketAddr, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::{oneshot, Semaphore};
+use tokio::task::JoinHandle;
+
+/// Pool size, expiry, and limits of a mock orchestrator. The defaults match
+/// the real orchestrator's.
+#[derive(Debug, Clone)]
+pub struct MockConfig {
+    /// Workers, and so sessions that can exist at once.
+    pub max_workers: usize,
+    /// Idle time after which a session expires.
+    pub ttl: Duration,
+    /// How often expired sessions are swept.
+    pub sweep_interval: Duration,
+    /// How long a create waits for a free worker before a 503.
+    pub queue_timeout: Duration,
+    /// Largest create body accepted; bigger ones get 413.
+    pub max_body: usize,
+}
+
+impl Default for MockConfig {
+    fn default() -> Self {
+        Self {
+            max_workers: 10,
+            ttl: Duration::from_secs(60),
+            sweep_interval: Duration::from_secs(5),
+            queue_timeout: Duration::from_secs(300),
+            max_body: 10 * 1024 * 1024,
+        }
+    }
+}
+
+/// A mock orchestrator serving on an ephemeral localhost port. Dropping it
+/// stops the server.
+pub struct MockServer {
+    addr: SocketAddr,
+    shutdown: Option<oneshot::Sender<()>>,
+    server: JoinHandle<()>,
+    sweeper: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// Bind 127.0.0.1 on a free port and start serving.
+    pub async fn start(config: MockConfig) -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let server = axum::Server::from_tcp(listener).map_err(std::io::Error::other)?;
+
+        let state = Arc::new(Mock::new(config));
+        let sweeper = tokio::spawn(sweep(Arc::clone(&state)));
+        let app = Router::new()
+            .route("/sessions", any(sessions))
+            .route("/sessions/:id", any(session))
+            .route("/health", any(health))
+            .route("/status", any(status))
+            .route("/debug/crash-worker", any(crash_worker))
+            .fallback(not_found)
+            .layer(DefaultBodyLimit::max(state.config.max_body))
+            .with_state(state);
+
+        let (shutdown, stopped) = oneshot::channel::<()>();
+        let server = tokio::spawn(async move {
+            let _ = server
+                .serve(app.into_make_service())
+                .with_graceful_shutdown(async {
+                    let _ = stopped.await;
+                })
+                .await;
+        });
+        Ok(Self {
+            addr,
+            shutdown: Some(shutdown),
+            server,
+            sweeper,
+        })
+    }
+
+    /// Base URL to point an `OrchestratorClient` at.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Stop accepting requests and wait for in-flight ones to finish.
+    pub async fn stop(mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        let _ = (&mut self.server).await;
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.server.abort();
+        self.sweeper.abort();
+    }
+}
+
+/// Shared server state. `slots` holds one permit per idle worker; creates
+/// wait on it, which is what queues them when the pool is full.
+struct Mock {
+    config: MockConfig,
+    slots: Semaphore,
+    pool: Mutex<Pool>,
+}
+
+struct Pool {
+    sessions: HashMap<String, Entry>,
+    /// Session held by each worker, if any.
+    workers: Vec<Option<String>>,
+    ids: Rng,
+}
+
+struct Entry {
+    session: Value,
+    worker: usize,
+    last_accessed: Instant,
+}
+
+impl Mock {
+    fn new(config: MockConfig) -> Self {
+        Self {
+            slots: Semaphore::new(config.max_workers),
+            pool: Mutex::new(Pool {
+                sessions: HashMap::new(),
+                workers: vec![None; config.max_workers],
+                ids: Rng::new(util::random_seed()),
+            }),
+            config,
+        }
+    }
+
+    fn pool(&self) -> std::sync::MutexGuard<'_, Pool> {
+        self.pool.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Remove a session and free its worker. False if there was no such session.
+    fn release(&self, id: &str) -> bool {
+        let mut pool = self.pool();
+        let Some(entry) = pool.sessions.remove(id) else {
+            return false;
+        };
+        pool.workers[entry.worker] = None;
+        self.slots.add_permits(1);
+        true
+    }
+}
+
+impl Pool {
+    /// A random version 4 UUID.
+    fn new_id(&mut self) -> String {
+        let hi = (self.ids.next_u64() & !0xf000) | 0x4000;
+        let lo = (self.ids.next_u64() & !(0b11 << 62)) | (0b10 << 62);
+        format!(
+            "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+            hi >> 32,
+            (hi >> 16) & 0xffff,
+            hi & 0xffff,
+            lo >> 48,
+            lo & 0xffff_ffff_ffff
+        )
+    }
+}
+
+/// Expire sessions idle for longer than the TTL, every sweep interval.
+async fn sweep(mock: Arc<Mock>) {
+    let mut ticks = tokio::time::interval(mock.config.sweep_interval);
+    loop {
+        ticks.tick().await;
+        let expired: Vec<String> = mock
+            .pool()
+            .sessions
+            .iter()
+            .filter(|(_, entry)| entry.last_accessed.elapsed() > mock.config.ttl)
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in expired {
+            mock.release(&id);
+        }
+    }
+}
+
+/// POST /sessions. Bad bodies get JSON errors, as the workers send them.
+async fn sessions(
+    State(mock): State<Arc<Mock>>,
+    method: Method,
+    headers: HeaderMap,
+    body: Result<Bytes, BytesRejection>,
+) -> Response {
+    if method != Method::POST {
+        return plain_error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    }
+    let body = match body {
+        Ok(body) => body,
+        Err(e) => return json_error(e.status(), &e.body_text()),
+    };
+    let content_type = headers
+        .get(header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if !essence.eq_ignore_ascii_case("application/json") {
+        return json_error(
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            "Content-Type must be application/json",
+        );
+    }
+    let data = match serde_json::from_slice::<Value>(&body) {
+        Ok(data @ Value::Object(_)) => data,
+        Ok(_) => return json_error(StatusCode::BAD_REQUEST, "body must be a JSON object"),
+        Err(e) => return json_error(StatusCode::BAD_REQUEST, &format!("invalid JSON: {e}")),
+    };
+
+    // A create whose client gives up is dropped here, so it never takes a
+    // worker later.
+    match tokio::time::timeout(mock.config.queue_timeout, mock.slots.acquire()).await {
+        Ok(Ok(permit)) => permit.forget(),
+        _ => {
+            return plain_error(
+                StatusCode::SERVICE_UNAVAILABLE,
+                "no workers available (queue timeout)",
+            )
+        }
+    }
+    let mut pool = mock.pool();
+    let id = pool.new_id();
+    let worker = pool
+        .workers
+        .iter()
+        .position(Option::is_none)
+        .expect("a permit guarantees an idle worker");
+    pool.workers[worker] = Some(id.clone());
+    let session = json!({
+        "id": id,
+        "created_at": Utc::now().to_rfc3339_opts(SecondsFormat::Millis, true),
+        "data": data,
+    });
+    pool.sessions.insert(
+        id,
+        Entry {
+            session: session.clone(),
+            worker,
+            last_accessed: Instant::now(),
+        },
+    );
+    (StatusCode::CREATED, Json(session)).into_response()
+}
+
+/// GET and DELETE /sessions/:id. A GET counts as activity for the TTL.
+async fn session(
+    State(mock): State<Arc<Mock>>,
+    method: Method,
+    Path(id): Path<String>,
+) -> Response {
+    match method {
+        Method::GET => {
+            let mut pool = mock.pool();
+            match pool.sessions.get_mut(&id) {
+                Some(entry) => {
+                    entry.last_accessed = Instant::now();
+                    Json(entry.session.clone()).into_response()
+                }
+                None => plain_error(StatusCode::NOT_FOUND, "session not found"),
+            }
+        }
+        Method::DELETE if mock.release(&id) => StatusCode::NO_CONTENT.into_response(),
+        Method::DELETE => plain_error(StatusCode::NOT_FOUND, "session not found"),
+        _ => plain_error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed"),
+    }
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+/// GET /status, shaped like the orchestrator's.
+async fn status(State(mock): State<Arc<Mock>>) -> Response {
+    let pool = mock.pool();
+    let workers: Vec<Value> = pool
+        .workers
+        .iter()
+        .enumerate()
+        .map(|(i, session)| {
+            json!({
+                "id": i + 1,
+                "port": 9000 + i,
+                "state": if session.is_some() { "busy" } else { "available" },
+                "session_id": session.as_deref().unwrap_or_default(),
+            })
+        })
+        .collect();
+    let busy = pool.sessions.len();
+    Json(json!({
+        "active_sessions": busy,
+        "worker_count": mock.config.max_workers,
+        "available_workers": mock.config.max_workers - busy,
+        "min_workers": mock.config.max_workers,
+        "max_workers": mock.config.max_workers,
+        "workers": workers,
+    }))
+    .into_response()
+}
+
+/// POST /debug/crash-worker?session_id=:id. The crashed worker restarts at
+/// once, so only the session is lost.
+async fn crash_worker(
+    State(mock): State<Arc<Mock>>,
+    method: Method,
+    Query(query): Query<HashMap<String, String>>,
+) -> Response {
+    if method != Method::POST {
+        return plain_error(StatusCode::METHOD_NOT_ALLOWED, "method not allowed");
+    }
+    match query.get("session_id").filter(|id| !id.is_empty()) {
+        None => plain_error(StatusCode::BAD_REQUEST, "session_id required"),
+        Some(id) if mock.release(id) => "worker killed".into_response(),
+        Some(_) => plain_error(StatusCode::NOT_FOUND, "session not found"),
+    }
+}
+
+async fn not_found() -> Response {
+    plain_error(StatusCode::NOT_FOUND, "404 page not found")
+}
+
+/// An error as Go's `http.Error` writes it.
+fn plain_error(status: StatusCode, message: &str) -> Response {
+    (
+        status,
+        [(header::CONTENT_TYPE, "text/plain; charset=utf-8")],
+        format!("{message}\n"),
+    )
+        .into_response()
+}
+
+fn json_error(status: StatusCode, message: &str) -> Response {
+    (status, Json(json!({ "error": message }))).into_response()
+}
@@ -0,0 +1,224 @@
+//! Comparison against an earlier run's report (`--baseline`), so a CI job
+//! sees what got worse since the last good run rather than only what fails.
+
+use super::{GroupReport, RunReport, TestStatus};
+use crate::console::outln;
+use colored::Colorize;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// How much slower than its baseline a test must be to count as regressed.
+pub const SLOWDOWN_RATIO: f64 = 1.5;
+
+/// Slowdowns smaller than this are ignored: a 2ms test taking 4ms is noise.
+pub const SLOWDOWN_FLOOR_MS: u64 = 10;
+
+/// The parts of a saved `RunReport` the comparison needs. Any JSON report
+/// works, whether written by `--save-baseline` or `--output json`.
+#[derive(Debug, Deserialize)]
+pub struct Baseline {
+    groups: Vec<GroupReport>,
+}
+
+impl Baseline {
+    /// Parse a JSON run report.
+    pub fn from_json(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("not a run report: {e}"))
+    }
+
+    /// Read and parse the report at `path`.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| format!("cannot read {}: {e}", path.display()))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Write `report` where a later run's `--baseline` can read it.
+pub fn save(report: &RunReport, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, report.to_json())
+}
+
+/// Each test's results across iterations, keyed by "Group / Test".
+fn history(groups: &[GroupReport]) -> BTreeMap<String, History> {
+    let mut history = BTreeMap::<String, History>::new();
+    for group in groups {
+        for test in &group.tests {
+            history
+                .entry(format!("{} / {}", group.name, test.name))
+                .or_default()
+                .add(test.status, test.duration_ms, test.attempts);
+        }
+    }
+    history
+}
+
+/// One test's results across the iterations of a run.
+#[derive(Default)]
+struct History {
+    passed: u32,
+    failed: u32,
+    retried_pass: bool,
+    total_ms: u64,
+}
+
+impl History {
+    fn add(&mut self, status: TestStatus, duration_ms: u64, attempts: u32) {
+        match status {
+            TestStatus::Passed | TestStatus::ExpectedFailure => self.passed += 1,
+            TestStatus::Failed | TestStatus::UnexpectedPass => self.failed += 1,
+            _ => return,
+        }
+        self.retried_pass |= status == TestStatus::Passed && attempts > 1;
+        self.total_ms += duration_ms;
+    }
+
+    fn executed(&self) -> bool {
+        self.passed + self.failed > 0
+    }
+
+    fn failing(&self) -> bool {
+        self.failed > 0 && self.passed == 0
+    }
+
+    /// Passed only after a retry, or passed and failed across iterations.
+    fn flaky(&self) -> bool {
+        self.retried_pass || (self.passed > 0 && self.failed > 0)
+    }
+
+    fn mean_ms(&self) -> u64 {
+        self.total_ms / u64::from(self.passed + self.failed)
+    }
+}
+
+/// A test that took markedly longer than in the baseline.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct Slowdown {
+    /// "Group / Test".
+    pub test: String,
+    pub baseline_ms: u64,
+    pub duration_ms: u64,
+}
+
+/// What changed since the baseline. Tests are named "Group / Test"; only
+/// tests that executed in both runs can regress.
+#[derive(Debug, Clone, Default, Serialize)]
+#[non_exhaustive]
+pub struct Comparison {
+    /// Failing now, passed in the baseline.
+    pub newly_failing: Vec<String>,
+    /// Passing in both, but more than `SLOWDOWN_RATIO` times slower now.
+    pub slower: Vec<Slowdown>,
+    /// Flaky now, stable in the baseline.
+    pub newly_flaky: Vec<String>,
+    /// Not in the baseline: new tests, or ones it didn't select.
+    pub added: Vec<String>,
+    /// In the baseline but not in this run.
+    pub removed: Vec<String>,
+}
+
+impl Comparison {
+    pub fn has_regressions(&self) -> bool {
+        !self.newly_failing.is_empty() || !self.slower.is_empty() || !self.newly_flaky.is_empty()
+    }
+
+    pub fn regression_count(&self) -> usize {
+        self.newly_failing.len() + self.slower.len() + self.newly_flaky.len()
+    }
+}
+
+/// Compare `report` with `baseline`. Durations are means over iterations.
+pub fn compare(report: &RunReport, baseline: &Baseline) -> Comparison {
+    let current = history(&report.groups);
+    let before = history(&baseline.groups);
+
+    let mut comparison = Comparison {
+        removed: before
+            .keys()
+            .filter(|name| !current.contains_key(*name))
+            .cloned()
+            .collect(),
+        ..Comparison::default()
+    };
+    for (name, now) in &current {
+        let Some(then) = before.get(name) else {
+            comparison.added.push(name.clone());
+            continue;
+        };
+        if !now.executed() || !then.executed() {
+            continue;
+        }
+        if now.failing() && !then.failing() {
+            comparison.newly_failing.push(name.clone());
+            continue;
+        }
+        if now.flaky() && !then.flaky() {
+            comparison.newly_flaky.push(name.clone());
+        }
+        let (baseline_ms, duration_ms) = (then.mean_ms(), now.mean_ms());
+        if !now.failing()
+            && !then.failing()
+            && duration_ms as f64 > baseline_ms as f64 * SLOWDOWN_RATIO
+            && duration_ms - baseline_ms >= SLOWDOWN_FLOOR_MS
+        {
+            comparison.slower.push(Slowdown {
+                test: name.clone(),
+                baseline_ms,
+                duration_ms,
+            });
+        }
+    }
+    comparison
+}
+
+/// Print the regressions section, then the added and removed tests.
+pub fn print(comparison: &Comparison) {
+    outln!();
+    if comparison.has_regressions() {
+        outln!(
+            "{}",
+            format!(
+                "📉 Regressions since the baseline ({}):",
+                comparison.regression_count()
+            )
+            .red()
+            .bold()
+        );
+    } else {
+        outln!("{}", "📈 No regressions since the baseline".green().bold());
+    }
+    for name in &comparison.newly_failing {
+        outln!("  {} {name}: now failing", "✗".red());
+    }
+    for slowdown in &comparison.slower {
+        outln!(
+            "  {} {}: {}ms → {}ms",
+            "✗".red(),
+            slowdown.test,
+            slowdown.baseline_ms,
+            slowdown.duration_ms
+        );
+    }
+    for name in &comparison.newly_flaky {
+        outln!("  {} {name}: now flaky", "✗".red());
+    }
+    if !comparison.added.is_empty() {
+        outln!("  new since the baseline: {}", listing(&comparison.added));
+    }
+    if !comparison.removed.is_empty() {
+        outln!("  not in this run: {}", listing(&comparison.removed));
+    }
+}
+
+/// Up to five names, then how many more, so a `--group` run against a full
+/// baseline doesn't print the whole suite.
+fn listing(names: &[String]) -> String {
+    const SHOWN: usize = 5;
+    let mut text = names[..names.len().min(SHOWN)].join(", ");
+    if names.len() > SHOWN {
+        text.push_str(&format!(" … and {} more", names.len() - SHOWN));
+    }
+    text
+}
@@ -0,0 +1,112 @@
+//! GitHub Actions workflow commands for a run, so failures show up as
+//! annotations on the job instead of only in its log.
+
+use super::{Outcome, RunReport, TestStatus};
+
+/// Whether the run is a GitHub Actions job, which sets `GITHUB_ACTIONS=true`.
+pub fn detected() -> bool {
+    std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// Escape a command's message, which ends at the first newline. Everything
+/// after the `::` closing the properties is message, so `::` and `,` stay.
+pub fn escape_data(message: &str) -> String {
+    message
+        .replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// Escape a command property such as `title`, where `:` and `,` also
+/// delimit.
+pub fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+fn command(kind: &str, title: &str, message: &str) -> String {
+    format!(
+        "::{kind} title={}::{}",
+        escape_property(title),
+        escape_data(message)
+    )
+}
+
+/// One `::error` per failed test, operation over its latency budget or run
+/// that never started, and a `::warning` per flaky pass and leaked session.
+/// Tests slower than in the baseline get a `::warning`, or an `::error`
+/// under `--fail-on-regression`.
+pub fn annotations(report: &RunReport) -> Vec<String> {
+    let repeated = report.groups.iter().any(|group| group.iteration > 1);
+    let mut lines = Vec::new();
+    if let Some(error) = &report.error {
+        lines.push(command("error", "steel-tester", error));
+    }
+    for group in &report.groups {
+        for test in &group.tests {
+            let mut title = format!("{} / {}", group.name, test.name);
+            if repeated {
+                title.push_str(&format!(" (iteration {})", group.iteration));
+            }
+            let error = test.error.as_deref().unwrap_or("failed");
+            match test.status {
+                TestStatus::Failed | TestStatus::UnexpectedPass => {
+                    lines.push(command("error", &title, error))
+                }
+                _ if test.is_flaky() => lines.push(command(
+                    "warning",
+                    &title,
+                    &format!("flaky: passed on attempt {}", test.attempts),
+                )),
+                _ => {}
+            }
+        }
+    }
+    for record in report.repeat.iter().filter(|record| record.is_flaky()) {
+        lines.push(command(
+            "warning",
+            &format!("{} / {}", record.group, record.name),
+            &format!(
+                "flaky: passed {} and failed {} of {} iterations",
+                record.passed,
+                record.failed,
+                record.passed + record.failed
+            ),
+        ));
+    }
+    for violation in crate::latency::violations(&report.latency) {
+        lines.push(command("error", "Latency budget", &violation));
+    }
+    if let Some(comparison) = &report.regressions {
+        let kind = if report.outcome == Outcome::Regressed {
+            "error"
+        } else {
+            "warning"
+        };
+        for slowdown in &comparison.slower {
+            lines.push(command(
+                kind,
+                &slowdown.test,
+                &format!(
+                    "slower than the baseline: {}ms, was {}ms",
+                    slowdown.duration_ms, slowdown.baseline_ms
+                ),
+            ));
+        }
+    }
+    for leaked in &report.leaked_sessions {
+        let fate = if leaked.swept {
+            "deleted by the sweep"
+        } else {
+            "still alive"
+        };
+        lines.push(command(
+            "warning",
+            "Leaked session",
+            &format!(
+                "{} created by {} was left behind ({fate})",
+                leaked.id, leaked.test
+            ),
+        ));
+    }
+    lines
+}
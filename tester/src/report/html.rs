@@ -0,0 +1,184 @@
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::Duration;
+
+use super::{Outcome, RunReport, TestResult, TestStatus};
+use crate::console::format_duration;
+
+/// Inline stylesheet; the report must open without any external assets.
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; margin: 2rem; color: #1f2328; }
+h1 { margin-bottom: 0.25rem; }
+.meta { color: #59636e; margin-bottom: 1.5rem; }
+.summary { display: flex; gap: 1rem; margin-bottom: 2rem; }
+.summary div { padding: 0.75rem 1rem; border-radius: 6px; background: #f6f8fa; }
+.summary strong { display: block; font-size: 1.5rem; }
+.outcome-passed { border-left: 4px solid #1a7f37; }
+.outcome-failed { border-left: 4px solid #cf222e; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 2rem; }
+th, td { text-align: left; padding: 0.4rem 0.75rem; border-bottom: 1px solid #d1d9e0; vertical-align: top; }
+th { background: #f6f8fa; }
+td.duration { text-align: right; white-space: nowrap; font-variant-numeric: tabular-nums; }
+.status { font-weight: 600; white-space: nowrap; }
+.passed { color: #1a7f37; }
+.failed, .unexpected_pass { color: #cf222e; }
+.expected_failure, .unsupported { color: #9a6700; }
+.skipped, .not_run { color: #59636e; }
+details summary { cursor: pointer; }
+pre { white-space: pre-wrap; background: #f6f8fa; padding: 0.5rem; border-radius: 6px; }
+.warning { color: #9a6700; }
+";
+
+/// Render `report` as a self-contained HTML document and write it to `path`.
+pub fn write(report: &RunReport, path: &Path) -> std::io::Result<()> {
+    std::fs::write(path, render(report))
+}
+
+/// Render `report` as a self-contained HTML document.
+pub fn render(report: &RunReport) -> String {
+    let t = &report.totals;
+    let outcome_class = if report.outcome == Outcome::Passed {
+        "outcome-passed"
+    } else {
+        "outcome-failed"
+    };
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str("<title>Orchestrator test report</title>\n");
+    let _ = writeln!(html, "<style>{STYLE}</style>\n</head>\n<body>");
+    html.push_str("<h1>Orchestrator test report</h1>\n");
+    let _ = writeln!(
+        html,
+        "<p class=\"meta\">{} &middot; started {} &middot; took {} &middot; seed {}</p>",
+        escape(&report.url),
+        report.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+        format_duration(Duration::from_millis(report.duration_ms)),
+        report.seed,
+    );
+
+    let _ = writeln!(html, "<div class=\"summary {outcome_class}\">");
+    for (label, value) in [
+        ("passed", format!("{}/{}", t.passed, t.total)),
+        ("failed", t.failed.to_string()),
+        ("skipped", t.skipped.to_string()),
+        ("unsupported", t.unsupported.to_string()),
+        ("expected failures", t.expected_failures.to_string()),
+        ("XPASS", t.unexpected_passes.to_string()),
+        ("not run", t.not_run.to_string()),
+        ("flaky", t.flaky.to_string()),
+    ] {
+        let _ = writeln!(html, "<div><strong>{value}</strong>{label}</div>");
+    }
+    html.push_str("</div>\n");
+
+    if let Some(error) = &report.error {
+        let _ = writeln!(html, "<p class=\"failed\">{}</p>", escape(error));
+    }
+
+    let repeated = report.groups.iter().any(|g| g.iteration > 1);
+    for group in &report.groups {
+        if repeated {
+            let _ = writeln!(
+                html,
+                "<h2>{} <small>(iteration {})</small></h2>",
+                escape(&group.name),
+                group.iteration
+            );
+        } else {
+            let _ = writeln!(html, "<h2>{}</h2>", escape(&group.name));
+        }
+        for warning in &group.warnings {
+            let _ = writeln!(html, "<p class=\"warning\">⚠ {}</p>", escape(warning));
+        }
+        html.push_str(
+            "<table>\n<tr><th>Test</th><th>Status</th><th>Duration</th><th>Details</th></tr>\n",
+        );
+        for test in &group.tests {
+            render_test(&mut html, test);
+        }
+        html.push_str("</table>\n");
+    }
+
+    if !report.leaked_sessions.is_empty() {
+        html.push_str("<h2>Leaked sessions</h2>\n<table>\n<tr><th>Session</th><th>Created by</th><th>Swept</th></tr>\n");
+        for leak in &report.leaked_sessions {
+            let _ = writeln!(
+                html,
+                "<tr><td><code>{}</code></td><td>{}</td><td>{}</td></tr>",
+                escape(&leak.id),
+                escape(&leak.test),
+                if leak.swept { "yes" } else { "no" }
+            );
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+/// One table row; errors and warnings are collapsed behind `<details>` toggles.
+fn render_test(html: &mut String, test: &TestResult) {
+    let (label, class) = status_label(test.status);
+    let duration = if test.attempts > 0 {
+        format_duration(Duration::from_millis(test.duration_ms))
+    } else {
+        String::new()
+    };
+    let details = match (&test.error, &test.skip_reason) {
+        (Some(error), _) => format!(
+            "<details><summary>{}</summary><pre>{}</pre></details>",
+            escape(first_line(error)),
+            escape(error)
+        ),
+        (None, Some(reason)) => escape(reason),
+        (None, None) if test.attempts > 1 => format!("passed on attempt {}", test.attempts),
+        (None, None) => String::new(),
+    };
+    let details = match test.warnings.as_slice() {
+        [] => details,
+        warnings => format!(
+            "{details}<details><summary>{} warning(s)</summary><pre>{}</pre></details>",
+            warnings.len(),
+            escape(&warnings.join("\n"))
+        ),
+    };
+    let _ = writeln!(
+        html,
+        "<tr><td>{}</td><td class=\"status {class}\">{label}</td><td class=\"duration\">{duration}</td><td>{details}</td></tr>",
+        escape(&test.name)
+    );
+}
+
+fn status_label(status: TestStatus) -> (&'static str, &'static str) {
+    match status {
+        TestStatus::Passed => ("✓ passed", "passed"),
+        TestStatus::Failed => ("✗ failed", "failed"),
+        TestStatus::Skipped => ("skipped", "skipped"),
+        TestStatus::Unsupported => ("unsupported", "unsupported"),
+        TestStatus::NotRun => ("not run", "not_run"),
+        TestStatus::ExpectedFailure => ("✗ expected", "expected_failure"),
+        TestStatus::UnexpectedPass => ("XPASS", "unexpected_pass"),
+    }
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines().next().unwrap_or_default()
+}
+
+/// Escape text for use in HTML element content and attribute values.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
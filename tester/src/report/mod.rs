@@ -0,0 +1,431 @@
+pub mod baseline;
+pub mod github;
+pub mod html;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::latency::OperationLatency;
+use crate::stats::EndpointStats;
+use baseline::Comparison;
+
+/// Final status of a single test case.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum TestStatus {
+    Passed,
+    Failed,
+    Skipped,
+    /// Skipped because the orchestrator lacks an endpoint the test needs.
+    Unsupported,
+    /// Selected, but never started because the run stopped early.
+    NotRun,
+    /// Failed as expected (`TestCase::expected_failure`); not counted against the run.
+    ExpectedFailure,
+    /// Marked as an expected failure but passed; fails the run.
+    UnexpectedPass,
+}
+
+impl TestStatus {
+    /// Whether this status makes the run fail.
+    pub fn is_failure(self) -> bool {
+        matches!(self, TestStatus::Failed | TestStatus::UnexpectedPass)
+    }
+}
+
+/// Result of a single test case.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct TestResult {
+    pub name: String,
+    pub status: TestStatus,
+    pub duration_ms: u64,
+    pub error: Option<String>,
+    pub skip_reason: Option<String>,
+    /// Number of times the test was executed (0 if skipped).
+    pub attempts: u32,
+    /// Measurements recorded by the final attempt (`context::record_metric`).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>,
+    /// Problems the final attempt tolerated without failing (`context::warn`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+    /// Behaviour the final attempt observed and accepted (`context::note`).
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notes: Vec<String>,
+}
+
+impl TestResult {
+    /// Result for a test that was never executed, with the reason why.
+    pub fn not_executed(name: &str, status: TestStatus, reason: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            duration_ms: 0,
+            error: None,
+            skip_reason: Some(reason.to_string()),
+            attempts: 0,
+            metrics: BTreeMap::new(),
+            warnings: Vec::new(),
+            notes: Vec::new(),
+        }
+    }
+
+    /// A test that only passed after at least one retry.
+    pub fn is_flaky(&self) -> bool {
+        self.status == TestStatus::Passed && self.attempts > 1
+    }
+}
+
+/// Results for one registered group, in registration order.
+#[derive(Debug, Serialize, Deserialize)]
+#[non_exhaustive]
+pub struct GroupReport {
+    pub name: String,
+    /// 1-based iteration this group result belongs to (see `--repeat`).
+    pub iteration: u32,
+    pub tests: Vec<TestResult>,
+    /// Non-fatal problems such as a failed teardown hook.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub warnings: Vec<String>,
+}
+
+impl GroupReport {
+    pub fn new(name: &str, iteration: u32, tests: Vec<TestResult>) -> Self {
+        Self {
+            name: name.to_string(),
+            iteration,
+            tests,
+            warnings: Vec::new(),
+        }
+    }
+}
+
+/// Pass/fail record of one test across all iterations of a repeated run.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct RepeatRecord {
+    pub group: String,
+    pub name: String,
+    pub passed: u32,
+    pub failed: u32,
+}
+
+impl RepeatRecord {
+    /// Passed in some iterations and failed in others.
+    pub fn is_flaky(&self) -> bool {
+        self.passed > 0 && self.failed > 0
+    }
+}
+
+/// A session a test created and left behind, found after the run.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct LeakedSession {
+    pub id: String,
+    /// "Group / Test" name of the test that created the session.
+    pub test: String,
+    /// Whether the sweep managed to delete it.
+    pub swept: bool,
+}
+
+impl LeakedSession {
+    pub fn new(id: &str, test: &str, swept: bool) -> Self {
+        Self {
+            id: id.to_string(),
+            test: test.to_string(),
+            swept,
+        }
+    }
+}
+
+/// Aggregate counts across all groups.
+#[derive(Debug, Default, Serialize)]
+#[non_exhaustive]
+pub struct Totals {
+    pub passed: usize,
+    pub failed: usize,
+    pub skipped: usize,
+    /// Tests skipped for a missing capability; not included in `skipped`.
+    pub unsupported: usize,
+    pub expected_failures: usize,
+    pub unexpected_passes: usize,
+    /// Selected tests that never started because the run stopped early.
+    pub not_run: usize,
+    /// Tests the run meant to execute, across iterations: everything not
+    /// skipped or unsupported.
+    pub planned: usize,
+    /// Planned tests that did execute (`planned - not_run`).
+    pub executed: usize,
+    /// Passed tests that needed more than one attempt.
+    pub flaky: usize,
+    /// Tests counted towards the result (passed + failed + unexpected passes).
+    pub total: usize,
+}
+
+/// Exit code table for `--help`; keep in sync with `Outcome::exit_code`.
+pub const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0    all tests passed and cleanup succeeded
+  1    one or more tests failed
+  2    the orchestrator was unreachable (also used for invalid arguments)
+  3    tests passed, but cleanup failed or sessions leaked under --strict-leaks
+  4    the --max-duration budget ran out before every test executed
+  5    tests passed, but an operation's p95 latency exceeded its --slo budget
+  6    tests passed, but regressed against --baseline under --fail-on-regression
+  130  interrupted by Ctrl-C";
+
+/// Overall outcome of the run, the basis for the process exit code.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Outcome {
+    /// Every test passed.
+    Passed,
+    /// At least one test failed.
+    TestsFailed,
+    /// Every test passed, but cleanup was incomplete: a session could not be
+    /// swept, a teardown hook failed, or (under `--strict-leaks`) tests left
+    /// sessions behind.
+    LeakedResources,
+    /// The orchestrator could not be reached; no tests were run.
+    Unreachable,
+    /// The run was stopped by Ctrl-C before every test executed.
+    Interrupted,
+    /// The run's time budget ran out before every test executed.
+    BudgetExceeded,
+    /// Every test passed, but an operation's p95 latency exceeded its
+    /// `--slo` budget.
+    SloExceeded,
+    /// Every test passed, but some got slower or flaky since the
+    /// `--baseline` run, under `--fail-on-regression`.
+    Regressed,
+}
+
+impl Outcome {
+    /// Process exit code for this outcome; see `EXIT_CODES_HELP`.
+    pub fn exit_code(self) -> i32 {
+        match self {
+            Outcome::Passed => 0,
+            Outcome::TestsFailed => 1,
+            Outcome::Unreachable => 2,
+            Outcome::LeakedResources => 3,
+            Outcome::BudgetExceeded => 4,
+            Outcome::SloExceeded => 5,
+            Outcome::Regressed => 6,
+            Outcome::Interrupted => crate::interrupt::INTERRUPTED_EXIT_CODE,
+        }
+    }
+}
+
+/// Why a run ended before every selected test executed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum RunStop {
+    /// Ctrl-C.
+    Interrupted,
+    /// `--max-duration` ran out.
+    BudgetExceeded,
+}
+
+/// Structured result of a full run, serializable as JSON.
+#[derive(Debug, Serialize)]
+#[non_exhaustive]
+pub struct RunReport {
+    pub started_at: DateTime<Utc>,
+    /// Wall-clock time from `started_at` until the report was built.
+    pub duration_ms: u64,
+    pub url: String,
+    /// Seed of the run's randomized tests; `--seed` replays it.
+    pub seed: u64,
+    pub groups: Vec<GroupReport>,
+    pub totals: Totals,
+    /// Per-test records across iterations; only populated under `--repeat`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub repeat: Vec<RepeatRecord>,
+    /// "Group / Test" names of the executed tests in the order they started;
+    /// only populated under `--shuffle` or `--shuffle-groups`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub order: Vec<String>,
+    /// Sessions still alive after the run; see `--strict-leaks`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub leaked_sessions: Vec<LeakedSession>,
+    /// Per-operation latencies of the run's client calls; see `--slo`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub latency: Vec<OperationLatency>,
+    /// Every exchange with the orchestrator, counted per endpoint.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stats: Vec<EndpointStats>,
+    /// Changes since the `--baseline` run, if one was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regressions: Option<Comparison>,
+    /// Requests the client sent again after a transient failure; see
+    /// `--client-retries`. A growing count means a degrading environment.
+    pub client_retries: u64,
+    pub outcome: Outcome,
+    pub exit_code: i32,
+    /// Set when the run was aborted before any test executed.
+    pub error: Option<String>,
+}
+
+impl RunReport {
+    /// Build a report from finished groups, computing totals and outcome.
+    /// Swept leaks only fail the run when `strict_leaks` is set; a run
+    /// stopped early by Ctrl-C or its time budget is reported as such
+    /// regardless of results so far.
+    pub fn new(
+        started_at: DateTime<Utc>,
+        url: &str,
+        seed: u64,
+        groups: Vec<GroupReport>,
+        leaked_sessions: Vec<LeakedSession>,
+        strict_leaks: bool,
+        stopped: Option<RunStop>,
+    ) -> Self {
+        let mut totals = Totals::default();
+        for result in groups.iter().flat_map(|g| &g.tests) {
+            match result.status {
+                TestStatus::Passed => {
+                    totals.passed += 1;
+                    if result.is_flaky() {
+                        totals.flaky += 1;
+                    }
+                }
+                TestStatus::Failed => totals.failed += 1,
+                TestStatus::Skipped => totals.skipped += 1,
+                TestStatus::Unsupported => totals.unsupported += 1,
+                TestStatus::NotRun => totals.not_run += 1,
+                TestStatus::ExpectedFailure => totals.expected_failures += 1,
+                TestStatus::UnexpectedPass => totals.unexpected_passes += 1,
+            }
+        }
+        totals.total = totals.passed + totals.failed + totals.unexpected_passes;
+        totals.executed = totals.total + totals.expected_failures;
+        totals.planned = totals.executed + totals.not_run;
+
+        let outcome = if stopped == Some(RunStop::Interrupted) {
+            Outcome::Interrupted
+        } else if stopped == Some(RunStop::BudgetExceeded) {
+            Outcome::BudgetExceeded
+        } else if totals.failed > 0 || totals.unexpected_passes > 0 {
+            Outcome::TestsFailed
+        } else if leaked_sessions.iter().any(|l| !l.swept)
+            || groups.iter().any(|g| !g.warnings.is_empty())
+            || (strict_leaks && !leaked_sessions.is_empty())
+        {
+            Outcome::LeakedResources
+        } else {
+            Outcome::Passed
+        };
+
+        let repeat = if groups.iter().any(|g| g.iteration > 1) {
+            Self::repeat_records(&groups)
+        } else {
+            Vec::new()
+        };
+
+        Self {
+            started_at,
+            duration_ms: elapsed_ms(started_at),
+            url: url.to_string(),
+            seed,
+            groups,
+            totals,
+            repeat,
+            order: Vec::new(),
+            leaked_sessions,
+            latency: Vec::new(),
+            stats: Vec::new(),
+            regressions: None,
+            client_retries: 0,
+            outcome,
+            exit_code: outcome.exit_code(),
+            error: None,
+        }
+    }
+
+    /// Report for a run that could not reach the orchestrator.
+    pub fn unreachable(started_at: DateTime<Utc>, url: &str, seed: u64, error: String) -> Self {
+        Self {
+            started_at,
+            duration_ms: elapsed_ms(started_at),
+            url: url.to_string(),
+            seed,
+            groups: Vec::new(),
+            totals: Totals::default(),
+            repeat: Vec::new(),
+            order: Vec::new(),
+            leaked_sessions: Vec::new(),
+            latency: Vec::new(),
+            stats: Vec::new(),
+            regressions: None,
+            client_retries: 0,
+            outcome: Outcome::Unreachable,
+            exit_code: Outcome::Unreachable.exit_code(),
+            error: Some(error),
+        }
+    }
+
+    /// Attach the run's latencies. An operation over its budget turns an
+    /// otherwise passing run into `Outcome::SloExceeded`.
+    pub fn set_latency(&mut self, latency: Vec<OperationLatency>) {
+        if self.outcome == Outcome::Passed && latency.iter().any(|op| op.over_budget()) {
+            self.outcome = Outcome::SloExceeded;
+            self.exit_code = self.outcome.exit_code();
+        }
+        self.latency = latency;
+    }
+
+    /// Attach the comparison with a baseline. With `fail_on_regression`,
+    /// a regression turns an otherwise passing run into `Outcome::Regressed`.
+    pub fn set_regressions(&mut self, comparison: Comparison, fail_on_regression: bool) {
+        if fail_on_regression && self.outcome == Outcome::Passed && comparison.has_regressions() {
+            self.outcome = Outcome::Regressed;
+            self.exit_code = self.outcome.exit_code();
+        }
+        self.regressions = Some(comparison);
+    }
+
+    /// Per-test pass/fail counts across iterations, in registration order.
+    /// Tests that never executed are left out.
+    fn repeat_records(groups: &[GroupReport]) -> Vec<RepeatRecord> {
+        let mut records: Vec<RepeatRecord> = Vec::new();
+        for group in groups {
+            for test in &group.tests {
+                let (passed, failed) = match test.status {
+                    TestStatus::Passed | TestStatus::ExpectedFailure => (1, 0),
+                    TestStatus::Failed | TestStatus::UnexpectedPass => (0, 1),
+                    TestStatus::Skipped | TestStatus::Unsupported | TestStatus::NotRun => continue,
+                };
+                match records
+                    .iter_mut()
+                    .find(|r| r.group == group.name && r.name == test.name)
+                {
+                    Some(record) => {
+                        record.passed += passed;
+                        record.failed += failed;
+                    }
+                    None => records.push(RepeatRecord {
+                        group: group.name.clone(),
+                        name: test.name.clone(),
+                        passed,
+                        failed,
+                    }),
+                }
+            }
+        }
+        records
+    }
+
+    /// Serialize the report as pretty-printed JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).expect("run report is always serializable")
+    }
+}
+
+/// Milliseconds elapsed since `since`, clamped at zero.
+fn elapsed_ms(since: DateTime<Utc>) -> u64 {
+    (Utc::now() - since).num_milliseconds().max(0) as u64
+}
@@ -0,0 +1,99 @@
+//! Client-side retries (`--client-retries`), so a transient 502 from an
+//! ingress in front of the orchestrator doesn't fail an otherwise healthy
+//! run. Only requests that are safe to repeat are retried.
+
+use reqwest::header::{HeaderMap, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+/// Gateway statuses that usually mean "try again", not "you asked wrong".
+pub const TRANSIENT_STATUSES: [StatusCode; 3] = [
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+    StatusCode::GATEWAY_TIMEOUT,
+];
+
+/// How often and how patiently a request is retried.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Retries after the first attempt; zero turns retrying off.
+    pub max_retries: u32,
+    /// Backoff before the first retry, doubled for each one after it.
+    pub base_delay: Duration,
+    /// Backoff never grows past this.
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: 0,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Up to `max_retries` retries with the default backoff.
+    pub fn new(max_retries: u32) -> Self {
+        Self {
+            max_retries,
+            ..Self::default()
+        }
+    }
+
+    /// Backoff before retry `retry` (0 for the first): `base_delay` doubled
+    /// `retry` times, capped at `max_delay`.
+    pub fn backoff(&self, retry: u32) -> Duration {
+        self.base_delay
+            .checked_mul(1 << retry.min(31))
+            .map_or(self.max_delay, |delay| delay.min(self.max_delay))
+    }
+
+    /// `backoff` with jitter, so clients that failed together don't retry
+    /// together: half of it is kept and the other half scaled by `jitter`,
+    /// which is drawn from `[0, 1)`.
+    pub fn delay(&self, retry: u32, jitter: f64) -> Duration {
+        let backoff = self.backoff(retry);
+        backoff / 2 + (backoff / 2).mul_f64(jitter.clamp(0.0, 1.0))
+    }
+}
+
+/// Whether a request can be sent again without changing its effect. GET,
+/// HEAD and DELETE can; a create only if it carries an Idempotency-Key, so
+/// the orchestrator can tell the repeat from a second create.
+pub fn is_repeatable(method: &Method, path: &str, has_idempotency_key: bool) -> bool {
+    match *method {
+        Method::GET | Method::HEAD | Method::DELETE => true,
+        Method::POST => path == "/sessions" && has_idempotency_key,
+        _ => false,
+    }
+}
+
+/// Whether a response status is worth retrying.
+pub fn is_transient_status(status: StatusCode) -> bool {
+    TRANSIENT_STATUSES.contains(&status)
+}
+
+/// Whether a request that got no response is worth retrying: it could not
+/// connect, or timed out.
+pub fn is_transient_error(error: &reqwest::Error) -> bool {
+    error.is_connect() || error.is_timeout()
+}
+
+/// How long a `Retry-After` header asks the client to wait: delay-seconds
+/// or an HTTP date, which counts as no wait once it has passed. None if the
+/// header is absent or malformed.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    Some(
+        (date.with_timezone(&chrono::Utc) - chrono::Utc::now())
+            .to_std()
+            .unwrap_or_default(),
+    )
+}
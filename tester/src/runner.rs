@@ -1,57 +1,1116 @@
 use colored::Colorize;
+use futures::stream::{self, StreamExt};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
 use std::future::Future;
+use std::path::{Path, PathBuf};
 use std::pin::Pin;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::Instrument;
 
-use crate::client::OrchestratorClient;
+use crate::artifacts::RequestLog;
+use crate::capabilities::{Capabilities, Capability};
+use crate::client::{ApiClient, OrchestratorClient};
+use crate::console::{self, outln};
+use crate::context::{self, Observations};
+use crate::interrupt::Interrupt;
+use crate::latency::{self, Budgets};
+use crate::report::{GroupReport, LeakedSession, RunReport, RunStop, TestResult, TestStatus};
+use crate::stats::{self, Stats};
+use crate::tests::util::Rng;
+
+/// Boxed future returned by a test case. Must be `Send` so the runner can
+/// drive several test cases concurrently.
+pub type TestFuture<'a> = Pin<Box<dyn Future<Output = Result<(), String>> + Send + 'a>>;
+
+/// Async test body: borrows the client and resolves to Ok(()) on success.
+/// The suite's tests take an `OrchestratorClient`; any `ApiClient` will do
+/// for the runner itself.
+pub type TestFn<C = OrchestratorClient> = Box<dyn Fn(&C) -> TestFuture<'_> + Send + Sync>;
 
 /// A single test case: a name and an async closure that returns Ok(()) on success.
-pub struct TestCase {
+pub struct TestCase<C = OrchestratorClient> {
     pub name: String,
-    pub func: Box<
-        dyn Fn(&OrchestratorClient) -> Pin<Box<dyn Future<Output = Result<(), String>> + '_>>
-            + Send
-            + Sync,
-    >,
+    pub func: TestFn<C>,
+    /// Overrides the runner's default per-test timeout.
+    pub timeout: Option<Duration>,
+    /// Whether a failure may be retried under `--retries`. Disable for tests
+    /// with side effects that must not be repeated blindly.
+    pub retryable: bool,
+    /// Free-form labels such as `slow` or `destructive`, used by `--tag` filters.
+    pub tags: Vec<&'static str>,
+    /// Marks a known failure with its tracking reason. A failure is then not
+    /// counted against the run, and an unexpected pass fails it.
+    pub expected_failure: Option<String>,
+    /// Always skip with this reason, e.g. when a required option is missing.
+    pub skip: Option<String>,
+    /// Optional endpoints the test needs; it is skipped as unsupported when
+    /// the orchestrator lacks one, unless `RunnerConfig::require_all`.
+    pub requires: Vec<Capability>,
+    /// Runs before every other test case of the suite; see `run_first`.
+    pub first: bool,
+}
+
+impl<C> TestCase<C> {
+    pub fn new(
+        name: impl Into<String>,
+        func: impl Fn(&C) -> TestFuture<'_> + Send + Sync + 'static,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            func: Box::new(func),
+            timeout: None,
+            retryable: true,
+            tags: Vec::new(),
+            expected_failure: None,
+            skip: None,
+            requires: Vec::new(),
+            first: false,
+        }
+    }
+
+    /// One test case per value, named by putting the value in place of the
+    /// `{}` in `template`: "Stress: {} simultaneous creates" over 10 and 100
+    /// gives "Stress: 10 simultaneous creates" and "Stress: 100 simultaneous
+    /// creates". They come back in the order of `values` and are selected,
+    /// listed and reported as tests of their own; set their timeouts and
+    /// tags on each. Two values that display alike would give two tests one
+    /// name, so that panics.
+    pub fn parameterized<P>(
+        template: &str,
+        values: impl IntoIterator<Item = P>,
+        func: impl Fn(&C, P) -> TestFuture<'_> + Send + Sync + 'static,
+    ) -> Vec<Self>
+    where
+        P: fmt::Display + Clone + Send + Sync + 'static,
+    {
+        assert!(
+            template.contains("{}"),
+            "parameterized test {template:?} has no {{}} for its value"
+        );
+        let func = Arc::new(func);
+        let mut names = HashSet::new();
+        values
+            .into_iter()
+            .map(|value| {
+                let name = template.replacen("{}", &value.to_string(), 1);
+                assert!(
+                    names.insert(name.clone()),
+                    "parameterized test {name:?} would be registered twice"
+                );
+                let func = Arc::clone(&func);
+                Self::new(name, move |client| func(client, value.clone()))
+            })
+            .collect()
+    }
+
+    /// Give this test case its own timeout instead of the runner default.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Attach tags used by `--tag` / `--exclude-tag` selection.
+    pub fn with_tags(mut self, tags: &[&'static str]) -> Self {
+        self.tags.extend_from_slice(tags);
+        self
+    }
+
+    /// Mark this test case as expected to fail, e.g. because of a known bug.
+    pub fn expected_failure(mut self, reason: impl Into<String>) -> Self {
+        self.expected_failure = Some(reason.into());
+        self
+    }
+
+    /// Skip this test case unconditionally with the given reason.
+    pub fn skip(mut self, reason: impl Into<String>) -> Self {
+        self.skip = Some(reason.into());
+        self
+    }
+
+    /// Declare an optional endpoint this test case needs.
+    pub fn requires(mut self, capability: Capability) -> Self {
+        self.requires.push(capability);
+        self
+    }
+
+    /// Never retry this test case, regardless of `--retries`.
+    pub fn not_retryable(mut self) -> Self {
+        self.retryable = false;
+        self
+    }
+
+    /// Run this test case before any other, whatever `--shuffle` and
+    /// `--shuffle-groups` say, and on its own even under `--parallel`: its
+    /// group goes first and it leads the group. For measurements that need
+    /// an orchestrator nothing else has touched yet.
+    pub fn run_first(mut self) -> Self {
+        self.first = true;
+        self
+    }
+}
+
+/// Optional async hooks run before and after a group's test cases.
+pub struct GroupHooks<C = OrchestratorClient> {
+    /// Runs before the group; on failure the whole group is skipped.
+    pub setup: Option<TestFn<C>>,
+    /// Runs after the group even if tests failed; errors are reported as warnings.
+    pub teardown: Option<TestFn<C>>,
+}
+
+impl<C> Default for GroupHooks<C> {
+    fn default() -> Self {
+        Self {
+            setup: None,
+            teardown: None,
+        }
+    }
+}
+
+/// A named group of test cases with its hooks.
+struct Group<C> {
+    name: &'static str,
+    tests: Vec<TestCase<C>>,
+    hooks: GroupHooks<C>,
+    /// Exempt from `--shuffle`: the tests depend on running in sequence.
+    ordered: bool,
+}
+
+/// Options controlling how the runner executes test cases.
+pub struct RunnerConfig {
+    /// Maximum number of test cases run concurrently within a group (1 = sequential).
+    pub parallel: usize,
+    /// Default time limit for a single test case.
+    pub test_timeout: Duration,
+    /// Extra attempts given to a failing retryable test before it counts as failed.
+    pub retries: u32,
+    /// Stop starting new tests after the first failure.
+    pub fail_fast: bool,
+    /// Number of times the whole selection is run.
+    pub repeat: u32,
+    /// Only run test cases whose name contains this substring (case-insensitive).
+    pub filter: Option<String>,
+    /// Only run these groups (all groups when empty). Matched case-insensitively.
+    pub groups: Vec<String>,
+    /// Never run these groups. Matched case-insensitively.
+    pub skip_groups: Vec<String>,
+    /// Only run test cases carrying at least one of these tags (all when empty).
+    pub tags: Vec<String>,
+    /// Never run test cases carrying any of these tags.
+    pub exclude_tags: Vec<String>,
+    /// Fail the run if tests leave sessions behind.
+    pub strict_leaks: bool,
+    /// Tests taking longer than this have their duration highlighted.
+    pub slow_threshold: Option<Duration>,
+    /// Write the HTTP exchanges of failed tests under this directory.
+    pub artifacts: Option<PathBuf>,
+    /// Seed the randomized tests were built with, recorded in the report.
+    pub seed: u64,
+    /// Optional endpoints the orchestrator serves; see `Capabilities::probe`.
+    pub capabilities: Capabilities,
+    /// Fail tests whose capabilities are missing instead of skipping them.
+    pub require_all: bool,
+    /// Stop starting tests, and retries, once the run has taken this long.
+    pub max_duration: Option<Duration>,
+    /// Run each group's tests in a random order drawn from `seed`.
+    pub shuffle: bool,
+    /// Run the groups in a random order drawn from `seed`.
+    pub shuffle_groups: bool,
+    /// p95 latency budgets per client operation; exceeding one fails an
+    /// otherwise passing run.
+    pub slo: Budgets,
+}
+
+impl Default for RunnerConfig {
+    /// The CLI's defaults: one test at a time, five-minute test timeout, one
+    /// pass, no selection filters, a random seed, and every capability
+    /// assumed present.
+    fn default() -> Self {
+        Self {
+            parallel: 1,
+            test_timeout: Duration::from_secs(300),
+            retries: 0,
+            fail_fast: false,
+            repeat: 1,
+            filter: None,
+            groups: Vec::new(),
+            skip_groups: Vec::new(),
+            tags: Vec::new(),
+            exclude_tags: Vec::new(),
+            strict_leaks: false,
+            slow_threshold: None,
+            artifacts: None,
+            seed: crate::tests::util::random_seed(),
+            capabilities: Capabilities::default(),
+            require_all: false,
+            max_duration: None,
+            shuffle: false,
+            shuffle_groups: false,
+            slo: Budgets::new(),
+        }
+    }
+}
+
+const FAIL_FAST_REASON: &str = "not run (fail-fast)";
+const INTERRUPTED_REASON: &str = "not run (interrupted)";
+const BUDGET_REASON: &str = "not run (budget exceeded)";
+const TAG_SKIP_PREFIX: &str = "excluded by tag";
+
+/// What happened to a single test case during a run.
+enum Outcome {
+    Ran {
+        result: Result<(), String>,
+        attempts: u32,
+        /// HTTP exchanges of the final attempt, when recording artifacts.
+        requests: Option<RequestLog>,
+        /// Measurements, warnings and notes recorded by the final attempt.
+        observations: Observations,
+    },
+    Skipped(String),
+    /// Not run because the orchestrator lacks this capability.
+    Unsupported(Capability),
+    /// Not started because the run's time budget ran out first.
+    OverBudget,
+}
+
+/// What ends a run before every test has executed, apart from failures
+/// under fail-fast: Ctrl-C, which cancels the tests in flight, and the time
+/// budget, which lets them finish.
+struct Stop<'a> {
+    interrupt: &'a Interrupt,
+    deadline: Option<Instant>,
+}
+
+impl Stop<'_> {
+    fn over_budget(&self) -> bool {
+        self.deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+    }
 }
 
 /// Collects and runs test cases, tracking pass/fail counts.
-pub struct TestRunner {
-    groups: Vec<(&'static str, Vec<TestCase>)>,
+pub struct TestRunner<C = OrchestratorClient> {
+    groups: Vec<Group<C>>,
+    config: RunnerConfig,
+    stats: Stats,
 }
 
-impl TestRunner {
-    pub fn new() -> Self {
-        Self { groups: Vec::new() }
+impl<C: ApiClient> TestRunner<C> {
+    pub fn new(config: RunnerConfig) -> Self {
+        Self {
+            groups: Vec::new(),
+            config,
+            stats: Stats::default(),
+        }
+    }
+
+    /// The run's per-endpoint stats. Give them to the client with
+    /// `OrchestratorClient::with_stats` so its exchanges show up in the
+    /// stats table and the report.
+    pub fn stats(&self) -> &Stats {
+        &self.stats
+    }
+
+    /// Record which optional endpoints the orchestrator serves, once probed.
+    pub fn set_capabilities(&mut self, capabilities: Capabilities) {
+        self.config.capabilities = capabilities;
     }
 
     /// Register a named group of test cases.
-    pub fn add_group(&mut self, name: &'static str, tests: Vec<TestCase>) {
-        self.groups.push((name, tests));
+    pub fn add_group(&mut self, name: &'static str, tests: Vec<TestCase<C>>) {
+        self.add_group_with_hooks(name, tests, GroupHooks::default());
+    }
+
+    /// Register a named group of test cases with setup/teardown hooks.
+    pub fn add_group_with_hooks(
+        &mut self,
+        name: &'static str,
+        tests: Vec<TestCase<C>>,
+        hooks: GroupHooks<C>,
+    ) {
+        self.groups.push(Group {
+            name,
+            tests,
+            hooks,
+            ordered: false,
+        });
+    }
+
+    /// Keep a registered group's tests in registration order under
+    /// `config.shuffle`, for tests that genuinely depend on their sequence.
+    pub fn keep_order(&mut self, name: &str) {
+        for group in self.groups.iter_mut().filter(|group| group.name == name) {
+            group.ordered = true;
+        }
+    }
+
+    /// The groups in the order one iteration runs them, each with its tests
+    /// in order, shuffled as configured. `run_first` tests and their groups
+    /// are then moved to the front.
+    fn run_order(&self, rng: &mut Rng) -> Vec<(&Group<C>, Vec<&TestCase<C>>)> {
+        let mut groups: Vec<&Group<C>> = self.groups.iter().collect();
+        if self.config.shuffle_groups {
+            rng.shuffle(&mut groups);
+        }
+        groups.sort_by_key(|group| !group.tests.iter().any(|test| test.first));
+        groups
+            .into_iter()
+            .map(|group| {
+                let mut tests: Vec<&TestCase<C>> = group.tests.iter().collect();
+                if self.config.shuffle && !group.ordered {
+                    rng.shuffle(&mut tests);
+                }
+                tests.sort_by_key(|test| !test.first);
+                (group, tests)
+            })
+            .collect()
+    }
+
+    /// Check that every group named in `--group`/`--skip-group` exists.
+    pub fn validate_groups(&self) -> Result<(), String> {
+        let unknown: Vec<&String> = self
+            .config
+            .groups
+            .iter()
+            .chain(&self.config.skip_groups)
+            .filter(|wanted| {
+                !self
+                    .groups
+                    .iter()
+                    .any(|group| group.name.eq_ignore_ascii_case(wanted))
+            })
+            .collect();
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        let available: Vec<&str> = self.groups.iter().map(|group| group.name).collect();
+        Err(format!(
+            "unknown group(s) {:?}; available groups: {}",
+            unknown,
+            available.join(", ")
+        ))
+    }
+
+    /// (group, test) names of registered test cases that pass the selection
+    /// filters, in registration order.
+    pub fn selected(&self) -> Vec<(&'static str, &str)> {
+        self.groups
+            .iter()
+            .filter(|group| self.group_skip_reason(group.name).is_none())
+            .flat_map(|group| group.tests.iter().map(move |test| (group.name, test)))
+            .filter(|(_, test)| self.skip_reason(test).is_none())
+            .map(|(name, test)| (name, test.name.as_str()))
+            .collect()
+    }
+
+    /// Why a whole group is excluded from this run, or None if it should run.
+    fn group_skip_reason(&self, name: &str) -> Option<String> {
+        let matches = |wanted: &String| name.eq_ignore_ascii_case(wanted);
+        if self.config.skip_groups.iter().any(matches) {
+            return Some("excluded by --skip-group".to_string());
+        }
+        if !self.config.groups.is_empty() && !self.config.groups.iter().any(matches) {
+            return Some("not selected by --group".to_string());
+        }
+        None
+    }
+
+    /// Why a test case is excluded from this run, or None if it should run.
+    fn skip_reason(&self, test: &TestCase<C>) -> Option<String> {
+        if let Some(reason) = &test.skip {
+            return Some(reason.clone());
+        }
+        if let Some(filter) = &self.config.filter {
+            if !test.name.to_lowercase().contains(&filter.to_lowercase()) {
+                return Some(format!("does not match filter \"{filter}\""));
+            }
+        }
+        let has_tag = |wanted: &String| test.tags.iter().any(|t| t.eq_ignore_ascii_case(wanted));
+        if let Some(tag) = self.config.exclude_tags.iter().find(|t| has_tag(t)) {
+            return Some(format!("{TAG_SKIP_PREFIX} \"{tag}\""));
+        }
+        if !self.config.tags.is_empty() && !self.config.tags.iter().any(has_tag) {
+            return Some(format!("{TAG_SKIP_PREFIX} (--tag)"));
+        }
+        None
+    }
+
+    /// The first capability a test needs that the orchestrator lacks.
+    fn missing_capability(&self, test: &TestCase<C>) -> Option<Capability> {
+        test.requires
+            .iter()
+            .copied()
+            .find(|capability| !self.config.capabilities.has(*capability))
+    }
+
+    /// How many of `tests` a run would execute, for the progress line.
+    fn planned<'t>(&self, tests: impl Iterator<Item = &'t TestCase<C>>) -> usize
+    where
+        C: 't,
+    {
+        tests
+            .filter(|test| self.skip_reason(test).is_none())
+            .filter(|test| self.missing_capability(test).is_none())
+            .count()
+    }
+
+    /// Results for tests that were selected but never started. Tests excluded by
+    /// the selection filters are still reported as skipped.
+    fn not_run(&self, tests: &[&TestCase<C>], iteration: u32, reason: &str) -> Vec<TestResult> {
+        tests
+            .iter()
+            .filter_map(|test| match self.skip_reason(test) {
+                Some(_) if iteration > 1 => None,
+                Some(skip) => Some(TestResult::not_executed(
+                    &test.name,
+                    TestStatus::Skipped,
+                    &skip,
+                )),
+                None => Some(TestResult::not_executed(
+                    &test.name,
+                    TestStatus::NotRun,
+                    reason,
+                )),
+            })
+            .collect()
+    }
+
+    /// Run a test case, retrying failures up to `config.retries` extra times.
+    async fn run_test(
+        &self,
+        group_name: &str,
+        test: &TestCase<C>,
+        client: &C,
+        stop: &Stop<'_>,
+    ) -> Outcome {
+        // Retrying an expected failure would only hunt for a pass.
+        let max_attempts = if test.retryable && test.expected_failure.is_none() {
+            self.config.retries + 1
+        } else {
+            1
+        };
+
+        let mut attempts = 0;
+        loop {
+            attempts += 1;
+            let name = format!("{group_name} / {}", test.name);
+            let requests = self
+                .config
+                .artifacts
+                .as_ref()
+                .map(|_| RequestLog::default());
+            let observations = Observations::default();
+            let span = tracing::info_span!("test", group = %group_name, name = %test.name, attempt = attempts);
+            let result = async {
+                tracing::info!("started");
+                let start = Instant::now();
+                let attempt = self.run_attempt(test, client);
+                let result =
+                    context::scope(name, requests.clone(), Some(observations.clone()), attempt)
+                        .await;
+                let elapsed_ms = start.elapsed().as_millis() as u64;
+                match &result {
+                    Ok(()) => tracing::info!(elapsed_ms, "passed"),
+                    Err(e) => tracing::info!(elapsed_ms, error = %e, "failed"),
+                }
+                result
+            }
+            .instrument(span)
+            .await;
+            if result.is_ok() || attempts >= max_attempts || stop.over_budget() {
+                return Outcome::Ran {
+                    result,
+                    attempts,
+                    requests,
+                    observations,
+                };
+            }
+        }
     }
 
-    /// Run all test groups sequentially and print results.
-    /// Returns (passed, total).
-    pub async fn run(&self, client: &OrchestratorClient) -> (usize, usize) {
-        let mut passed = 0usize;
-        let mut total = 0usize;
+    /// Run a single attempt of a test case, failing it if it exceeds its time limit.
+    async fn run_attempt(&self, test: &TestCase<C>, client: &C) -> Result<(), String> {
+        let limit = test.timeout.unwrap_or(self.config.test_timeout);
+        match tokio::time::timeout(limit, (test.func)(client)).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("timed out after {}s", limit.as_secs())),
+        }
+    }
+
+    /// Run all test groups sequentially and print results, repeating the
+    /// whole selection `config.repeat` times. On `interrupt`, the test in
+    /// flight is cancelled and the remaining tests are reported as not run;
+    /// past `config.max_duration`, tests in flight finish first.
+    pub async fn run(&self, client: &C, interrupt: &Interrupt) -> RunReport {
+        let started_at = chrono::Utc::now();
+        let stop = Stop {
+            interrupt,
+            deadline: self.config.max_duration.map(|limit| Instant::now() + limit),
+        };
+        let repeat = self.config.repeat.max(1);
+        let mut groups = Vec::with_capacity(self.groups.len() * repeat as usize);
+        // Why the remaining tests are not run, once the run stops early.
+        let mut stopped: Option<&str> = None;
+        let mut rng = Rng::derived(self.config.seed, "shuffle");
+        let planned: usize = self
+            .groups
+            .iter()
+            .filter(|group| self.group_skip_reason(group.name).is_none())
+            .map(|group| self.planned(group.tests.iter()))
+            .sum();
+        let progress = console::progress::begin(planned * repeat as usize, self.config.parallel);
+        // Tests of the groups done so far that were planned to run.
+        let mut settled = 0;
 
-        println!();
+        for iteration in 1..=repeat {
+            outln!();
+            if repeat > 1 {
+                outln!("{}", format!("▸ Iteration {iteration}/{repeat}").bold());
+            }
 
-        for (_group_name, tests) in &self.groups {
-            for test in tests {
-                total += 1;
-                match (test.func)(client).await {
-                    Ok(()) => {
-                        passed += 1;
-                        println!("{} {}", "✓".green(), test.name);
+            for (group, tests) in self.run_order(&mut rng) {
+                let Group {
+                    name: group_name,
+                    hooks,
+                    ..
+                } = group;
+                let tests = tests.as_slice();
+                if let Some(reason) = self.group_skip_reason(group_name) {
+                    // Selection doesn't change between iterations; report skips once.
+                    if iteration > 1 {
+                        continue;
                     }
-                    Err(e) => {
-                        println!("{} {}: {}", "✗".red(), test.name.red(), e);
+                    outln!(
+                        "{}",
+                        format!("- {group_name} ({} skipped: {reason})", tests.len()).dimmed()
+                    );
+                    let tests = tests
+                        .iter()
+                        .map(|t| TestResult::not_executed(&t.name, TestStatus::Skipped, &reason))
+                        .collect();
+                    groups.push(GroupReport::new(group_name, iteration, tests));
+                    continue;
+                }
+
+                if interrupt.is_set() {
+                    stopped = Some(INTERRUPTED_REASON);
+                } else if stopped.is_none() && stop.over_budget() {
+                    stopped = Some(BUDGET_REASON);
+                }
+                settled += self.planned(tests.iter().copied());
+                if let Some(reason) = stopped {
+                    let tests = self.not_run(tests, iteration, reason);
+                    groups.push(GroupReport::new(group_name, iteration, tests));
+                    console::progress::settle(settled);
+                    continue;
+                }
+
+                let group = self
+                    .run_group_with_hooks(group_name, tests, hooks, iteration, client, &stop)
+                    .await;
+                console::progress::settle(settled);
+                if self.config.fail_fast && group.tests.iter().any(|t| t.status.is_failure()) {
+                    stopped = Some(FAIL_FAST_REASON);
+                } else if group
+                    .tests
+                    .iter()
+                    .any(|t| t.skip_reason.as_deref() == Some(BUDGET_REASON))
+                {
+                    stopped = Some(BUDGET_REASON);
+                }
+                groups.push(group);
+            }
+        }
+
+        drop(progress);
+
+        // Before the sweep, whose deletes aren't part of any test.
+        let latency = latency::summarize(&self.stats, &self.config.slo);
+        let stats = self.stats.snapshot();
+        let leaked = sweep_leaked_sessions(client).await;
+        let mut report = RunReport::new(
+            started_at,
+            client.base_url(),
+            self.config.seed,
+            groups,
+            leaked,
+            self.config.strict_leaks,
+            if interrupt.is_set() {
+                Some(RunStop::Interrupted)
+            } else if stopped == Some(BUDGET_REASON) {
+                Some(RunStop::BudgetExceeded)
+            } else {
+                None
+            },
+        );
+        if self.config.shuffle || self.config.shuffle_groups {
+            report.order = report
+                .groups
+                .iter()
+                .flat_map(|g| g.tests.iter().map(move |t| (g, t)))
+                .filter(|(_, t)| t.attempts > 0)
+                .map(|(g, t)| format!("{} / {}", g.name, t.name))
+                .collect();
+        }
+        if report.outcome == crate::report::Outcome::Interrupted {
+            outln!(
+                "{}",
+                format!(
+                    "⏹ interrupted: {} test(s) not executed",
+                    report.totals.not_run
+                )
+                .yellow()
+            );
+        } else if report.outcome == crate::report::Outcome::BudgetExceeded {
+            let budget = self.config.max_duration.unwrap_or_default();
+            outln!(
+                "{}",
+                format!(
+                    "⏹ time budget of {} exceeded: {} test(s) not executed",
+                    console::format_duration(budget),
+                    report.totals.not_run
+                )
+                .yellow()
+            );
+        } else if stopped == Some(FAIL_FAST_REASON) {
+            outln!(
+                "{}",
+                format!(
+                    "⏹ fail-fast: stopped after first failure, {} test(s) not executed",
+                    report.totals.not_run
+                )
+                .yellow()
+            );
+        }
+        let skipped_by_tag = report
+            .groups
+            .iter()
+            .flat_map(|g| &g.tests)
+            .filter(|t| {
+                t.skip_reason
+                    .as_deref()
+                    .is_some_and(|r| r.starts_with(TAG_SKIP_PREFIX))
+            })
+            .count();
+        if skipped_by_tag > 0 {
+            outln!(
+                "{}",
+                format!("- {skipped_by_tag} test(s) skipped by tag filters").dimmed()
+            );
+        }
+        if repeat > 1 {
+            print_repeat_summary(&report);
+        }
+        latency::print_table(&latency, &self.config.slo);
+        for violation in latency::violations(&latency) {
+            outln!("{} SLO: {violation}", "✗".red());
+        }
+        stats::print_table(&stats);
+        report.set_latency(latency);
+        report.stats = stats;
+        report.client_retries = client.retried_requests();
+        report
+    }
+
+    /// Run a group between its setup and teardown hooks. A failed setup skips
+    /// the group; a failed teardown is recorded as a warning.
+    async fn run_group_with_hooks(
+        &self,
+        group_name: &str,
+        tests: &[&TestCase<C>],
+        hooks: &GroupHooks<C>,
+        iteration: u32,
+        client: &C,
+        stop: &Stop<'_>,
+    ) -> GroupReport {
+        // Nothing to run in this group: don't pay for its setup.
+        let runnable = |test: &&TestCase<C>| {
+            self.skip_reason(test).is_none()
+                && (self.config.require_all || self.missing_capability(test).is_none())
+        };
+        if !tests.iter().any(runnable) {
+            return self
+                .run_group(group_name, tests, iteration, client, stop)
+                .await;
+        }
+
+        let setup = self
+            .run_hook(hooks.setup.as_ref(), client)
+            .instrument(tracing::info_span!("setup", group = %group_name));
+        let mut group =
+            match context::scope(format!("{group_name} / setup"), None, None, setup).await {
+                Ok(()) => {
+                    self.run_group(group_name, tests, iteration, client, stop)
+                        .await
+                }
+                Err(e) => {
+                    let reason = format!("group setup failed: {e}");
+                    outln!(
+                        "{}",
+                        format!("⚠ {group_name}: {reason} — skipping group").yellow()
+                    );
+                    let tests = tests
+                        .iter()
+                        .map(|t| TestResult::not_executed(&t.name, TestStatus::Skipped, &reason))
+                        .collect();
+                    GroupReport::new(group_name, iteration, tests)
+                }
+            };
+
+        let teardown = self
+            .run_hook(hooks.teardown.as_ref(), client)
+            .instrument(tracing::info_span!("teardown", group = %group_name));
+        if let Err(e) =
+            context::scope(format!("{group_name} / teardown"), None, None, teardown).await
+        {
+            let warning = format!("teardown failed: {e}");
+            outln!("{}", format!("⚠ {group_name}: {warning}").yellow());
+            group.warnings.push(warning);
+        }
+
+        group
+    }
+
+    /// "(84ms)" label for a result line, yellow above `config.slow_threshold`.
+    fn duration_label(&self, elapsed: Duration) -> colored::ColoredString {
+        let label = format!("({})", console::format_duration(elapsed));
+        match self.config.slow_threshold {
+            Some(threshold) if elapsed > threshold => label.yellow(),
+            _ => label.dimmed(),
+        }
+    }
+
+    /// Run an optional hook under the default per-test timeout.
+    async fn run_hook(&self, hook: Option<&TestFn<C>>, client: &C) -> Result<(), String> {
+        let Some(hook) = hook else {
+            return Ok(());
+        };
+        let limit = self.config.test_timeout;
+        match tokio::time::timeout(limit, hook(client)).await {
+            Ok(result) => result,
+            Err(_) => Err(format!("timed out after {}s", limit.as_secs())),
+        }
+    }
+
+    /// Run one group of test cases. Up to `config.parallel` test cases run at
+    /// once; results are still printed in registration order.
+    async fn run_group(
+        &self,
+        group_name: &str,
+        tests: &[&TestCase<C>],
+        iteration: u32,
+        client: &C,
+        stop: &Stop<'_>,
+    ) -> GroupReport {
+        let parallel = self.config.parallel.max(1);
+        // `run_order` put `run_first` tests at the front.
+        let (first, rest) = tests.split_at(tests.iter().take_while(|test| test.first).count());
+
+        let run_one = |test| async move {
+            let start = Instant::now();
+            let result = match (self.skip_reason(test), self.missing_capability(test)) {
+                (Some(reason), _) => Outcome::Skipped(reason),
+                (None, Some(capability)) => Outcome::Unsupported(capability),
+                (None, None) if stop.over_budget() => Outcome::OverBudget,
+                (None, None) => {
+                    let label = format!("{group_name} / {}", test.name);
+                    console::progress::started(&label);
+                    let outcome = self.run_test(group_name, test, client, stop).await;
+                    console::progress::finished(&label, start.elapsed());
+                    outcome
+                }
+            };
+            (test, result, start.elapsed())
+        };
+        // `buffered` polls up to `parallel` futures at once but yields
+        // their outputs in the order they were submitted. `run_first`
+        // tests run one at a time, and finish before the rest start.
+        let mut results = stream::iter(first.iter().copied())
+            .map(run_one)
+            .buffered(1)
+            .chain(
+                stream::iter(rest.iter().copied())
+                    .map(run_one)
+                    .buffered(parallel),
+            );
+
+        let mut group = GroupReport::new(group_name, iteration, Vec::with_capacity(tests.len()));
+        let mut stop_reason = None;
+        let mut finished = 0;
+
+        loop {
+            let next = tokio::select! {
+                next = results.next() => next,
+                _ = stop.interrupt.wait() => {
+                    stop_reason = Some(INTERRUPTED_REASON);
+                    break;
+                }
+            };
+            let Some((test, result, elapsed)) = next else {
+                break;
+            };
+            finished += 1;
+            let took = self.duration_label(elapsed);
+            let (requests, metrics, warnings, notes, unsupported) = match &result {
+                Outcome::Ran {
+                    requests,
+                    observations,
+                    ..
+                } => (
+                    requests.clone(),
+                    observations.metrics(),
+                    observations.warnings(),
+                    observations.notes(),
+                    observations.unsupported(),
+                ),
+                Outcome::Skipped(_) | Outcome::Unsupported(_) | Outcome::OverBudget => {
+                    (None, BTreeMap::new(), Vec::new(), Vec::new(), None)
+                }
+            };
+            let (status, error, skip_reason, attempts) = match result {
+                Outcome::Ran {
+                    result: Ok(()),
+                    attempts,
+                    ..
+                } if unsupported.is_some() => {
+                    let reason = unsupported.unwrap_or_default();
+                    outln!(
+                        "{}",
+                        format!("- {} (unsupported: {reason})", test.name).yellow()
+                    );
+                    (TestStatus::Unsupported, None, Some(reason), attempts)
+                }
+                Outcome::Ran {
+                    result: Err(e),
+                    attempts,
+                    ..
+                } if test.expected_failure.is_some() => {
+                    let reason = test.expected_failure.as_deref().unwrap_or_default();
+                    outln!(
+                        "{} {took} {}",
+                        format!("✗ {}", test.name).yellow(),
+                        format!("(expected: {reason}): {e}").yellow()
+                    );
+                    (TestStatus::ExpectedFailure, Some(e), None, attempts)
+                }
+                Outcome::Ran {
+                    result: Ok(()),
+                    attempts,
+                    ..
+                } if test.expected_failure.is_some() => {
+                    let reason = test.expected_failure.as_deref().unwrap_or_default();
+                    let message = format!("passed but was expected to fail ({reason})");
+                    outln!(
+                        "{} {} {took}: {message}",
+                        "XPASS".red().bold(),
+                        test.name.red()
+                    );
+                    (TestStatus::UnexpectedPass, Some(message), None, attempts)
+                }
+                Outcome::Ran {
+                    result: Ok(()),
+                    attempts,
+                    ..
+                } => {
+                    if attempts > 1 {
+                        outln!(
+                            "{} {} {took} {}",
+                            "✓".green(),
+                            test.name,
+                            format!("(passed on attempt {attempts})").yellow()
+                        );
+                    } else {
+                        outln!("{} {} {took}", "✓".green(), test.name);
                     }
+                    (TestStatus::Passed, None, None, attempts)
+                }
+                Outcome::Ran {
+                    result: Err(e),
+                    attempts,
+                    ..
+                } => {
+                    let tries = if attempts > 1 {
+                        format!(" (after {attempts} attempts)")
+                    } else {
+                        String::new()
+                    };
+                    outln!("{} {} {took}{tries}: {}", "✗".red(), test.name.red(), e);
+                    (TestStatus::Failed, Some(e), None, attempts)
+                }
+                Outcome::Skipped(_) if iteration > 1 => continue,
+                Outcome::Skipped(reason) => {
+                    outln!(
+                        "{}",
+                        format!("- {} (skipped: {reason})", test.name).dimmed()
+                    );
+                    (TestStatus::Skipped, None, Some(reason), 0)
+                }
+                Outcome::Unsupported(capability) if self.config.require_all => {
+                    let e = format!("requires {capability}, which the orchestrator lacks");
+                    outln!("{} {}: {e} (--require-all)", "✗".red(), test.name.red());
+                    (TestStatus::Failed, Some(e), None, 0)
                 }
+                Outcome::OverBudget => {
+                    (TestStatus::NotRun, None, Some(BUDGET_REASON.to_string()), 0)
+                }
+                Outcome::Unsupported(_) if iteration > 1 => continue,
+                Outcome::Unsupported(capability) => {
+                    let reason = format!("orchestrator lacks {capability}");
+                    outln!(
+                        "{}",
+                        format!("- {} (unsupported: {reason})", test.name).yellow()
+                    );
+                    (TestStatus::Unsupported, None, Some(reason), 0)
+                }
+            };
+            for (name, value) in &metrics {
+                outln!("{}", format!("  {name}: {value}").dimmed());
+            }
+            for warning in &warnings {
+                outln!("{}", format!("  ⚠ {warning}").yellow());
+            }
+            for note in &notes {
+                outln!("{}", format!("  ▸ {note}").dimmed());
+            }
+            group.tests.push(TestResult {
+                name: test.name.clone(),
+                status,
+                duration_ms: elapsed.as_millis() as u64,
+                error,
+                skip_reason,
+                attempts,
+                metrics,
+                warnings,
+                notes,
+            });
+
+            if status == TestStatus::Failed {
+                if let (Some(dir), Some(requests)) = (&self.config.artifacts, requests) {
+                    save_requests(&requests, dir, group_name, &test.name);
+                }
+            }
+
+            if status.is_failure() && self.config.fail_fast {
+                stop_reason = Some(FAIL_FAST_REASON);
+                break;
+            }
+        }
+        // Dropping the stream cancels any test still in flight.
+        drop(results);
+
+        if let Some(reason) = stop_reason {
+            let remaining = &tests[finished..];
+            group
+                .tests
+                .extend(self.not_run(remaining, iteration, reason));
+        }
+
+        group
+    }
+}
+
+/// Write a failed test's HTTP exchanges under the artifacts directory.
+fn save_requests(requests: &RequestLog, dir: &Path, group_name: &str, test: &str) {
+    match requests.write(dir, group_name, test) {
+        Ok(path) => outln!(
+            "{}",
+            format!("  requests saved to {}", path.display()).dimmed()
+        ),
+        Err(e) => outln!(
+            "{}",
+            format!("⚠ failed to save requests for {test}: {e}").yellow()
+        ),
+    }
+}
+
+/// Print the `count` slowest executed tests of the run, slowest first.
+pub fn print_slowest(report: &RunReport, count: usize) {
+    let mut timed: Vec<_> = report
+        .groups
+        .iter()
+        .flat_map(|g| g.tests.iter().map(move |t| (g.name.as_str(), t)))
+        .filter(|(_, t)| t.attempts > 0)
+        .collect();
+    if timed.is_empty() {
+        return;
+    }
+    timed.sort_by_key(|(_, t)| std::cmp::Reverse(t.duration_ms));
+
+    outln!(
+        "{}",
+        format!("🐢 Slowest {} tests:", count.min(timed.len())).bold()
+    );
+    for (group, test) in timed.into_iter().take(count) {
+        let took = console::format_duration(Duration::from_millis(test.duration_ms));
+        outln!("  {took:>8}  {group} / {}", test.name);
+    }
+    outln!();
+}
+
+/// Delete every session a test created but never cleaned up. Sessions that
+/// are already gone (expired, or lost with a crashed worker) are not leaks.
+async fn sweep_leaked_sessions(client: &impl ApiClient) -> Vec<LeakedSession> {
+    let mut leaked = Vec::new();
+    for (id, test) in client.outstanding_sessions() {
+        let swept = match client.delete_session(&id).await {
+            Ok(status) if status == reqwest::StatusCode::NOT_FOUND => continue,
+            Ok(status) if status.is_success() => true,
+            Ok(status) => {
+                outln!(
+                    "{}",
+                    format!("⚠ failed to sweep session {id}: HTTP {status}").yellow()
+                );
+                false
+            }
+            Err(e) => {
+                outln!(
+                    "{}",
+                    format!("⚠ failed to sweep session {id}: {e}").yellow()
+                );
+                false
             }
+        };
+        leaked.push(LeakedSession { id, test, swept });
+    }
+
+    if !leaked.is_empty() {
+        let swept = leaked.iter().filter(|l| l.swept).count();
+        outln!();
+        outln!(
+            "{}",
+            format!("⚠ swept {swept} leaked sessions").yellow().bold()
+        );
+        for session in &leaked {
+            outln!(
+                "{}",
+                format!("  {} ({})", session.test, session.id).yellow()
+            );
         }
+    }
+    leaked
+}
 
-        (passed, total)
+/// Print per-test pass/fail counts across iterations, flagging mixed records.
+fn print_repeat_summary(report: &RunReport) {
+    outln!();
+    outln!("{}", "▸ Repeat summary".bold());
+    for record in &report.repeat {
+        let line = format!(
+            "  {} / {}: {}/{} iterations passed",
+            record.group,
+            record.name,
+            record.passed,
+            record.passed + record.failed
+        );
+        if record.is_flaky() {
+            outln!("{} {}", line.yellow(), "FLAKY".yellow().bold());
+        } else if record.failed > 0 {
+            outln!("{}", line.red());
+        } else {
+            outln!("{}", line.green());
+        }
     }
 }
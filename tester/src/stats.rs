@@ -0,0 +1,303 @@
+//! Every HTTP exchange the client makes during a run, counted per endpoint:
+//! requests, errors by class, retries, bytes and latency. The runner owns
+//! one `Stats` and hands it to the client; the table is printed at the end
+//! of the run and the full numbers go into the JSON report.
+//!
+//! Concurrent tests record into the same endpoints, so nothing on the
+//! recording path takes a shared lock: counters are atomics, latencies go
+//! to one of several shards, and the endpoint map is only write-locked the
+//! first time an endpoint is seen.
+
+use crate::console::outln;
+use crate::tests::util;
+use colored::Colorize;
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+
+/// Latency shards per endpoint; enough that parallel tests rarely meet on one.
+const SHARDS: usize = 16;
+
+/// How one HTTP exchange ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reply {
+    /// A response arrived, with this status.
+    Status(StatusCode),
+    /// The request timed out before a response arrived.
+    Timeout,
+    /// No connection could be made.
+    Connect,
+    /// Any other failure before a response arrived.
+    Transport,
+}
+
+impl Reply {
+    /// How a `reqwest` exchange that got no response, or no whole body,
+    /// failed.
+    pub fn failed(error: &reqwest::Error) -> Self {
+        if error.is_timeout() {
+            Self::Timeout
+        } else if error.is_connect() {
+            Self::Connect
+        } else {
+            Self::Transport
+        }
+    }
+}
+
+/// The name exchanges are counted under: the method and path, with session
+/// IDs replaced so every session's requests share one endpoint, e.g.
+/// "GET /sessions/{id}".
+pub fn endpoint(method: &Method, path: &str) -> String {
+    let template = match path.strip_prefix("/sessions/") {
+        Some(rest) if rest.contains('/') => "/sessions/{id}/…",
+        Some(rest) if !rest.is_empty() => "/sessions/{id}",
+        _ => path,
+    };
+    format!("{method} {template}")
+}
+
+/// Shared per-endpoint counters. Clones record into the same counters, so
+/// the runner keeps one and the client and its clones another.
+#[derive(Clone, Default)]
+pub struct Stats {
+    endpoints: Arc<RwLock<HashMap<String, Arc<Counters>>>>,
+}
+
+#[derive(Default)]
+struct Counters {
+    requests: AtomicU64,
+    client_errors: AtomicU64,
+    server_errors: AtomicU64,
+    timeouts: AtomicU64,
+    connect_errors: AtomicU64,
+    transport_errors: AtomicU64,
+    retries: AtomicU64,
+    bytes_sent: AtomicU64,
+    bytes_received: AtomicU64,
+    next_shard: AtomicUsize,
+    latencies: [Mutex<Vec<Duration>>; SHARDS],
+}
+
+impl Stats {
+    /// Count one exchange with `endpoint`: how it ended, how long until the
+    /// response headers arrived, and the body bytes sent and received.
+    pub fn record(
+        &self,
+        endpoint: &str,
+        reply: Reply,
+        latency: Duration,
+        bytes_sent: u64,
+        bytes_received: u64,
+    ) {
+        let counters = self.counters(endpoint);
+        counters.requests.fetch_add(1, Ordering::Relaxed);
+        counters.bytes_sent.fetch_add(bytes_sent, Ordering::Relaxed);
+        counters
+            .bytes_received
+            .fetch_add(bytes_received, Ordering::Relaxed);
+        let class = match reply {
+            Reply::Status(status) if status.is_client_error() => Some(&counters.client_errors),
+            Reply::Status(status) if status.is_server_error() => Some(&counters.server_errors),
+            Reply::Status(_) => None,
+            Reply::Timeout => Some(&counters.timeouts),
+            Reply::Connect => Some(&counters.connect_errors),
+            Reply::Transport => Some(&counters.transport_errors),
+        };
+        if let Some(class) = class {
+            class.fetch_add(1, Ordering::Relaxed);
+        }
+        if let Reply::Status(_) = reply {
+            counters.record_latency(latency);
+        }
+    }
+
+    /// Count a retry of a request to `endpoint`; the retry's own exchange is
+    /// recorded separately.
+    pub fn record_retry(&self, endpoint: &str) {
+        self.counters(endpoint)
+            .retries
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Retries across all endpoints.
+    pub fn retries(&self) -> u64 {
+        self.endpoints
+            .read()
+            .unwrap()
+            .values()
+            .map(|c| c.retries.load(Ordering::Relaxed))
+            .sum()
+    }
+
+    /// The counts and latency percentiles of every endpoint seen so far,
+    /// sorted by endpoint.
+    pub fn snapshot(&self) -> Vec<EndpointStats> {
+        let mut endpoints: Vec<EndpointStats> = self
+            .endpoints
+            .read()
+            .unwrap()
+            .iter()
+            .map(|(endpoint, counters)| counters.snapshot(endpoint))
+            .collect();
+        endpoints.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        endpoints
+    }
+
+    /// Latencies recorded for `endpoint` so far, sorted; empty if it was
+    /// never called.
+    pub fn latencies(&self, endpoint: &str) -> Vec<Duration> {
+        self.endpoints
+            .read()
+            .unwrap()
+            .get(endpoint)
+            .map(|counters| counters.latencies())
+            .unwrap_or_default()
+    }
+
+    fn counters(&self, endpoint: &str) -> Arc<Counters> {
+        if let Some(counters) = self.endpoints.read().unwrap().get(endpoint) {
+            return Arc::clone(counters);
+        }
+        Arc::clone(
+            self.endpoints
+                .write()
+                .unwrap()
+                .entry(endpoint.to_string())
+                .or_default(),
+        )
+    }
+}
+
+impl Counters {
+    /// Latencies are spread round-robin over the shards, so concurrent
+    /// records to one endpoint mostly lock different ones.
+    fn record_latency(&self, latency: Duration) {
+        let shard = self.next_shard.fetch_add(1, Ordering::Relaxed) % SHARDS;
+        self.latencies[shard].lock().unwrap().push(latency);
+    }
+
+    fn latencies(&self) -> Vec<Duration> {
+        let mut latencies: Vec<Duration> = self
+            .latencies
+            .iter()
+            .flat_map(|shard| shard.lock().unwrap().clone())
+            .collect();
+        latencies.sort_unstable();
+        latencies
+    }
+
+    fn snapshot(&self, endpoint: &str) -> EndpointStats {
+        let latencies = self.latencies();
+        let at = |p| {
+            util::percentile(&latencies, p)
+                .map(millis)
+                .unwrap_or_default()
+        };
+        let load = |counter: &AtomicU64| counter.load(Ordering::Relaxed);
+        EndpointStats {
+            endpoint: endpoint.to_string(),
+            requests: load(&self.requests),
+            client_errors: load(&self.client_errors),
+            server_errors: load(&self.server_errors),
+            timeouts: load(&self.timeouts),
+            connect_errors: load(&self.connect_errors),
+            transport_errors: load(&self.transport_errors),
+            retries: load(&self.retries),
+            bytes_sent: load(&self.bytes_sent),
+            bytes_received: load(&self.bytes_received),
+            p50_ms: at(50.0),
+            p95_ms: at(95.0),
+            p99_ms: at(99.0),
+            max_ms: latencies.last().copied().map(millis).unwrap_or_default(),
+        }
+    }
+}
+
+/// What was sent to one endpoint during a run.
+#[derive(Debug, Clone, Serialize)]
+#[non_exhaustive]
+pub struct EndpointStats {
+    /// E.g. "POST /sessions"; see `endpoint`.
+    pub endpoint: String,
+    /// Exchanges, retries included.
+    pub requests: u64,
+    /// 4xx responses.
+    pub client_errors: u64,
+    /// 5xx responses.
+    pub server_errors: u64,
+    pub timeouts: u64,
+    pub connect_errors: u64,
+    /// Requests that got no response for another reason, e.g. a reset.
+    pub transport_errors: u64,
+    pub retries: u64,
+    /// Request bodies that were buffered; streamed bodies aren't counted.
+    pub bytes_sent: u64,
+    /// Response bodies as they arrived, after any chunked encoding.
+    pub bytes_received: u64,
+    /// Percentiles of the exchanges that got a response, until its headers.
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+impl EndpointStats {
+    /// Requests that got a 5xx or no response at all.
+    pub fn errors(&self) -> u64 {
+        self.server_errors + self.timeouts + self.connect_errors + self.transport_errors
+    }
+
+    /// `errors` as a share of `requests`, from 0 to 1.
+    pub fn error_rate(&self) -> f64 {
+        if self.requests == 0 {
+            0.0
+        } else {
+            self.errors() as f64 / self.requests as f64
+        }
+    }
+}
+
+/// Print requests, 4xx, error rate and p50/p95 per endpoint.
+pub fn print_table(endpoints: &[EndpointStats]) {
+    if endpoints.is_empty() {
+        return;
+    }
+    let width = endpoints
+        .iter()
+        .map(|e| e.endpoint.chars().count())
+        .max()
+        .unwrap_or_default()
+        .max("endpoint".len());
+    outln!();
+    outln!(
+        "{}",
+        format!(
+            "{:<width$} {:>8} {:>6} {:>7} {:>7} {:>9} {:>9}",
+            "endpoint", "requests", "4xx", "errors", "retries", "p50", "p95"
+        )
+        .bold()
+    );
+    for e in endpoints {
+        let errors = format!("{:.1}%", e.error_rate() * 100.0);
+        let line = format!(
+            "{:<width$} {:>8} {:>6} {errors:>7} {:>7} {:>9.2} {:>9.2}",
+            e.endpoint, e.requests, e.client_errors, e.retries, e.p50_ms, e.p95_ms
+        );
+        if e.errors() > 0 {
+            outln!("{}", line.yellow());
+        } else {
+            outln!("{line}");
+        }
+    }
+    outln!("Errors are 5xx responses and requests that got none; latencies in milliseconds.");
+}
+
+/// `duration` in milliseconds to the microsecond, as latencies are printed
+/// and reported.
+pub(crate) fn millis(duration: Duration) -> f64 {
+    (duration.as_secs_f64() * 1_000_000.0).round() / 1000.0
+}
@@ -0,0 +1,146 @@
+use crate::client::{ClientError, OrchestratorClient, RawResponse};
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::util;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use reqwest::{Method, StatusCode};
+
+/// Any ID works: the auth proxy must answer before the orchestrator looks it up.
+const MISSING_ID: &str = "nonexistent-session-id-12345";
+
+/// Credentials a create is refused for: (test name, `Authorization` value,
+/// or None to send none).
+const BAD_CREDENTIALS: &[(&str, Option<&str>)] = &[
+    ("Create without a token is refused", None),
+    (
+        "Create with a malformed token is refused",
+        Some("Bearer not a token!"),
+    ),
+    // A well-formed HS256 JWT that expired in 2001, with a made-up signature.
+    (
+        "Create with an expired JWT is refused",
+        Some(concat!(
+            "Bearer eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.",
+            "eyJzdWIiOiJzdGVlbC10ZXN0ZXIiLCJleHAiOjEwMDAwMDAwMDB9.",
+            "c2lnbmF0dXJlLXRoYXQtbm8ta2V5LW1hZGU"
+        )),
+    ),
+];
+
+/// Register auth test cases. They only make sense against a deployment behind
+/// the auth proxy, so they are skipped unless a token was given.
+pub fn tests(has_token: bool) -> Vec<TestCase> {
+    let mut tests = vec![
+        TestCase::new("Unauthenticated request is rejected", |client| {
+            Box::pin(test_unauthenticated(client))
+        }),
+        TestCase::new("Authenticated request is accepted", |client| {
+            Box::pin(test_authenticated(client))
+        }),
+    ];
+    for &(name, authorization) in BAD_CREDENTIALS {
+        tests.push(TestCase::new(name, move |client| {
+            Box::pin(test_refused_create(client, authorization))
+        }));
+    }
+    if has_token {
+        tests
+    } else {
+        tests
+            .into_iter()
+            .map(|test| test.skip("requires --token or STEEL_TOKEN"))
+            .collect()
+    }
+}
+
+/// A request without the bearer token should be rejected with 401.
+async fn test_unauthenticated(client: &OrchestratorClient) -> Result<(), String> {
+    let anonymous = client.without_token();
+    let resp = anonymous
+        .send(anonymous.request(Method::GET, &format!("/sessions/{MISSING_ID}")))
+        .await
+        .map_err(|e| format!("unauthenticated request failed: {e}"))?;
+
+    match resp.status() {
+        StatusCode::UNAUTHORIZED => Ok(()),
+        status => Err(format!("expected 401 without a token, got {status}")),
+    }
+}
+
+/// The same request with the token should get through to the orchestrator.
+async fn test_authenticated(client: &OrchestratorClient) -> Result<(), String> {
+    match client.get_session(MISSING_ID).await {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Err(ClientError::Http { status, .. }) if status == StatusCode::UNAUTHORIZED => {
+            Err("token was rejected with 401".into())
+        }
+        Ok(_) => Err("expected 404 for a missing session, got a session".into()),
+        Err(e) => Err(format!("unexpected error: {e}")),
+    }
+}
+
+/// A create with bad credentials must get 401 with a `WWW-Authenticate`
+/// challenge, or 403, and a JSON error body either way; and no session may
+/// be made, which the authorized client checks by listing.
+async fn test_refused_create(
+    client: &OrchestratorClient,
+    authorization: Option<&str>,
+) -> Result<(), String> {
+    let marker = util::unique("refused_create");
+    let data = serde_json::json!({ "user": marker });
+    let anonymous = client.without_token();
+    let mut headers = HeaderMap::new();
+    if let Some(value) = authorization {
+        headers.insert(
+            AUTHORIZATION,
+            HeaderValue::from_str(value).expect("credentials are valid header values"),
+        );
+    }
+    let resp = anonymous
+        .request_raw_with(Method::POST, "/sessions", Some(&data), headers)
+        .await?;
+
+    let created = check_no_session(client, &marker).await;
+    match (check_refusal(&resp), created) {
+        (Ok(()), created) => created,
+        (Err(refusal), Ok(())) => Err(refusal),
+        (Err(refusal), Err(created)) => Err(format!("{refusal}; {created}")),
+    }
+}
+
+fn check_refusal(resp: &RawResponse) -> Result<(), String> {
+    match resp.status {
+        StatusCode::UNAUTHORIZED if !resp.headers.contains_key(WWW_AUTHENTICATE) => {
+            return Err(format!("401 without a WWW-Authenticate header: {resp}"))
+        }
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {}
+        _ => return Err(format!("expected 401 or 403, got {resp}")),
+    }
+    serde_json::from_str::<serde_json::Value>(&resp.body)
+        .map(drop)
+        .map_err(|_| format!("error body is not JSON: {resp}"))
+}
+
+/// Fails, deleting it, if a session carrying `marker` exists. Without a
+/// listing nothing can be checked, which is warned about.
+async fn check_no_session(client: &OrchestratorClient, marker: &str) -> Result<(), String> {
+    let sessions = match client.list_sessions(None, None).await {
+        Ok(sessions) => sessions,
+        Err(e) => {
+            context::warn(format!(
+                "could not list sessions to check none was created: {e}"
+            ));
+            return Ok(());
+        }
+    };
+    match sessions
+        .iter()
+        .find(|session| session.data.get("user").and_then(|user| user.as_str()) == Some(marker))
+    {
+        Some(session) => {
+            let _ = client.delete_session(&session.id).await;
+            Err(format!("session {} was created anyway", session.id))
+        }
+        None => Ok(()),
+    }
+}
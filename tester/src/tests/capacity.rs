@@ -0,0 +1,362 @@
+use crate::client::{ClientError, OrchestratorClient, Session};
+use crate::context;
+use crate::runner::{GroupHooks, TestCase};
+use crate::tests::util;
+use reqwest::StatusCode;
+use std::time::{Duration, Instant};
+
+/// Name the capacity group is registered under; teardown uses it to find the
+/// sessions its tests created.
+pub const GROUP: &str = "Capacity";
+
+/// Limit on each create while filling the pool, which may have to spawn workers.
+const FILL_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a create beyond capacity is given before it counts as queued.
+const QUEUE_PROBE: Duration = Duration::from_secs(2);
+
+/// How soon a queued create must be served once a slot frees up.
+const SERVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Health checks issued in each phase of the saturated health test.
+const HEALTH_CHECKS: usize = 20;
+
+/// Limit on each of those health checks, as strict as a liveness probe.
+const HEALTH_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long the extra create gets to reach the orchestrator's queue before the
+/// second phase of health checks.
+const QUEUE_SETTLE: Duration = Duration::from_millis(200);
+
+/// Growth of mean create latency, last decile of the churn over the first,
+/// that counts as degradation.
+const MAX_CHURN_GROWTH: f64 = 2.0;
+
+/// Latency growth below this is noise, however large the ratio.
+const CHURN_NOISE_FLOOR: Duration = Duration::from_millis(20);
+
+/// Register pool capacity test cases. `pool_size` overrides the `max_workers`
+/// reported by GET /status. Each test fills the whole pool, so the group
+/// assumes nothing else holds sessions while it runs. `churn_iterations` is
+/// the number of create/delete cycles in the churn test.
+pub fn tests(pool_size: Option<usize>, churn_iterations: usize) -> Vec<TestCase> {
+    vec![
+        TestCase::new(
+            "Create beyond capacity queues or is rejected",
+            move |client| Box::pin(test_over_capacity(client, pool_size)),
+        ),
+        TestCase::new("Freed slot serves the next create", move |client| {
+            Box::pin(test_freed_slot(client, pool_size))
+        }),
+        TestCase::new("Health stays responsive with a full pool", move |client| {
+            Box::pin(test_health_when_full(client, pool_size))
+        }),
+        TestCase::new(
+            format!("Create/delete churn ({churn_iterations} cycles)"),
+            move |client| Box::pin(test_churn(client, pool_size, churn_iterations)),
+        )
+        .with_tags(&["slow"]),
+    ]
+}
+
+/// Teardown deleting every session the group's tests left behind, e.g. when
+/// one timed out while the pool was full.
+pub fn hooks() -> GroupHooks {
+    GroupHooks {
+        setup: None,
+        teardown: Some(Box::new(|client| Box::pin(cleanup(client)))),
+    }
+}
+
+async fn cleanup(client: &OrchestratorClient) -> Result<(), String> {
+    let prefix = format!("{GROUP} / ");
+    for (id, test) in client.tracker().outstanding() {
+        if test.starts_with(&prefix) {
+            client
+                .delete_session(&id)
+                .await
+                .map_err(|e| format!("failed to delete session {id}: {e}"))?;
+        }
+    }
+    Ok(())
+}
+
+/// The pool is full: one more create must either wait for a worker or be
+/// rejected with 503/429, never served.
+async fn test_over_capacity(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+) -> Result<(), String> {
+    let held = fill_pool(client, pool_size).await?;
+    let result = probe_extra_create(client, held.len()).await;
+    release(client, &held).await;
+    result
+}
+
+/// With the pool full, deleting one session must let the next create through
+/// promptly, whether it was already queued or had been rejected.
+async fn test_freed_slot(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+) -> Result<(), String> {
+    let mut held = fill_pool(client, pool_size).await?;
+    let result = serve_after_delete(client, &mut held).await;
+    release(client, &held).await;
+    result
+}
+
+async fn serve_after_delete(
+    client: &OrchestratorClient,
+    held: &mut Vec<Session>,
+) -> Result<(), String> {
+    let pool_size = held.len();
+    let mut waiting = {
+        let client = client.clone();
+        context::spawn(async move {
+            let data = serde_json::json!({"user": util::unique("capacity_waiting")});
+            client.create_session(data).await
+        })
+    };
+    tokio::time::sleep(QUEUE_PROBE).await;
+
+    let queued = !waiting.is_finished();
+    if !queued {
+        match (&mut waiting).await {
+            Ok(Ok(session)) => {
+                held.push(session);
+                return Err(format!(
+                    "create beyond {pool_size} sessions was served; is --pool-size too small?"
+                ));
+            }
+            Ok(Err(e)) if is_rejection(&e) => {}
+            Ok(Err(e)) => return Err(format!("create beyond capacity failed: {e}")),
+            Err(e) => return Err(format!("task join error: {e}")),
+        }
+    }
+
+    let freed = held.remove(0);
+    client
+        .delete_session(&freed.id)
+        .await
+        .map_err(|e| format!("failed to delete session {}: {e}", freed.id))?;
+
+    let served = if queued {
+        match tokio::time::timeout(SERVE_TIMEOUT, waiting).await {
+            Ok(Ok(result)) => result,
+            Ok(Err(e)) => return Err(format!("task join error: {e}")),
+            Err(_) => {
+                return Err(format!(
+                    "queued create was not served within {}s of a session being deleted",
+                    SERVE_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    } else {
+        let data = serde_json::json!({"user": util::unique("capacity_next")});
+        match tokio::time::timeout(SERVE_TIMEOUT, client.create_session(data)).await {
+            Ok(result) => result,
+            Err(_) => {
+                return Err(format!(
+                    "create after a delete did not complete within {}s",
+                    SERVE_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    };
+    let session = served.map_err(|e| format!("create after a delete failed: {e}"))?;
+    held.push(session);
+    Ok(())
+}
+
+/// With the pool full, and again while one more create is waiting for a
+/// worker, every health check must succeed within `HEALTH_TIMEOUT`. The
+/// slowest and mean health latency are recorded as metrics.
+async fn test_health_when_full(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+) -> Result<(), String> {
+    let mut held = fill_pool(client, pool_size).await?;
+    let mut latencies = Vec::with_capacity(2 * HEALTH_CHECKS);
+    let mut result = check_health_repeatedly(client, "pool full", &mut latencies).await;
+
+    let mut pending = {
+        let client = client.clone();
+        context::spawn(async move {
+            let data = serde_json::json!({"user": util::unique("capacity_pending")});
+            client.create_session(data).await
+        })
+    };
+    tokio::time::sleep(QUEUE_SETTLE).await;
+    if pending.is_finished() {
+        context::warn(
+            "the extra create was not queued, so health was only checked with the pool full",
+        );
+    } else if result.is_ok() {
+        result = check_health_repeatedly(client, "create pending", &mut latencies).await;
+    }
+    // Aborting drops the request, which cancels its place in the queue.
+    pending.abort();
+    if let Ok(Ok(session)) = (&mut pending).await {
+        held.push(session);
+    }
+    release(client, &held).await;
+
+    if let Some(max) = latencies.iter().max() {
+        context::record_metric("health_max_ms", max.as_millis() as f64);
+        context::record_metric("health_mean_ms", mean(&latencies).as_millis() as f64);
+    }
+    result
+}
+
+/// Issue `HEALTH_CHECKS` health checks one after another, appending each
+/// latency to `latencies`; `phase` names the pool state in failures.
+async fn check_health_repeatedly(
+    client: &OrchestratorClient,
+    phase: &str,
+    latencies: &mut Vec<Duration>,
+) -> Result<(), String> {
+    for i in 1..=HEALTH_CHECKS {
+        let start = Instant::now();
+        let outcome = tokio::time::timeout(HEALTH_TIMEOUT, client.health_detailed()).await;
+        latencies.push(start.elapsed());
+        match outcome {
+            Ok(Ok(_)) => {}
+            Ok(Err(e)) => {
+                return Err(format!(
+                    "{phase}: health check {i}/{HEALTH_CHECKS} failed: {e}"
+                ))
+            }
+            Err(_) => {
+                return Err(format!(
+                    "{phase}: health check {i}/{HEALTH_CHECKS} took over {}s",
+                    HEALTH_TIMEOUT.as_secs()
+                ))
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Back-to-back create/delete cycles must not leak worker slots: afterwards
+/// the full pool must still be available, and creates must not have slowed
+/// down over the run. Mean create latency of the first and last decile is
+/// recorded as metrics.
+async fn test_churn(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+    iterations: usize,
+) -> Result<(), String> {
+    let mut latencies = Vec::with_capacity(iterations);
+    for i in 1..=iterations {
+        let data = serde_json::json!({"user": util::unique("churn")});
+        let start = Instant::now();
+        let session = client
+            .create_session(data)
+            .await
+            .map_err(|e| format!("cycle {i}: create failed: {e}"))?;
+        latencies.push(start.elapsed());
+        let status = client
+            .delete_session(&session.id)
+            .await
+            .map_err(|e| format!("cycle {i}: delete failed: {e}"))?;
+        if !status.is_success() {
+            return Err(format!("cycle {i}: DELETE returned {status}"));
+        }
+    }
+
+    let decile = (iterations / 10).max(1);
+    let first = mean(&latencies[..decile]);
+    let last = mean(&latencies[latencies.len() - decile..]);
+    context::record_metric("first_decile_ms", first.as_millis() as f64);
+    context::record_metric("last_decile_ms", last.as_millis() as f64);
+
+    let held = fill_pool(client, pool_size)
+        .await
+        .map_err(|e| format!("after {iterations} cycles: {e}"))?;
+    release(client, &held).await;
+
+    if last > CHURN_NOISE_FLOOR + first
+        && last.as_secs_f64() > first.as_secs_f64() * MAX_CHURN_GROWTH
+    {
+        return Err(format!(
+            "create latency grew from {}ms to {}ms (first vs last decile mean)",
+            first.as_millis(),
+            last.as_millis()
+        ));
+    }
+    Ok(())
+}
+
+fn mean(durations: &[Duration]) -> Duration {
+    durations.iter().sum::<Duration>() / durations.len().max(1) as u32
+}
+
+/// Try one create beyond `pool_size` sessions; it must queue or be rejected.
+async fn probe_extra_create(client: &OrchestratorClient, pool_size: usize) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("capacity_extra")});
+    match tokio::time::timeout(QUEUE_PROBE, client.create_session(data)).await {
+        // Dropping the request cancels it, so it does not take a worker later.
+        Err(_) => Ok(()),
+        Ok(Err(e)) if is_rejection(&e) => Ok(()),
+        Ok(Err(e)) => Err(format!("create beyond capacity failed: {e}")),
+        Ok(Ok(session)) => {
+            let _ = client.delete_session(&session.id).await;
+            Err(format!(
+                "create beyond {pool_size} sessions was served; is --pool-size too small?"
+            ))
+        }
+    }
+}
+
+/// The orchestrator turned a create away because no worker was free.
+fn is_rejection(e: &ClientError) -> bool {
+    matches!(
+        e,
+        ClientError::Http { status, .. }
+            if *status == StatusCode::SERVICE_UNAVAILABLE || *status == StatusCode::TOO_MANY_REQUESTS
+    )
+}
+
+/// Create sessions until the pool is full, returning them. `pool_size`
+/// overrides the `max_workers` reported by GET /status.
+pub async fn fill_pool(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+) -> Result<Vec<Session>, String> {
+    let pool_size = match pool_size {
+        Some(size) => size,
+        None => client
+            .status()
+            .await
+            .map(|status| status.max_workers)
+            .map_err(|e| {
+                format!("cannot discover pool size from /status ({e}); pass --pool-size")
+            })?,
+    };
+
+    let mut held = Vec::with_capacity(pool_size);
+    for i in 0..pool_size {
+        let data = serde_json::json!({"user": util::unique(&format!("capacity_{i}"))});
+        let error = match tokio::time::timeout(FILL_TIMEOUT, client.create_session(data)).await {
+            Ok(Ok(session)) => {
+                held.push(session);
+                continue;
+            }
+            Ok(Err(e)) => e.to_string(),
+            Err(_) => format!("timed out after {}s", FILL_TIMEOUT.as_secs()),
+        };
+        release(client, &held).await;
+        return Err(format!(
+            "filled only {i}/{pool_size} slots: {error}; \
+             is --pool-size too large or another client holding sessions?"
+        ));
+    }
+    Ok(held)
+}
+
+/// Delete `sessions`, ignoring failures.
+pub async fn release(client: &OrchestratorClient, sessions: &[Session]) {
+    for session in sessions {
+        let _ = client.delete_session(&session.id).await;
+    }
+}
@@ -0,0 +1,385 @@
+use crate::capabilities::Capability;
+use crate::client::{ClientError, OrchestratorClient};
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::util::{self, Rng};
+use reqwest::StatusCode;
+use std::collections::{BTreeMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Requests allowed in flight at once.
+const MAX_IN_FLIGHT: usize = 8;
+
+/// Limit on each request; one that takes longer counts as hung.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Range the pause between two crashes is drawn from.
+const CRASH_INTERVAL: (Duration, Duration) = (Duration::from_secs(3), Duration::from_secs(7));
+
+/// Fraction of failed requests on uncrashed sessions above which the test fails.
+const MAX_ERROR_RATE: f64 = 0.01;
+
+/// How long the final burst of creates may take while crashed workers are
+/// still being replaced.
+const BURST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Extra time on top of the chaos duration for the drain and the final burst.
+const DRAIN_ALLOWANCE: Duration = Duration::from_secs(90);
+
+/// Length, pace, and randomness of the chaos run.
+#[derive(Debug, Clone, Copy)]
+pub struct ChaosSettings {
+    pub duration: Duration,
+    /// Requests started per second.
+    pub rps: u32,
+    /// Relative weights of creates, GETs, and deletes in the workload.
+    pub mix: Mix,
+    pub seed: u64,
+    /// Overrides the `max_workers` reported by GET /status.
+    pub pool_size: Option<usize>,
+}
+
+/// Relative weights of the three operations, parsed from "CREATE:GET:DELETE".
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mix {
+    pub create: u32,
+    pub get: u32,
+    pub delete: u32,
+}
+
+impl std::str::FromStr for Mix {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, String> {
+        let weights: Vec<u32> = value
+            .split(':')
+            .map(|w| w.trim().parse())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("expected CREATE:GET:DELETE weights, got {value:?}"))?;
+        match weights[..] {
+            [create, get, delete] if create > 0 => Ok(Self {
+                create,
+                get,
+                delete,
+            }),
+            [_, _, _] => Err(format!("the create weight must be positive, got {value:?}")),
+            _ => Err(format!("expected CREATE:GET:DELETE weights, got {value:?}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Op {
+    Create,
+    Get,
+    Delete,
+}
+
+/// Register the chaos test. It crashes workers for as long as it is told to,
+/// so only `steel-tester chaos` registers it.
+pub fn tests(settings: &ChaosSettings) -> Vec<TestCase> {
+    let settings = *settings;
+    let tags = &["destructive", "debug-endpoint", "slow"];
+    vec![TestCase::new(
+        format!(
+            "Random worker crashes under load ({}s at {}/s)",
+            settings.duration.as_secs(),
+            settings.rps
+        ),
+        move |client| Box::pin(test_chaos(client, settings)),
+    )
+    .with_timeout(settings.duration + DRAIN_ALLOWANCE)
+    .not_retryable()
+    .with_tags(tags)
+    .requires(Capability::CrashWorker)]
+}
+
+/// Sessions the workload may use, and those whose worker was crashed.
+#[derive(Default)]
+struct Sessions {
+    live: Vec<String>,
+    crashed: HashSet<String>,
+}
+
+/// Outcome counts by label, plus the failures that end the test outright.
+#[derive(Default)]
+struct Tally {
+    outcomes: BTreeMap<String, usize>,
+    /// Requests on sessions whose worker was never crashed.
+    healthy: usize,
+    healthy_errors: usize,
+    fatal: Vec<String>,
+}
+
+/// Run the configured create/GET/delete mix for the chaos duration while a
+/// background task crashes a random live session's worker every few seconds.
+/// No request may get a 500 or hang, requests on uncrashed sessions must stay
+/// under `MAX_ERROR_RATE` errors, and afterwards the whole pool must serve a
+/// concurrent burst of creates. Outcome counts are recorded as metrics, and
+/// failures name the seed that replays the run's choices.
+async fn test_chaos(client: &OrchestratorClient, settings: ChaosSettings) -> Result<(), String> {
+    let pool_size = match settings.pool_size {
+        Some(size) => size,
+        None => client
+            .status()
+            .await
+            .map(|status| status.max_workers)
+            .map_err(|e| {
+                format!("cannot discover pool size from /status ({e}); pass --pool-size")
+            })?,
+    };
+    // Leave room for crashed workers' replacements so creates never queue.
+    let max_live = pool_size.saturating_sub(2).max(1);
+
+    let sessions = Arc::new(Mutex::new(Sessions::default()));
+    let tally = Arc::new(Mutex::new(Tally::default()));
+    let mut rng = Rng::derived(settings.seed, "chaos workload");
+    let crasher = {
+        let client = client.clone();
+        let sessions = Arc::clone(&sessions);
+        let tally = Arc::clone(&tally);
+        let rng = Rng::derived(settings.seed, "chaos crashes");
+        context::spawn(crash_loop(client, sessions, tally, rng))
+    };
+
+    let period = Duration::from_secs(1) / settings.rps;
+    let permits = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let start = Instant::now();
+    let mut next = start;
+    let mut handles = Vec::new();
+    while start.elapsed() < settings.duration {
+        tokio::time::sleep_until(next.into()).await;
+        let permit = permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let (op, target) = pick(&mut rng, &settings.mix, &sessions, max_live);
+        let client = client.clone();
+        let sessions = Arc::clone(&sessions);
+        let tally = Arc::clone(&tally);
+        handles.push(context::spawn(async move {
+            run_op(&client, op, target, &sessions, &tally).await;
+            drop(permit);
+        }));
+        next = (next + period).max(Instant::now() - Duration::from_secs(1));
+    }
+    crasher.abort();
+    futures::future::join_all(handles).await;
+
+    let live = std::mem::take(&mut sessions.lock().unwrap().live);
+    for id in &live {
+        let _ = client.delete_session(id).await;
+    }
+
+    let tally = std::mem::take(&mut *tally.lock().unwrap());
+    for (outcome, n) in &tally.outcomes {
+        context::record_metric(&outcome.replace(' ', "_"), *n as f64);
+    }
+    let seed = settings.seed;
+    if let Some(first) = tally.fatal.first() {
+        return Err(format!(
+            "{} requests got a 500 or hung, first: {first} (--seed {seed})",
+            tally.fatal.len()
+        ));
+    }
+    let error_rate = tally.healthy_errors as f64 / tally.healthy.max(1) as f64;
+    if error_rate > MAX_ERROR_RATE {
+        return Err(format!(
+            "{}/{} requests on uncrashed sessions failed ({:.1}%, limit {:.1}%) (--seed {seed})",
+            tally.healthy_errors,
+            tally.healthy,
+            error_rate * 100.0,
+            MAX_ERROR_RATE * 100.0
+        ));
+    }
+    check_burst(client, pool_size)
+        .await
+        .map_err(|e| format!("{e} (--seed {seed})"))
+}
+
+/// Choose the next operation and its session. Creates stand in for GETs and
+/// deletes while nothing is live, and deletes for creates once `max_live`
+/// sessions are held.
+fn pick(
+    rng: &mut Rng,
+    mix: &Mix,
+    sessions: &Mutex<Sessions>,
+    max_live: usize,
+) -> (Op, Option<String>) {
+    let roll = rng.below((mix.create + mix.get + mix.delete) as usize) as u32;
+    let op = if roll < mix.create {
+        Op::Create
+    } else if roll < mix.create + mix.get {
+        Op::Get
+    } else {
+        Op::Delete
+    };
+    let mut sessions = sessions.lock().unwrap();
+    let live = &mut sessions.live;
+    match op {
+        Op::Create if live.len() < max_live => (Op::Create, None),
+        _ if live.is_empty() => (Op::Create, None),
+        Op::Get => {
+            let id = live[rng.below(live.len())].clone();
+            (Op::Get, Some(id))
+        }
+        Op::Create | Op::Delete => {
+            // Taken out of the live list up front so nothing else picks it.
+            let id = live.swap_remove(rng.below(live.len()));
+            (Op::Delete, Some(id))
+        }
+    }
+}
+
+/// Run one operation and tally its outcome. Failures on sessions whose worker
+/// was crashed are expected and kept out of the error rate, but a 500 or a
+/// hang is fatal whichever session it hits.
+async fn run_op(
+    client: &OrchestratorClient,
+    op: Op,
+    target: Option<String>,
+    sessions: &Mutex<Sessions>,
+    tally: &Mutex<Tally>,
+) {
+    let (label, result) = match (op, &target) {
+        (Op::Create, _) | (_, None) => {
+            let data = serde_json::json!({"user": util::unique("chaos")});
+            let result = tokio::time::timeout(REQUEST_TIMEOUT, client.create_session(data)).await;
+            let result = result.map(|r| {
+                r.map(|session| {
+                    sessions.lock().unwrap().live.push(session.id);
+                })
+            });
+            ("create", result)
+        }
+        (Op::Get, Some(id)) => {
+            let result = tokio::time::timeout(REQUEST_TIMEOUT, client.get_session(id)).await;
+            ("get", result.map(|r| r.map(|_| ())))
+        }
+        (Op::Delete, Some(id)) => {
+            let result = tokio::time::timeout(REQUEST_TIMEOUT, client.delete_session(id)).await;
+            let result = result.map(|r| {
+                r.and_then(|status| match status {
+                    s if s.is_success() => Ok(()),
+                    StatusCode::NOT_FOUND => Err(ClientError::NotFound { request_id: None }),
+                    status => Err(ClientError::Http {
+                        status,
+                        body: String::new(),
+                        request_id: None,
+                        retry_after: None,
+                    }),
+                })
+            });
+            ("delete", result)
+        }
+    };
+
+    let crashed = target
+        .as_ref()
+        .is_some_and(|id| sessions.lock().unwrap().crashed.contains(id));
+    let target = target.as_deref().unwrap_or("new session");
+    let mut tally = tally.lock().unwrap();
+    let outcome = match &result {
+        Ok(Ok(())) => "ok".to_string(),
+        Ok(Err(ClientError::NotFound { .. })) => "404".to_string(),
+        Ok(Err(ClientError::Http { status, .. })) => status.as_u16().to_string(),
+        Ok(Err(ClientError::Transport { .. } | ClientError::Verbatim(_))) => {
+            "connection error".to_string()
+        }
+        Ok(Err(ClientError::Decode { .. })) => "undecodable".to_string(),
+        Err(_) => "hung".to_string(),
+    };
+    match &result {
+        Ok(Err(ClientError::Http { status, body, .. }))
+            if *status == StatusCode::INTERNAL_SERVER_ERROR =>
+        {
+            tally.fatal.push(format!("{label} {target}: 500 {body}"));
+        }
+        Err(_) => tally.fatal.push(format!(
+            "{label} {target}: no answer within {}s",
+            REQUEST_TIMEOUT.as_secs()
+        )),
+        _ => {}
+    }
+    if !crashed {
+        tally.healthy += 1;
+        if !matches!(result, Ok(Ok(()))) {
+            tally.healthy_errors += 1;
+        }
+    }
+    let suffix = if crashed { " (crashed)" } else { "" };
+    *tally
+        .outcomes
+        .entry(format!("{label} {outcome}{suffix}"))
+        .or_default() += 1;
+}
+
+/// Every few seconds, crash the worker of a random live session and retire
+/// the session from the workload.
+async fn crash_loop(
+    client: OrchestratorClient,
+    sessions: Arc<Mutex<Sessions>>,
+    tally: Arc<Mutex<Tally>>,
+    mut rng: Rng,
+) {
+    loop {
+        tokio::time::sleep(rng.duration_between(CRASH_INTERVAL.0, CRASH_INTERVAL.1)).await;
+        let victim = {
+            let mut sessions = sessions.lock().unwrap();
+            if sessions.live.is_empty() {
+                continue;
+            }
+            let index = rng.below(sessions.live.len());
+            let id = sessions.live.swap_remove(index);
+            sessions.crashed.insert(id.clone());
+            id
+        };
+        let outcome = match client.crash_worker(&victim).await {
+            Ok(()) => "crashes",
+            Err(_) => "crashes missed",
+        };
+        *tally
+            .lock()
+            .unwrap()
+            .outcomes
+            .entry(outcome.to_string())
+            .or_default() += 1;
+    }
+}
+
+/// Fire `pool_size` creates at once; all must succeed within `BURST_TIMEOUT`.
+async fn check_burst(client: &OrchestratorClient, pool_size: usize) -> Result<(), String> {
+    let handles: Vec<_> = (0..pool_size)
+        .map(|i| {
+            let client = client.clone();
+            context::spawn(async move {
+                let data = serde_json::json!({"user": util::unique(&format!("chaos_burst_{i}"))});
+                tokio::time::timeout(BURST_TIMEOUT, client.create_session(data)).await
+            })
+        })
+        .collect();
+
+    let mut created = Vec::new();
+    let mut errors = Vec::new();
+    for handle in futures::future::join_all(handles).await {
+        match handle {
+            Ok(Ok(Ok(session))) => created.push(session.id),
+            Ok(Ok(Err(e))) => errors.push(e.to_string()),
+            Ok(Err(_)) => errors.push(format!("timed out after {}s", BURST_TIMEOUT.as_secs())),
+            Err(e) => errors.push(format!("task join error: {e}")),
+        }
+    }
+    for id in &created {
+        let _ = client.delete_session(id).await;
+    }
+    match errors.first() {
+        Some(first) => Err(format!(
+            "after the chaos, {}/{pool_size} burst creates failed: {first}",
+            errors.len()
+        )),
+        None => Ok(()),
+    }
+}
@@ -1,53 +1,539 @@
-use crate::client::OrchestratorClient;
+use crate::client::{ClientError, OrchestratorClient, Session};
+use crate::context;
 use crate::runner::TestCase;
+use crate::tests::util;
+use reqwest::{Method, StatusCode};
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
 
-/// Register concurrent test cases.
-pub fn tests() -> Vec<TestCase> {
-    vec![TestCase {
-        name: "Concurrent sessions (10 parallel)".to_string(),
-        func: Box::new(|client: &OrchestratorClient| {
+/// Rounds each race test runs.
+const RACE_ITERATIONS: usize = 20;
+
+/// How long one side of a race may take before it counts as a hang.
+const RACE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a stress-test create may queue before it counts as timed out.
+const STRESS_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Sessions the isolation test holds at once, fewer if the pool is smaller.
+const ISOLATION_SESSIONS: usize = 20;
+
+/// Rounds of concurrent GETs in the isolation test.
+const ISOLATION_ROUNDS: usize = 3;
+
+/// Sessions the ID format test creates, in batches.
+const ID_SESSIONS: usize = 500;
+
+/// Creates in flight at once in the ID format test, fewer if the pool is
+/// smaller.
+const ID_BATCH: usize = 10;
+
+/// Register concurrent test cases, with a stress test for each number of
+/// creates in `stress_concurrency` to fire at once.
+pub fn tests(stress_concurrency: &[usize]) -> Vec<TestCase> {
+    let mut tests = vec![
+        TestCase::new("Concurrent sessions (10 parallel)", |client| {
             Box::pin(test_concurrent_sessions(client))
         }),
-    }]
+        TestCase::new("Cloned clients (50 parallel create/delete)", |client| {
+            Box::pin(test_cloned_clients(client))
+        }),
+        TestCase::new(
+            format!("Session IDs are unique and uniform ({ID_SESSIONS} sessions)"),
+            |client| Box::pin(test_id_format(client)),
+        ),
+        TestCase::new(
+            format!("Session data isolation ({ISOLATION_SESSIONS} sessions)"),
+            |client| Box::pin(test_isolation(client)),
+        ),
+        TestCase::new(
+            format!("GET racing DELETE ({RACE_ITERATIONS} rounds)"),
+            |client| Box::pin(test_get_delete_race(client)),
+        ),
+        TestCase::new(
+            format!("DELETE racing DELETE ({RACE_ITERATIONS} rounds)"),
+            |client| Box::pin(test_double_delete_race(client)),
+        ),
+    ];
+    tests.extend(TestCase::parameterized(
+        "Stress: {} simultaneous creates",
+        stress_concurrency.to_vec(),
+        |client, count| Box::pin(test_stress(client, count)),
+    ));
+    tests
 }
 
 /// Spawn 10 session-create requests simultaneously and verify all succeed.
-/// Uses its own reqwest clients per task since OrchestratorClient isn't Send.
 async fn test_concurrent_sessions(client: &OrchestratorClient) -> Result<(), String> {
     let count = 10;
-    let base_url: String = client.base_url().to_string();
     let mut handles = Vec::with_capacity(count);
 
     for i in 0..count {
-        let url = base_url.clone();
-        handles.push(tokio::spawn(async move {
-            let http = reqwest::Client::builder()
-                .timeout(std::time::Duration::from_secs(35))
-                .build()
-                .unwrap();
-            let data = serde_json::json!({"user": format!("concurrent_{i}")});
-            let resp = http
-                .post(format!("{url}/sessions"))
-                .json(&data)
-                .send()
+        let client = client.clone();
+        handles.push(context::spawn(async move {
+            let data = serde_json::json!({"user": util::unique(&format!("concurrent_{i}"))});
+            let session = client
+                .create_session(data)
                 .await
                 .map_err(|e| format!("request {i} failed: {e}"))?;
+            Ok(session.id)
+        }));
+    }
 
-            if !resp.status().is_success() {
-                let status = resp.status();
-                let body = resp.text().await.unwrap_or_default();
-                return Err(format!("request {i} returned {status}: {body}"));
-            }
+    // Collect results
+    let (session_ids, errors) = join_all(handles).await;
 
-            let session: crate::client::Session = resp
-                .json()
+    // Cleanup all created sessions (always, even if some failed)
+    for id in &session_ids {
+        let _ = client.delete_session(id).await;
+    }
+
+    check_unique(count, &session_ids, &errors)
+}
+
+/// Regression test for sharing one client across tasks: 50 tasks each create
+/// and immediately delete a session through a clone of the same client. Every
+/// session is released right away, since 50 held sessions would exceed the
+/// worker pool and queue.
+async fn test_cloned_clients(client: &OrchestratorClient) -> Result<(), String> {
+    let count = 50;
+    let mut handles = Vec::with_capacity(count);
+
+    for i in 0..count {
+        let client = client.clone();
+        handles.push(context::spawn(async move {
+            let data = serde_json::json!({"user": util::unique(&format!("cloned_{i}"))});
+            let session = client
+                .create_session(data)
+                .await
+                .map_err(|e| format!("create {i} failed: {e}"))?;
+            client
+                .delete_session(&session.id)
                 .await
-                .map_err(|e| format!("request {i} parse failed: {e}"))?;
+                .map_err(|e| format!("delete {i} failed: {e}"))?;
             Ok(session.id)
         }));
     }
 
-    // Collect results
+    let (session_ids, errors) = join_all(handles).await;
+    check_unique(count, &session_ids, &errors)
+}
+
+/// Create `ID_SESSIONS` sessions in parallel batches, deleting each as soon
+/// as it is created so the pool never fills, and check the IDs together: all
+/// distinct, not a counter, and all of one format. Sessions whose delete
+/// failed are swept through the tracker at the end.
+async fn test_id_format(client: &OrchestratorClient) -> Result<(), String> {
+    let batch = match client.status().await {
+        Ok(status) => status.max_workers.clamp(1, ID_BATCH),
+        Err(_) => ID_BATCH,
+    };
+
+    let mut ids = Vec::with_capacity(ID_SESSIONS);
+    let mut errors = Vec::new();
+    for start in (0..ID_SESSIONS).step_by(batch) {
+        let handles = (start..(start + batch).min(ID_SESSIONS))
+            .map(|i| {
+                let client = client.clone();
+                context::spawn(async move {
+                    let data = serde_json::json!({"user": util::unique(&format!("id_{i}"))});
+                    let session = client
+                        .create_session(data)
+                        .await
+                        .map_err(|e| format!("create {i} failed: {e}"))?;
+                    let _ = client.delete_session(&session.id).await;
+                    Ok(session.id)
+                })
+            })
+            .collect();
+        let (batch_ids, batch_errors) = join_all(handles).await;
+        ids.extend(batch_ids);
+        errors.extend(batch_errors);
+    }
+    sweep_own_sessions(client).await;
+
+    check_unique(ID_SESSIONS, &ids, &errors)?;
+    check_not_sequential(&ids)?;
+    check_uniform(&ids)
+}
+
+/// Delete whatever the current test created and has not yet deleted.
+async fn sweep_own_sessions(client: &OrchestratorClient) {
+    let Some(test) = context::current_test() else {
+        return;
+    };
+    for (id, owner) in client.tracker().outstanding() {
+        if owner == test {
+            let _ = client.delete_session(&id).await;
+        }
+    }
+}
+
+/// Every ID must have the same length and character class.
+fn check_uniform(ids: &[String]) -> Result<(), String> {
+    let mut formats: BTreeMap<(usize, String), (usize, &str)> = BTreeMap::new();
+    for id in ids {
+        let entry = formats
+            .entry((id.len(), id_class(id)))
+            .or_insert((0, id.as_str()));
+        entry.0 += 1;
+    }
+    if formats.len() > 1 {
+        let found = formats
+            .iter()
+            .map(|((len, class), (n, example))| {
+                format!("{n} × {len}-char {class} (e.g. {example:?})")
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        return Err(format!(
+            "session IDs come in {} formats: {found}",
+            formats.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Character class of an ID: "UUID v4" (or another version) for the RFC 4122
+/// layout, else the narrowest of decimal, hex, alphanumeric, and base64url.
+fn id_class(id: &str) -> String {
+    let bytes = id.as_bytes();
+    let uuid_shaped = bytes.len() == 36
+        && bytes.iter().enumerate().all(|(i, b)| match i {
+            8 | 13 | 18 | 23 => *b == b'-',
+            _ => b.is_ascii_hexdigit(),
+        });
+    if uuid_shaped {
+        return match bytes[19] {
+            b'8' | b'9' | b'a' | b'b' | b'A' | b'B' => format!("UUID v{}", bytes[14] as char),
+            _ => "non-RFC 4122 UUID".to_string(),
+        };
+    }
+    let class = if bytes.iter().all(u8::is_ascii_digit) {
+        "decimal"
+    } else if bytes.iter().all(u8::is_ascii_hexdigit) {
+        "hex"
+    } else if bytes.iter().all(u8::is_ascii_alphanumeric) {
+        "alphanumeric"
+    } else if bytes
+        .iter()
+        .all(|b| b.is_ascii_alphanumeric() || *b == b'-' || *b == b'_')
+    {
+        "base64url"
+    } else {
+        "mixed"
+    };
+    class.to_string()
+}
+
+/// Fail if the IDs are one prefix plus a counter, i.e. their integer suffixes,
+/// sorted, each exceed the previous by exactly one.
+fn check_not_sequential(ids: &[String]) -> Result<(), String> {
+    let mut prefix = None;
+    let mut counters = Vec::with_capacity(ids.len());
+    for id in ids {
+        let stem = id.trim_end_matches(|c: char| c.is_ascii_digit());
+        let Ok(counter) = id[stem.len()..].parse::<u128>() else {
+            return Ok(());
+        };
+        if *prefix.get_or_insert(stem) != stem {
+            return Ok(());
+        }
+        counters.push(counter);
+    }
+    counters.sort_unstable();
+    if counters.len() > 1 && counters.windows(2).all(|w| w[1] == w[0] + 1) {
+        let prefix = prefix.unwrap_or_default();
+        return Err(format!(
+            "session IDs are sequential: {prefix}{} through {prefix}{}",
+            counters[0],
+            counters[counters.len() - 1]
+        ));
+    }
+    Ok(())
+}
+
+/// Create sessions concurrently, each with its own marker, then fetch them
+/// all concurrently, several rounds over: every session must return its own
+/// marker, never another's. Capped at the pool size from /status, since the
+/// sessions are held for the whole test.
+async fn test_isolation(client: &OrchestratorClient) -> Result<(), String> {
+    let count = match client.status().await {
+        Ok(status) if status.max_workers < ISOLATION_SESSIONS => {
+            context::warn(format!(
+                "pool holds {} sessions, so only {} were checked",
+                status.max_workers, status.max_workers
+            ));
+            status.max_workers
+        }
+        _ => ISOLATION_SESSIONS,
+    };
+
+    let handles = (0..count)
+        .map(|i| {
+            let client = client.clone();
+            context::spawn(async move {
+                let marker = util::unique(&format!("isolation_{i}"));
+                let session = client
+                    .create_session(serde_json::json!({"marker": marker}))
+                    .await
+                    .map_err(|e| format!("create {i} failed: {e}"))?;
+                Ok((session.id, marker))
+            })
+        })
+        .collect::<Vec<_>>();
+    let mut sessions = Vec::with_capacity(count);
+    let mut errors = Vec::new();
+    for handle in futures::future::join_all(handles).await {
+        match handle {
+            Ok(Ok(session)) => sessions.push(session),
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(format!("task join error: {e}")),
+        }
+    }
+
+    let result = match errors.first() {
+        Some(first) => Err(format!("{}/{count} creates failed: {first}", errors.len())),
+        None => check_markers(client, &sessions).await,
+    };
+
+    let deletes = sessions
+        .into_iter()
+        .map(|(id, _)| {
+            let client = client.clone();
+            context::spawn(async move { client.delete_session(&id).await })
+        })
+        .collect::<Vec<_>>();
+    futures::future::join_all(deletes).await;
+    result
+}
+
+/// Fetch every (session ID, marker) pair concurrently, `ISOLATION_ROUNDS`
+/// times, reporting each session that returned someone else's marker.
+async fn check_markers(
+    client: &OrchestratorClient,
+    sessions: &[(String, String)],
+) -> Result<(), String> {
+    let owners: HashMap<&str, &str> = sessions
+        .iter()
+        .map(|(id, marker)| (marker.as_str(), id.as_str()))
+        .collect();
+
+    for round in 1..=ISOLATION_ROUNDS {
+        let handles = sessions
+            .iter()
+            .map(|(id, _)| {
+                let client = client.clone();
+                let id = id.clone();
+                context::spawn(async move { client.get_session(&id).await })
+            })
+            .collect::<Vec<_>>();
+
+        let mut mixups = Vec::new();
+        for ((id, marker), handle) in sessions
+            .iter()
+            .zip(futures::future::join_all(handles).await)
+        {
+            let fetched = match handle {
+                Ok(Ok(session)) => session,
+                Ok(Err(e)) => {
+                    mixups.push(format!("GET {id} failed: {e}"));
+                    continue;
+                }
+                Err(e) => return Err(format!("task join error: {e}")),
+            };
+            let returned = fetched.data.get("marker").and_then(|v| v.as_str());
+            if returned == Some(marker.as_str()) {
+                continue;
+            }
+            mixups.push(
+                match returned.and_then(|m| owners.get(m).map(|owner| (m, owner))) {
+                    Some((m, owner)) => format!("{id} returned {m}, the marker of {owner}"),
+                    None => format!("{id} returned {} instead of {marker}", fetched.data),
+                },
+            );
+        }
+        if !mixups.is_empty() {
+            return Err(format!(
+                "round {round}/{ISOLATION_ROUNDS}: {}/{} sessions returned the wrong data: {}",
+                mixups.len(),
+                sessions.len(),
+                mixups.join("; ")
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// GET and DELETE the same session at the same moment. The GET must see the
+/// session (200 with a well-formed body) or not (404), and the DELETE must
+/// succeed.
+async fn test_get_delete_race(client: &OrchestratorClient) -> Result<(), String> {
+    for round in 1..=RACE_ITERATIONS {
+        let data = serde_json::json!({"user": util::unique("race_get_delete")});
+        let session = client.create_session(data).await?;
+
+        let (get, delete) = {
+            let get_client = client.clone();
+            let delete_client = client.clone();
+            let path = format!("/sessions/{}", session.id);
+            let id = session.id.clone();
+            util::race(
+                RACE_TIMEOUT,
+                async move {
+                    let req = get_client.request(Method::GET, &path);
+                    get_client.send_raw(req).await
+                },
+                async move { delete_client.delete_session(&id).await },
+            )
+            .await
+        };
+        let _ = client.delete_session(&session.id).await;
+
+        let get = get.map_err(|e| format!("round {round}: GET {e}"))?;
+        match get {
+            Ok(resp) if resp.status == StatusCode::OK => {
+                serde_json::from_str::<Session>(&resp.body).map_err(|e| {
+                    format!("round {round}: GET returned a malformed session ({e}): {resp}")
+                })?;
+            }
+            Ok(resp) if resp.status == StatusCode::NOT_FOUND => {}
+            Ok(resp) => return Err(format!("round {round}: GET returned {resp}")),
+            Err(e) => return Err(format!("round {round}: GET failed: {e}")),
+        }
+        match delete.map_err(|e| format!("round {round}: DELETE {e}"))? {
+            Ok(status) if status.is_success() => {}
+            Ok(status) => return Err(format!("round {round}: DELETE returned {status}")),
+            Err(e) => return Err(format!("round {round}: DELETE failed: {e}")),
+        }
+    }
+    Ok(())
+}
+
+/// DELETE the same session twice at the same moment: exactly one may succeed,
+/// the other must get 404, and neither may fail with 5xx.
+async fn test_double_delete_race(client: &OrchestratorClient) -> Result<(), String> {
+    for round in 1..=RACE_ITERATIONS {
+        let data = serde_json::json!({"user": util::unique("race_double_delete")});
+        let session = client.create_session(data).await?;
+
+        let (first, second) = {
+            let (a, b) = (client.clone(), client.clone());
+            let (id_a, id_b) = (session.id.clone(), session.id.clone());
+            util::race(
+                RACE_TIMEOUT,
+                async move { a.delete_session(&id_a).await },
+                async move { b.delete_session(&id_b).await },
+            )
+            .await
+        };
+
+        let mut statuses = Vec::with_capacity(2);
+        for result in [first, second] {
+            match result.map_err(|e| format!("round {round}: DELETE {e}"))? {
+                Ok(status) => statuses.push(status),
+                Err(e) => return Err(format!("round {round}: DELETE failed: {e}")),
+            }
+        }
+        let wins = statuses.iter().filter(|s| s.is_success()).count();
+        let not_found = statuses
+            .iter()
+            .filter(|s| **s == StatusCode::NOT_FOUND)
+            .count();
+        if wins != 1 || not_found != 1 {
+            return Err(format!(
+                "round {round}: racing DELETEs returned {} and {}; expected one success and one 404",
+                statuses[0], statuses[1]
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fire `count` creates at once and hold every session until all have
+/// answered. Each must be served or turned away with 429/503; anything else
+/// (500, reset connection, a create still queued after
+/// `STRESS_REQUEST_TIMEOUT`) fails the test. The outcome counts are recorded
+/// as metrics.
+async fn test_stress(client: &OrchestratorClient, count: usize) -> Result<(), String> {
+    let handles: Vec<_> = (0..count)
+        .map(|i| {
+            let client = client.clone();
+            context::spawn(async move {
+                let data = serde_json::json!({"user": util::unique(&format!("stress_{i}"))});
+                tokio::time::timeout(STRESS_REQUEST_TIMEOUT, client.create_session(data)).await
+            })
+        })
+        .collect();
+
+    let mut created = Vec::new();
+    let mut outcomes: BTreeMap<String, usize> = BTreeMap::new();
+    let mut failures = 0;
+    for handle in futures::future::join_all(handles).await {
+        let outcome = match handle {
+            Ok(Ok(Ok(session))) => {
+                created.push(session.id);
+                "created".to_string()
+            }
+            Ok(Ok(Err(ClientError::Http { status, .. })))
+                if status == StatusCode::TOO_MANY_REQUESTS
+                    || status == StatusCode::SERVICE_UNAVAILABLE =>
+            {
+                format!("rejected with {}", status.as_u16())
+            }
+            Ok(Ok(Err(e))) => {
+                failures += 1;
+                match e {
+                    ClientError::Http { status, .. } => format!("failed with {}", status.as_u16()),
+                    ClientError::NotFound { .. } => "failed with 404".to_string(),
+                    ClientError::Transport { .. } | ClientError::Verbatim(_) => {
+                        "connection errors".to_string()
+                    }
+                    ClientError::Decode { .. } => "undecodable responses".to_string(),
+                }
+            }
+            Ok(Err(_)) => {
+                failures += 1;
+                "timed out".to_string()
+            }
+            Err(_) => {
+                failures += 1;
+                "task errors".to_string()
+            }
+        };
+        *outcomes.entry(outcome).or_default() += 1;
+    }
+
+    let deletes: Vec<_> = created
+        .into_iter()
+        .map(|id| {
+            let client = client.clone();
+            context::spawn(async move { client.delete_session(&id).await })
+        })
+        .collect();
+    futures::future::join_all(deletes).await;
+
+    for (outcome, n) in &outcomes {
+        context::record_metric(&outcome.replace(' ', "_"), *n as f64);
+    }
+    let summary = outcomes
+        .iter()
+        .map(|(outcome, n)| format!("{n} {outcome}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+    if failures > 0 {
+        return Err(format!(
+            "{failures}/{count} requests got neither a session nor 429/503 within {}s ({summary})",
+            STRESS_REQUEST_TIMEOUT.as_secs()
+        ));
+    }
+    Ok(())
+}
+
+/// Await every task, splitting session IDs from error messages.
+async fn join_all(
+    handles: Vec<tokio::task::JoinHandle<Result<String, String>>>,
+) -> (Vec<String>, Vec<String>) {
     let mut session_ids = Vec::new();
     let mut errors = Vec::new();
 
@@ -58,12 +544,11 @@ async fn test_concurrent_sessions(client: &OrchestratorClient) -> Result<(), Str
             Err(e) => errors.push(format!("task join error: {e}")),
         }
     }
+    (session_ids, errors)
+}
 
-    // Cleanup all created sessions (always, even if some failed)
-    for id in &session_ids {
-        let _ = client.delete_session(id).await;
-    }
-
+/// Fail unless all `count` tasks succeeded with distinct session IDs.
+fn check_unique(count: usize, session_ids: &[String], errors: &[String]) -> Result<(), String> {
     if !errors.is_empty() {
         return Err(format!(
             "{}/{count} failed: {}",
@@ -0,0 +1,130 @@
+use crate::client::{OrchestratorClient, RawResponse};
+use crate::runner::TestCase;
+use crate::tests::util;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, ACCESS_CONTROL_ALLOW_HEADERS, ACCESS_CONTROL_ALLOW_METHODS,
+    ACCESS_CONTROL_ALLOW_ORIGIN, ACCESS_CONTROL_REQUEST_HEADERS, ACCESS_CONTROL_REQUEST_METHOD,
+    ORIGIN,
+};
+use reqwest::Method;
+
+/// Register CORS test cases for a dashboard served from `origin`. Without
+/// one there is nothing to expect, so they are skipped.
+pub fn tests(origin: Option<&str>) -> Vec<TestCase> {
+    let Some(origin) = origin else {
+        return [
+            "Preflight for POST /sessions is allowed",
+            "GET responses allow the origin",
+        ]
+        .into_iter()
+        .map(|name| {
+            TestCase::new(name, |_| Box::pin(async { Ok(()) })).skip("requires --cors-origin")
+        })
+        .collect();
+    };
+    let origin = origin.to_string();
+    let get_origin = origin.clone();
+    vec![
+        TestCase::new("Preflight for POST /sessions is allowed", move |client| {
+            let origin = origin.clone();
+            Box::pin(async move { test_preflight(client, &origin).await })
+        }),
+        TestCase::new("GET responses allow the origin", move |client| {
+            let origin = get_origin.clone();
+            Box::pin(async move { test_simple_get(client, &origin).await })
+        }),
+    ]
+}
+
+/// The preflight a browser sends before a JSON create must get a 2xx allowing
+/// the origin, POST, and the Content-Type header. Every header that is
+/// missing or doesn't allow its part is listed.
+async fn test_preflight(client: &OrchestratorClient, origin: &str) -> Result<(), String> {
+    let headers = request_headers(&[
+        (ORIGIN, origin),
+        (ACCESS_CONTROL_REQUEST_METHOD, "POST"),
+        (ACCESS_CONTROL_REQUEST_HEADERS, "content-type"),
+    ]);
+    let resp = client
+        .request_raw_with(Method::OPTIONS, "/sessions", None, headers)
+        .await?;
+
+    let mut problems = Vec::new();
+    if !resp.status.is_success() {
+        problems.push(format!("status is {}, not 2xx", resp.status));
+    }
+    problems.extend(check_origin(&resp, origin));
+    problems.extend(check_listed(&resp, ACCESS_CONTROL_ALLOW_METHODS, "POST"));
+    problems.extend(check_listed(
+        &resp,
+        ACCESS_CONTROL_ALLOW_HEADERS,
+        "content-type",
+    ));
+    if problems.is_empty() {
+        Ok(())
+    } else {
+        Err(problems.join("; "))
+    }
+}
+
+/// A plain GET of a session from the dashboard's origin must carry
+/// Access-Control-Allow-Origin, or the browser hides the response.
+async fn test_simple_get(client: &OrchestratorClient, origin: &str) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("cors")});
+    let session = client.create_session(data).await?;
+    let resp = client
+        .request_raw_with(
+            Method::GET,
+            &format!("/sessions/{}", session.id),
+            None,
+            request_headers(&[(ORIGIN, origin)]),
+        )
+        .await;
+    let _ = client.delete_session(&session.id).await;
+    let resp = resp?;
+
+    if !resp.status.is_success() {
+        return Err(format!("expected the session, got {resp}"));
+    }
+    match check_origin(&resp, origin) {
+        Some(problem) => Err(problem),
+        None => Ok(()),
+    }
+}
+
+fn request_headers(headers: &[(HeaderName, &str)]) -> HeaderMap {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = HeaderValue::from_str(value).expect("CORS request headers are valid");
+            (name.clone(), value)
+        })
+        .collect()
+}
+
+/// Access-Control-Allow-Origin must be `origin` or `*`.
+fn check_origin(resp: &RawResponse, origin: &str) -> Option<String> {
+    match header(resp, &ACCESS_CONTROL_ALLOW_ORIGIN) {
+        None => Some(format!("{ACCESS_CONTROL_ALLOW_ORIGIN} is missing")),
+        Some(allowed) if allowed == origin || allowed == "*" => None,
+        Some(allowed) => Some(format!(
+            "{ACCESS_CONTROL_ALLOW_ORIGIN} is {allowed:?}, expected {origin:?} or \"*\""
+        )),
+    }
+}
+
+/// `name` must list `wanted`, compared case-insensitively, or be `*`.
+fn check_listed(resp: &RawResponse, name: HeaderName, wanted: &str) -> Option<String> {
+    let Some(listed) = header(resp, &name) else {
+        return Some(format!("{name} is missing"));
+    };
+    let allowed = listed
+        .split(',')
+        .map(str::trim)
+        .any(|item| item == "*" || item.eq_ignore_ascii_case(wanted));
+    (!allowed).then(|| format!("{name} is {listed:?}, which doesn't allow {wanted}"))
+}
+
+fn header<'r>(resp: &'r RawResponse, name: &HeaderName) -> Option<&'r str> {
+    resp.headers.get(name).and_then(|value| value.to_str().ok())
+}
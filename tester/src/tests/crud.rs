@@ -1,39 +1,272 @@
-use crate::client::OrchestratorClient;
+use crate::capabilities::Capability;
+use crate::client::{ClientError, OrchestratorClient, Session, TimestampFormat};
+use crate::context;
 use crate::runner::TestCase;
+use crate::tests::util::{self, Rng};
+use base64::Engine as _;
+use chrono::{DateTime, SecondsFormat, Utc};
+use reqwest::header::CONTENT_TYPE;
+use reqwest::Method;
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+
+/// The orchestrator routes only GET and DELETE on /sessions/:id, so PUT is
+/// answered with 405. Drop the marker once updates are implemented.
+const NO_UPDATE: &str = "orchestrator has no PUT /sessions/:id yet";
+
+/// Likewise, GET /sessions (listing) is not routed yet.
+const NO_LIST: &str = "orchestrator has no GET /sessions yet";
+
+/// Values the reserved-key test plants in user data.
+const SPOOFED_ID: &str = "spoofed";
+const SPOOFED_CREATED_AT: &str = "1999-01-01";
+
+/// Sessions the listing-consistency test creates, and how many it deletes
+/// before the final cleanup.
+const LISTED_SESSIONS: usize = 5;
+const LISTED_DELETES: usize = 2;
+
+/// Sessions the pagination test pages through, and its page size.
+const PAGED_SESSIONS: usize = 25;
+const PAGE_LIMIT: usize = 10;
+
+/// A limit no orchestrator should honour in full without capping it.
+const ABSURD_LIMIT: usize = 10_000;
+
+/// Pages the pagination test reads before deciding `offset` is ignored.
+const MAX_PAGES: usize = 1000;
+
+/// Pause before the listing-consistency test's one re-list, for a TTL sweep
+/// or a delete still in flight to settle.
+const RELIST_DELAY: Duration = Duration::from_millis(500);
+
+/// Bytes in the binary round-trip test's blob, before base64 encoding.
+const BINARY_BLOB_SIZE: usize = 200 * 1024;
+
+/// Base64 line length of MIME, which breaks lines with CRLF.
+const MIME_LINE: usize = 76;
+
+/// Gap between the two creates of the created_at test, enough for any
+/// timestamp resolution to tick over.
+const CREATED_AT_GAP: Duration = Duration::from_secs(1);
+
+/// Create/get/delete cycles the cold-start test runs between its cold and
+/// warm creates.
+const WARM_UP_CYCLES: usize = 5;
+
+/// Tolerances and payload seed for the CRUD checks.
+#[derive(Debug, Clone, Copy)]
+pub struct CrudSettings {
+    /// Numbers that come back as the same float, having lost integer precision
+    /// or the sign of zero, are reported as warnings instead of failures.
+    pub allow_numeric_lossy: bool,
+    /// How far a `created_at` may be from the tester's clock.
+    pub clock_skew_tolerance: Duration,
+    /// Seeds the binary blob, so a failing one can be regenerated.
+    pub seed: u64,
+    /// Longest the suite's first create may take to its first byte; None
+    /// only reports it.
+    pub cold_start_budget: Option<Duration>,
+}
+
+impl Default for CrudSettings {
+    fn default() -> Self {
+        Self {
+            allow_numeric_lossy: false,
+            clock_skew_tolerance: Duration::from_secs(60),
+            seed: 0,
+            cold_start_budget: None,
+        }
+    }
+}
 
 /// Register CRUD test cases.
-pub fn tests() -> Vec<TestCase> {
+pub fn tests(settings: &CrudSettings) -> Vec<TestCase> {
+    let CrudSettings {
+        allow_numeric_lossy,
+        clock_skew_tolerance,
+        seed,
+        cold_start_budget,
+    } = *settings;
     vec![
-        TestCase {
-            name: "Create session".to_string(),
-            func: Box::new(|client: &OrchestratorClient| {
-                Box::pin(test_create_session(client))
-            }),
-        },
-        TestCase {
-            name: "Get session".to_string(),
-            func: Box::new(|client: &OrchestratorClient| {
-                Box::pin(test_get_session(client))
-            }),
-        },
-        TestCase {
-            name: "Delete session".to_string(),
-            func: Box::new(|client: &OrchestratorClient| {
-                Box::pin(test_delete_session(client))
-            }),
-        },
-        TestCase {
-            name: "404 on missing session".to_string(),
-            func: Box::new(|client: &OrchestratorClient| {
-                Box::pin(test_missing_session(client))
-            }),
-        },
+        TestCase::new("Cold-start create latency", move |client| {
+            Box::pin(test_cold_start(client, cold_start_budget))
+        })
+        .run_first(),
+        TestCase::new("Create session", |client| {
+            Box::pin(test_create_session(client))
+        }),
+        TestCase::new("Get session", |client| Box::pin(test_get_session(client))),
+        TestCase::new("Delete session", |client| {
+            Box::pin(test_delete_session(client))
+        }),
+        TestCase::new("Double delete returns 404", |client| {
+            Box::pin(test_double_delete(client))
+        }),
+        TestCase::new("404 on missing session", |client| {
+            Box::pin(test_missing_session(client))
+        }),
+        TestCase::new(
+            "created_at is a current, non-decreasing time",
+            move |client| Box::pin(test_created_at(client, clock_skew_tolerance)),
+        ),
+        TestCase::new("User data cannot spoof id or created_at", |client| {
+            Box::pin(test_reserved_keys(client))
+        }),
+        TestCase::new("Unicode data round-trips", |client| {
+            Box::pin(test_unicode_round_trip(client))
+        }),
+        TestCase::new("Base64 binary data round-trips", move |client| {
+            Box::pin(test_binary_round_trip(client, seed))
+        }),
+        TestCase::new("Numeric data round-trips", move |client| {
+            Box::pin(test_numeric_round_trip(client, allow_numeric_lossy))
+        }),
+        TestCase::new("Update session", |client| {
+            Box::pin(test_update_session(client))
+        })
+        .expected_failure(NO_UPDATE),
+        TestCase::new("Update missing session returns 404", |client| {
+            Box::pin(test_update_missing_session(client))
+        })
+        .expected_failure(NO_UPDATE),
+        TestCase::new("Update deleted session returns 404", |client| {
+            Box::pin(test_update_deleted_session(client))
+        })
+        .expected_failure(NO_UPDATE),
+        TestCase::new("List sessions", |client| {
+            Box::pin(test_list_sessions(client))
+        })
+        .requires(Capability::ListSessions)
+        .expected_failure(NO_LIST),
+        TestCase::new("Listing tracks creates and deletes", |client| {
+            Box::pin(test_listing_consistency(client))
+        })
+        .requires(Capability::ListSessions)
+        .expected_failure(NO_LIST),
+        TestCase::new("Listing paginates with limit and offset", |client| {
+            Box::pin(test_pagination(client))
+        })
+        .requires(Capability::ListSessions)
+        .expected_failure(NO_LIST),
     ]
 }
 
+/// The run's first create, before anything else has warmed the orchestrator
+/// up, then a create after `WARM_UP_CYCLES` create/get/delete cycles. Both
+/// latencies are recorded; the cold one must reach its first byte within
+/// `budget`, if one is set. Only the first `--repeat` iteration is cold.
+async fn test_cold_start(
+    client: &OrchestratorClient,
+    budget: Option<Duration>,
+) -> Result<(), String> {
+    let cold = client
+        .create_session_timed(serde_json::json!({"user": util::unique("cold_start")}))
+        .await?;
+    let _ = client.delete_session(&cold.value.id).await;
+    for _ in 0..WARM_UP_CYCLES {
+        let session = client
+            .create_session(serde_json::json!({"user": util::unique("warm_up")}))
+            .await?;
+        let fetched = client.get_session(&session.id).await;
+        client.delete_session(&session.id).await?;
+        fetched?;
+    }
+    let warm = client
+        .create_session_timed(serde_json::json!({"user": util::unique("warm_start")}))
+        .await?;
+    let _ = client.delete_session(&warm.value.id).await;
+
+    for (name, latency) in [
+        ("cold_first_byte_ms", cold.first_byte),
+        ("cold_total_ms", cold.total),
+        ("warm_first_byte_ms", warm.first_byte),
+        ("warm_total_ms", warm.total),
+    ] {
+        context::record_metric(name, latency.as_millis() as f64);
+    }
+    match budget {
+        Some(budget) if cold.first_byte > budget => Err(format!(
+            "cold create took {}ms to its first byte, over the {}ms budget (warm: {}ms)",
+            cold.first_byte.as_millis(),
+            budget.as_millis(),
+            warm.first_byte.as_millis()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Base64 strings as workers store screenshots and PDFs must come back
+/// byte-exact: a seeded `BINARY_BLOB_SIZE` blob, the same encoding broken into
+/// CRLF-separated MIME lines, and encodings ending in no, one and two `=`.
+/// Failures report lengths and SHA-256 checksums, never the data.
+async fn test_binary_round_trip(client: &OrchestratorClient, seed: u64) -> Result<(), String> {
+    let mut rng = Rng::derived(seed, "binary_blob");
+    let bytes: Vec<u8> = (0..BINARY_BLOB_SIZE)
+        .map(|_| (rng.next_u64() >> 56) as u8)
+        .collect();
+    let base64 = base64::engine::general_purpose::STANDARD;
+    let blob = base64.encode(&bytes);
+    let mime = blob
+        .as_bytes()
+        .chunks(MIME_LINE)
+        .map(|line| std::str::from_utf8(line).expect("base64 is ASCII"))
+        .collect::<Vec<_>>()
+        .join("\r\n");
+    let mut fields = vec![("mime".to_string(), mime)];
+    // 30, 31 and 32 bytes encode with no, two and one padding characters.
+    for len in [30, 31, 32] {
+        fields.push((format!("padded_{len}"), base64.encode(&bytes[..len])));
+    }
+
+    let mut data = serde_json::json!({"user": util::unique("test_binary"), "blob": blob});
+    for (name, value) in &fields {
+        data[name] = value.clone().into();
+    }
+    let created = client.create_session(data).await?;
+    let fetched = client.get_session(&created.id).await;
+    let _ = client.delete_session(&created.id).await;
+    let fetched = fetched?;
+
+    let returned = fetched.data["blob"].as_str().unwrap_or_default();
+    let decoded = base64.decode(returned).map_err(|e| {
+        format!(
+            "blob came back as {} chars of invalid base64: {e}",
+            returned.len()
+        )
+    })?;
+    if decoded != bytes {
+        return Err(format!(
+            "blob changed: sent {} bytes (sha256 {}), got {} bytes (sha256 {})",
+            bytes.len(),
+            sha256(&bytes),
+            decoded.len(),
+            sha256(&decoded)
+        ));
+    }
+    for (name, sent) in &fields {
+        let returned = fetched.data[name].as_str().unwrap_or_default();
+        if returned != sent {
+            return Err(format!(
+                "{name} changed: sent {} chars (sha256 {}), got {} chars (sha256 {})",
+                sent.len(),
+                sha256(sent.as_bytes()),
+                returned.len(),
+                sha256(returned.as_bytes())
+            ));
+        }
+    }
+    Ok(())
+}
+
+fn sha256(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
 /// POST /sessions should return a valid session with id, created_at, and data.
 async fn test_create_session(client: &OrchestratorClient) -> Result<(), String> {
-    let data = serde_json::json!({"user": "test_create"});
+    let user = util::unique("test_create");
+    let data = serde_json::json!({"user": user});
     let session = client.create_session(data.clone()).await?;
 
     if session.id.is_empty() {
@@ -42,9 +275,8 @@ async fn test_create_session(client: &OrchestratorClient) -> Result<(), String>
     if session.created_at.is_null() {
         return Err("created_at is null".into());
     }
-    if session.data.get("user").and_then(|v| v.as_str()) != Some("test_create") {
-        return Err(format!("unexpected data: {:?}", session.data));
-    }
+    util::assert_json_eq(&data, &session.data)
+        .map_err(|diff| format!("create returned altered data: {diff}"))?;
 
     // Cleanup
     let _ = client.delete_session(&session.id).await;
@@ -53,17 +285,17 @@ async fn test_create_session(client: &OrchestratorClient) -> Result<(), String>
 
 /// GET /sessions/:id should return the same session that was created.
 async fn test_get_session(client: &OrchestratorClient) -> Result<(), String> {
-    let data = serde_json::json!({"user": "test_get"});
-    let created = client.create_session(data).await?;
+    let user = util::unique("test_get");
+    let data = serde_json::json!({"user": user});
+    let created = client.create_session(data.clone()).await?;
 
     let fetched = client.get_session(&created.id).await?;
 
     if fetched.id != created.id {
         return Err(format!("id mismatch: {} != {}", fetched.id, created.id));
     }
-    if fetched.data.get("user").and_then(|v| v.as_str()) != Some("test_get") {
-        return Err(format!("data mismatch: {:?}", fetched.data));
-    }
+    util::assert_json_eq(&data, &fetched.data)
+        .map_err(|diff| format!("GET returned altered data: {diff}"))?;
 
     // Cleanup
     let _ = client.delete_session(&created.id).await;
@@ -72,7 +304,7 @@ async fn test_get_session(client: &OrchestratorClient) -> Result<(), String> {
 
 /// DELETE /sessions/:id should return 204 and subsequent GET should return 404.
 async fn test_delete_session(client: &OrchestratorClient) -> Result<(), String> {
-    let data = serde_json::json!({"user": "test_delete"});
+    let data = serde_json::json!({"user": util::unique("test_delete")});
     let session = client.create_session(data).await?;
 
     let status = client.delete_session(&session.id).await?;
@@ -82,17 +314,686 @@ async fn test_delete_session(client: &OrchestratorClient) -> Result<(), String>
 
     // Verify it's actually gone
     match client.get_session(&session.id).await {
-        Err(e) if e == "404" => Ok(()),
+        Err(ClientError::NotFound { .. }) => Ok(()),
         Ok(s) => Err(format!("session still exists after delete: {:?}", s.id)),
         Err(e) => Err(format!("unexpected error after delete: {e}")),
     }
 }
 
+/// A second DELETE of the same session should return 404, not a server error.
+async fn test_double_delete(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("test_double_delete")});
+    let session = client.create_session(data).await?;
+
+    let first = client.delete_session(&session.id).await?;
+    if first.as_u16() != 204 {
+        return Err(format!("first delete: expected 204, got {first}"));
+    }
+
+    let second = client.delete_session(&session.id).await?;
+    if second.as_u16() != 404 {
+        return Err(format!("second delete: expected 404, got {second}"));
+    }
+    Ok(())
+}
+
 /// GET /sessions/<invalid-id> should return 404.
 async fn test_missing_session(client: &OrchestratorClient) -> Result<(), String> {
     match client.get_session("nonexistent-session-id-12345").await {
-        Err(e) if e == "404" => Ok(()),
+        Err(ClientError::NotFound { .. }) => Ok(()),
         Ok(_) => Err("expected 404 but got a session".into()),
         Err(e) => Err(format!("unexpected error: {e}")),
     }
 }
+
+/// Two sessions created a second apart must have `created_at` values that
+/// parse, are within `tolerance` of the tester's clock, and do not go
+/// backwards. Epoch milliseconds are accepted but reported as a warning.
+async fn test_created_at(client: &OrchestratorClient, tolerance: Duration) -> Result<(), String> {
+    let first = create_timed(client, "test_created_at_1").await?;
+    tokio::time::sleep(CREATED_AT_GAP).await;
+    let second = create_timed(client, "test_created_at_2").await;
+    let _ = client.delete_session(&first.session.id).await;
+    let second = second?;
+    let _ = client.delete_session(&second.session.id).await;
+
+    let (first_time, first_format) = parse_created_at(&first.session)?;
+    let (second_time, second_format) = parse_created_at(&second.session)?;
+    if first_format != second_format {
+        return Err(format!(
+            "created_at format changed between sessions: {first_format} then {second_format}"
+        ));
+    }
+    if first_format == TimestampFormat::EpochMillis {
+        context::warn("created_at is epoch milliseconds, not RFC 3339");
+    }
+    check_skew(&first, first_time, tolerance)?;
+    check_skew(&second, second_time, tolerance)?;
+    if second_time < first_time {
+        return Err(format!(
+            "created_at went backwards: {} then, {}s later, {} ({first_format})",
+            first.session.created_at,
+            CREATED_AT_GAP.as_secs(),
+            second.session.created_at
+        ));
+    }
+    Ok(())
+}
+
+/// A created session with the tester's clock just before and after the
+/// create.
+struct TimedSession {
+    session: Session,
+    sent: DateTime<Utc>,
+    received: DateTime<Utc>,
+}
+
+async fn create_timed(client: &OrchestratorClient, prefix: &str) -> Result<TimedSession, String> {
+    let sent = Utc::now();
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique(prefix)}))
+        .await?;
+    Ok(TimedSession {
+        session,
+        sent,
+        received: Utc::now(),
+    })
+}
+
+fn parse_created_at(session: &Session) -> Result<(DateTime<Utc>, TimestampFormat), String> {
+    session.created_time().ok_or_else(|| {
+        format!(
+            "created_at {} is neither RFC 3339 nor epoch milliseconds",
+            session.created_at
+        )
+    })
+}
+
+/// `time` must fall within `tolerance` of the window the create was sent in.
+fn check_skew(
+    timed: &TimedSession,
+    time: DateTime<Utc>,
+    tolerance: Duration,
+) -> Result<(), String> {
+    let tolerance = chrono::Duration::from_std(tolerance).unwrap_or(chrono::Duration::MAX);
+    let skew = if time < timed.sent {
+        timed.sent - time
+    } else if time > timed.received {
+        time - timed.received
+    } else {
+        return Ok(());
+    };
+    if skew > tolerance {
+        return Err(format!(
+            "created_at {} is {}s off the tester's clock ({}); see --clock-skew-tolerance",
+            timed.session.created_at,
+            skew.num_seconds(),
+            timed.sent.to_rfc3339_opts(SecondsFormat::Millis, true)
+        ));
+    }
+    Ok(())
+}
+
+/// User data with its own `id` and `created_at` must stay user data: the
+/// session keeps the orchestrator's ID and timestamp, the user's values come
+/// back untouched under `data`, and only the real ID finds the session.
+async fn test_reserved_keys(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({
+        "id": SPOOFED_ID,
+        "created_at": SPOOFED_CREATED_AT,
+        "nested": {"id": SPOOFED_ID, "user": util::unique("test_reserved_keys")},
+    });
+    let created = client.create_session(data.clone()).await?;
+    let result = check_reserved_keys(client, &created, &data).await;
+    let _ = client.delete_session(&created.id).await;
+    result
+}
+
+async fn check_reserved_keys(
+    client: &OrchestratorClient,
+    created: &Session,
+    data: &serde_json::Value,
+) -> Result<(), String> {
+    if created.id.is_empty() || created.id == SPOOFED_ID {
+        return Err(format!(
+            "session id is {:?}, taken from user data",
+            created.id
+        ));
+    }
+    if created.created_at == SPOOFED_CREATED_AT {
+        return Err(format!(
+            "created_at is {}, taken from user data",
+            created.created_at
+        ));
+    }
+    util::assert_json_eq(data, &created.data)
+        .map_err(|diff| format!("create returned altered user data: {diff}"))?;
+
+    let fetched = client
+        .get_session(&created.id)
+        .await
+        .map_err(|e| format!("GET by the real id {} failed: {e}", created.id))?;
+    if fetched.id != created.id {
+        return Err(format!(
+            "GET {} returned session {}",
+            created.id, fetched.id
+        ));
+    }
+    util::assert_json_eq(data, &fetched.data)
+        .map_err(|diff| format!("GET returned altered user data: {diff}"))?;
+
+    match client.get_session(SPOOFED_ID).await {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(session) => Err(format!(
+            "GET {SPOOFED_ID} found session {}; the user's id was registered",
+            session.id
+        )),
+        Err(e) => Err(format!("GET {SPOOFED_ID}: expected 404, got {e}")),
+    }
+}
+
+/// Data with emoji, CJK and RTL text, an escaped NUL, and awkward keys must
+/// come back exactly as sent, in a response that is declared UTF-8 or has no
+/// charset at all.
+async fn test_unicode_round_trip(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({
+        "user": util::unique("test_unicode"),
+        "emoji": "🚀🔥 👩‍👩‍👧 🏳️‍🌈",
+        "cjk": "会话管理 テスト 세션",
+        "rtl": "مرحبا بالعالم שלום",
+        "nul": "before\u{0}after",
+        "dotted.key": "dots",
+        "slashed/key": "slashes",
+        "a.b/c": {"nested": "ünïcödé"},
+        "k".repeat(1024): "very long key",
+    });
+    let created = client.create_session(data.clone()).await?;
+
+    let resp = client
+        .send(client.request(Method::GET, &format!("/sessions/{}", created.id)))
+        .await;
+    let _ = client.delete_session(&created.id).await;
+    let resp = resp?;
+
+    let content_type = resp
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let fetched: Session = resp
+        .json()
+        .await
+        .map_err(|e| format!("GET returned an undecodable session: {e}"))?;
+
+    util::assert_json_eq(&data, &fetched.data)
+        .map_err(|diff| format!("data changed on the way back: {diff}"))?;
+    let charset = content_type
+        .split(';')
+        .skip(1)
+        .filter_map(|param| param.trim().split_once('='))
+        .find(|(name, _)| name.eq_ignore_ascii_case("charset"))
+        .map(|(_, value)| value.trim_matches('"'));
+    match charset {
+        None => Ok(()),
+        Some(charset) if charset.eq_ignore_ascii_case("utf-8") => Ok(()),
+        Some(_) => Err(format!(
+            "response declares a non-UTF-8 charset: {content_type}"
+        )),
+    }
+}
+
+/// Numbers that commonly lose precision or sign in a store that parses JSON
+/// numbers as f64.
+fn numeric_fields() -> Vec<(&'static str, serde_json::Value)> {
+    vec![
+        ("i64_max", i64::MAX.into()),
+        ("i64_min", i64::MIN.into()),
+        ("u64_max", u64::MAX.into()),
+        ("point_one_plus_point_two", (0.1_f64 + 0.2).into()),
+        ("pi", std::f64::consts::PI.into()),
+        ("negative_zero", (-0.0_f64).into()),
+        ("min_subnormal", 5e-324_f64.into()),
+        ("tiny_exponent", 1e-300_f64.into()),
+        ("f64_max", f64::MAX.into()),
+    ]
+}
+
+/// Integers at the edges of i64/u64, floats that need all 17 significant
+/// digits, negative zero, and extreme exponents must come back unchanged.
+/// Every changed field is reported; with `allow_lossy`, those that merely lost
+/// precision or the sign of zero are warnings.
+async fn test_numeric_round_trip(
+    client: &OrchestratorClient,
+    allow_lossy: bool,
+) -> Result<(), String> {
+    let fields = numeric_fields();
+    let mut data = serde_json::Map::new();
+    data.insert("user".into(), util::unique("test_numeric").into());
+    for (name, value) in &fields {
+        data.insert(name.to_string(), value.clone());
+    }
+    let created = client.create_session(data.into()).await?;
+    let fetched = client.get_session(&created.id).await;
+    let _ = client.delete_session(&created.id).await;
+    let fetched = fetched?;
+
+    let mut changed = Vec::new();
+    for (name, sent) in &fields {
+        let Some(got) = fetched.data.get(name) else {
+            changed.push(format!("{name}: sent {sent}, missing on the way back"));
+            continue;
+        };
+        if same_number(sent, got) {
+            continue;
+        }
+        let mismatch = format!("{name}: sent {sent}, got {got}");
+        if allow_lossy && is_lossy(sent, got) {
+            context::warn(format!("{mismatch} (allowed by --allow-numeric-lossy)"));
+        } else {
+            changed.push(mismatch);
+        }
+    }
+    if changed.is_empty() {
+        Ok(())
+    } else {
+        Err(format!(
+            "numbers changed on the way back: {}",
+            changed.join("; ")
+        ))
+    }
+}
+
+/// Equal as JSON values, including the sign of zero, which `Value` equality
+/// ignores.
+fn same_number(sent: &serde_json::Value, got: &serde_json::Value) -> bool {
+    let negative = |v: &serde_json::Value| v.as_f64().map(f64::is_sign_negative);
+    sent == got && negative(sent) == negative(got)
+}
+
+/// Both are numbers with the same f64 value, so only integer precision or the
+/// sign of zero was lost, e.g. u64::MAX coming back as 1.8446744073709552e19.
+fn is_lossy(sent: &serde_json::Value, got: &serde_json::Value) -> bool {
+    matches!((sent.as_f64(), got.as_f64()), (Some(a), Some(b)) if a == b)
+}
+
+/// PUT /sessions/:id should replace the data, keep created_at, and be visible to GET.
+async fn test_update_session(client: &OrchestratorClient) -> Result<(), String> {
+    let user = util::unique("test_update");
+    let created = client
+        .create_session(serde_json::json!({"user": user, "step": 1}))
+        .await?;
+
+    let result = check_update(client, &created, &user).await;
+
+    // Cleanup
+    let _ = client.delete_session(&created.id).await;
+    result
+}
+
+async fn check_update(
+    client: &OrchestratorClient,
+    created: &Session,
+    user: &str,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": user, "step": 2});
+    let updated = client.update_session(&created.id, data.clone()).await?;
+    if updated.id != created.id {
+        return Err(format!(
+            "id changed on update: {} != {}",
+            updated.id, created.id
+        ));
+    }
+    util::assert_json_eq(&data, &updated.data)
+        .map_err(|diff| format!("update returned stale data: {diff}"))?;
+
+    let fetched = client.get_session(&created.id).await?;
+    util::assert_json_eq(&data, &fetched.data)
+        .map_err(|diff| format!("GET after update returned stale data: {diff}"))?;
+    if fetched.created_at != created.created_at {
+        return Err(format!(
+            "created_at changed on update: {} != {}",
+            fetched.created_at, created.created_at
+        ));
+    }
+    Ok(())
+}
+
+/// PUT /sessions/<invalid-id> should return 404.
+async fn test_update_missing_session(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": "nobody"});
+    match client
+        .update_session("nonexistent-session-id-12345", data)
+        .await
+    {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(_) => Err("expected 404 but the update succeeded".into()),
+        Err(e) => Err(format!("unexpected error: {e}")),
+    }
+}
+
+/// PUT on a deleted session should return 404 rather than resurrecting it.
+async fn test_update_deleted_session(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("test_update_deleted")});
+    let session = client.create_session(data.clone()).await?;
+
+    let status = client.delete_session(&session.id).await?;
+    if status.as_u16() != 204 {
+        return Err(format!("expected 204, got {status}"));
+    }
+
+    match client.update_session(&session.id, data).await {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(_) => Err("update of a deleted session succeeded".into()),
+        Err(e) => Err(format!("unexpected error: {e}")),
+    }
+}
+
+/// GET /sessions should include every live session and drop deleted ones.
+async fn test_list_sessions(client: &OrchestratorClient) -> Result<(), String> {
+    let mut ids = Vec::new();
+    for i in 0..3 {
+        let data = serde_json::json!({"user": util::unique(&format!("test_list_{i}"))});
+        ids.push(client.create_session(data).await?.id);
+    }
+
+    let result = check_listing(client, &ids).await;
+
+    // Cleanup
+    for id in &ids {
+        let _ = client.delete_session(id).await;
+    }
+    result
+}
+
+async fn check_listing(client: &OrchestratorClient, ids: &[String]) -> Result<(), String> {
+    let listed = client.list_sessions(None, None).await?;
+    for id in ids {
+        if !listed.iter().any(|s| &s.id == id) {
+            return Err(format!("session {id} missing from listing"));
+        }
+    }
+
+    let status = client.delete_session(&ids[0]).await?;
+    if status.as_u16() != 204 {
+        return Err(format!("expected 204, got {status}"));
+    }
+
+    let listed = client.list_sessions(None, None).await?;
+    if listed.iter().any(|s| s.id == ids[0]) {
+        return Err(format!("deleted session {} still listed", ids[0]));
+    }
+    for id in &ids[1..] {
+        if !listed.iter().any(|s| &s.id == id) {
+            return Err(format!("session {id} missing from listing after delete"));
+        }
+    }
+    Ok(())
+}
+
+/// GET /sessions should grow by exactly the sessions created and shrink by
+/// exactly those deleted. Only sessions carrying this test's `batch` marker
+/// are counted, so other clients of a shared orchestrator don't matter.
+async fn test_listing_consistency(client: &OrchestratorClient) -> Result<(), String> {
+    let batch = util::unique("test_list_batch");
+    let before = marked(client, &batch).await?;
+    if !before.is_empty() {
+        return Err(format!(
+            "{} sessions listed with batch {batch} before any were created",
+            before.len()
+        ));
+    }
+
+    let mut created = Vec::new();
+    let mut result = Ok(());
+    for i in 0..LISTED_SESSIONS {
+        let data =
+            serde_json::json!({"user": util::unique("test_list_count"), "batch": batch, "n": i});
+        match client.create_session(data).await {
+            Ok(session) => created.push(session),
+            Err(e) => {
+                result = Err(format!("create {}: {e}", i + 1));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        result = check_listing_counts(client, &batch, &created).await;
+    }
+
+    // Cleanup
+    for session in &created {
+        let _ = client.delete_session(&session.id).await;
+    }
+    result?;
+    expect_listed(client, &batch, &[], "after cleanup").await
+}
+
+async fn check_listing_counts(
+    client: &OrchestratorClient,
+    batch: &str,
+    created: &[Session],
+) -> Result<(), String> {
+    expect_listed(client, batch, created, "after creating them").await?;
+
+    for session in &created[..LISTED_DELETES] {
+        let status = client.delete_session(&session.id).await?;
+        if status.as_u16() != 204 {
+            return Err(format!("DELETE {}: expected 204, got {status}", session.id));
+        }
+    }
+    expect_listed(
+        client,
+        batch,
+        &created[LISTED_DELETES..],
+        &format!("after deleting {LISTED_DELETES}"),
+    )
+    .await
+}
+
+/// The listed sessions carrying `batch`.
+async fn marked(client: &OrchestratorClient, batch: &str) -> Result<Vec<Session>, ClientError> {
+    let listed = client.list_sessions(None, None).await?;
+    Ok(listed.into_iter().filter(|s| in_batch(s, batch)).collect())
+}
+
+fn in_batch(session: &Session, batch: &str) -> bool {
+    session.data.get("batch").and_then(|b| b.as_str()) == Some(batch)
+}
+
+/// Check that exactly `expected` are listed with `batch`, with their data.
+/// A mismatch gets one re-list after `RELIST_DELAY` before it counts.
+async fn expect_listed(
+    client: &OrchestratorClient,
+    batch: &str,
+    expected: &[Session],
+    when: &str,
+) -> Result<(), String> {
+    let mut relisted = false;
+    loop {
+        let listed = marked(client, batch).await?;
+        let problem = listing_mismatch(&listed, expected);
+        match problem {
+            None => return Ok(()),
+            Some(problem) if relisted => {
+                return Err(format!("listing {when}: {problem} (also after a re-list)"))
+            }
+            Some(_) => {
+                relisted = true;
+                tokio::time::sleep(RELIST_DELAY).await;
+            }
+        }
+    }
+}
+
+fn listing_mismatch(listed: &[Session], expected: &[Session]) -> Option<String> {
+    if listed.len() != expected.len() {
+        return Some(format!(
+            "expected {} marked sessions, got {}",
+            expected.len(),
+            listed.len()
+        ));
+    }
+    for session in expected {
+        match listed.iter().find(|s| s.id == session.id) {
+            None => return Some(format!("session {} missing", session.id)),
+            Some(found) => {
+                if let Some(diff) = util::json_diff(&session.data, &found.data) {
+                    return Some(format!(
+                        "session {} listed with other data: {diff}",
+                        session.id
+                    ));
+                }
+            }
+        }
+    }
+    None
+}
+
+/// Paging through GET /sessions with `limit` and `offset` should visit each
+/// session once, in the same order every time, and degrade sanely at the
+/// edges. Only this test's sessions are checked, since others may come and
+/// go on a shared orchestrator.
+async fn test_pagination(client: &OrchestratorClient) -> Result<(), String> {
+    let batch = util::unique("test_page_batch");
+    let mut ids = Vec::new();
+    let mut result = Ok(());
+    for i in 0..PAGED_SESSIONS {
+        let data = serde_json::json!({"user": util::unique("test_page"), "batch": batch, "n": i});
+        match client.create_session(data).await {
+            Ok(session) => ids.push(session.id),
+            Err(e) => {
+                result = Err(format!("create {}: {e}", i + 1));
+                break;
+            }
+        }
+    }
+    if result.is_ok() {
+        result = check_pagination(client, &batch, &ids).await;
+    }
+
+    // Cleanup, all at once
+    futures::future::join_all(ids.iter().map(|id| client.delete_session(id))).await;
+    result
+}
+
+async fn check_pagination(
+    client: &OrchestratorClient,
+    batch: &str,
+    ids: &[String],
+) -> Result<(), String> {
+    let (first, listed) = paginate(client, batch).await?;
+    for id in ids {
+        match first.iter().filter(|seen| *seen == id).count() {
+            1 => {}
+            0 => return Err(format!("session {id} missing from every page")),
+            n => return Err(format!("session {id} listed on {n} pages")),
+        }
+    }
+    let (second, _) = paginate(client, batch).await?;
+    if first != second {
+        return Err(format!(
+            "two paginations listed the sessions in different orders: {first:?} then {second:?}"
+        ));
+    }
+
+    let offset = listed + PAGE_LIMIT;
+    match client.list_sessions(Some(PAGE_LIMIT), Some(offset)).await {
+        Ok(page) if page.is_empty() => {}
+        Ok(page) => {
+            return Err(format!(
+                "offset {offset} past the {listed} listed sessions returned {} sessions",
+                page.len()
+            ))
+        }
+        Err(e) => return Err(format!("offset {offset} past the end: {e}")),
+    }
+
+    // limit=0 may mean "none" or "no limit", or be rejected, but must not
+    // fail or truncate arbitrarily.
+    match client.list_sessions(Some(0), None).await {
+        Ok(page) => {
+            let marked = page.iter().filter(|s| in_batch(s, batch)).count();
+            if marked != 0 && marked != ids.len() {
+                return Err(format!(
+                    "limit=0 returned {marked} of the {} sessions",
+                    ids.len()
+                ));
+            }
+        }
+        Err(e) => client_error_only(&e, "limit=0")?,
+    }
+
+    // An absurd limit may be capped, but not crash the handler.
+    match client.list_sessions(Some(ABSURD_LIMIT), None).await {
+        Ok(page) => {
+            let marked = page.iter().filter(|s| in_batch(s, batch)).count();
+            if page.len() > ABSURD_LIMIT {
+                return Err(format!(
+                    "limit={ABSURD_LIMIT} returned {} sessions",
+                    page.len()
+                ));
+            }
+            if marked < ids.len() {
+                context::warn(format!(
+                    "limit={ABSURD_LIMIT} returned {} sessions, {marked} of this test's {}: capped",
+                    page.len(),
+                    ids.len()
+                ));
+            }
+        }
+        Err(e) => client_error_only(&e, &format!("limit={ABSURD_LIMIT}"))?,
+    }
+    Ok(())
+}
+
+/// Page through the whole listing `PAGE_LIMIT` at a time. Returns the IDs
+/// of `batch`'s sessions in the order listed, and how many sessions were
+/// listed in all.
+async fn paginate(
+    client: &OrchestratorClient,
+    batch: &str,
+) -> Result<(Vec<String>, usize), String> {
+    let mut marked = Vec::new();
+    let mut offset = 0;
+    let mut previous: Vec<String> = Vec::new();
+    for _ in 0..MAX_PAGES {
+        let page = client
+            .list_sessions(Some(PAGE_LIMIT), Some(offset))
+            .await
+            .map_err(|e| format!("page at offset {offset}: {e}"))?;
+        if page.len() > PAGE_LIMIT {
+            return Err(format!(
+                "limit={PAGE_LIMIT} returned {} sessions at offset {offset}",
+                page.len()
+            ));
+        }
+        if page.is_empty() {
+            return Ok((marked, offset));
+        }
+        let ids: Vec<String> = page.iter().map(|s| s.id.clone()).collect();
+        if ids == previous {
+            return Err(format!(
+                "offset {offset} returned the same page as the one before; is offset ignored?"
+            ));
+        }
+        offset += page.len();
+        marked.extend(
+            page.into_iter()
+                .filter(|s| in_batch(s, batch))
+                .map(|s| s.id),
+        );
+        previous = ids;
+    }
+    Err(format!(
+        "still listing after {MAX_PAGES} pages of {PAGE_LIMIT}; is offset ignored?"
+    ))
+}
+
+/// Accept a 4xx rejection of an edge-case query; anything else fails.
+fn client_error_only(e: &ClientError, query: &str) -> Result<(), String> {
+    match e {
+        ClientError::Http { status, .. } if status.is_client_error() => Ok(()),
+        _ => Err(format!("{query}: {e}")),
+    }
+}
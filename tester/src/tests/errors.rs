@@ -0,0 +1,99 @@
+use crate::client::{OrchestratorClient, Session};
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::{capacity, util};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+/// The orchestrator reports its own errors with `http.Error`, which writes
+/// the message as plain text.
+const PLAIN_TEXT_ERRORS: &str = "orchestrator reports errors as plain text";
+
+/// How long a create beyond capacity is given to be rejected before the pool
+/// counts as queueing.
+const REJECTION_PROBE: Duration = Duration::from_secs(2);
+
+/// Register error response test cases, each checking a different error body
+/// against `util::check_error_schema`. `pool_size` is as for the Capacity
+/// group; the pool-full test fills the whole pool.
+pub fn tests(pool_size: Option<usize>) -> Vec<TestCase> {
+    vec![
+        TestCase::new("404 on GET has a JSON error body", |client| {
+            Box::pin(test_missing(client, Method::GET))
+        })
+        .expected_failure(PLAIN_TEXT_ERRORS),
+        TestCase::new("404 on DELETE has a JSON error body", |client| {
+            Box::pin(test_missing(client, Method::DELETE))
+        })
+        .expected_failure(PLAIN_TEXT_ERRORS),
+        TestCase::new("Malformed create has a JSON error body", |client| {
+            Box::pin(test_malformed_create(client))
+        }),
+        TestCase::new("Pool-full rejection has a JSON error body", move |client| {
+            Box::pin(test_pool_full(client, pool_size))
+        }),
+    ]
+}
+
+/// `method` on a session that does not exist must get a 404 with a
+/// schema-conforming body.
+async fn test_missing(client: &OrchestratorClient, method: Method) -> Result<(), String> {
+    let path = "/sessions/nonexistent-session-id-12345";
+    let resp = client.request_raw(method.clone(), path, None).await?;
+    if resp.status != StatusCode::NOT_FOUND {
+        return Err(format!("{method} {path}: expected 404, got {resp}"));
+    }
+    util::check_error_schema(&resp).map_err(|e| format!("{method} {path}: {e}"))
+}
+
+/// A truncated JSON body must get a 4xx with a schema-conforming body.
+async fn test_malformed_create(client: &OrchestratorClient) -> Result<(), String> {
+    let resp = client
+        .create_session_raw(b"{\"user\":", "application/json")
+        .await?;
+    if !resp.status.is_client_error() {
+        return Err(format!("POST /sessions: expected 4xx, got {resp}"));
+    }
+    util::check_error_schema(&resp).map_err(|e| format!("POST /sessions: {e}"))
+}
+
+/// With the pool full, a rejected create must carry a schema-conforming body.
+/// A pool that queues the create instead has no error to check, which is
+/// reported as a warning.
+async fn test_pool_full(
+    client: &OrchestratorClient,
+    pool_size: Option<usize>,
+) -> Result<(), String> {
+    let held = capacity::fill_pool(client, pool_size).await?;
+    let body = serde_json::json!({"user": util::unique("errors_pool_full")}).to_string();
+    let result = tokio::time::timeout(
+        REJECTION_PROBE,
+        client.create_session_raw(body.as_bytes(), "application/json"),
+    )
+    .await;
+    capacity::release(client, &held).await;
+
+    let resp = match result {
+        // Dropping the request cancels it, so it does not take a worker later.
+        Err(_) => {
+            context::warn(format!(
+                "create beyond {} sessions queued for {}s instead of being rejected; \
+                 no error body to check",
+                held.len(),
+                REJECTION_PROBE.as_secs()
+            ));
+            return Ok(());
+        }
+        Ok(resp) => resp?,
+    };
+    if resp.status.is_success() {
+        if let Ok(session) = serde_json::from_str::<Session>(&resp.body) {
+            let _ = client.delete_session(&session.id).await;
+        }
+        return Err(format!(
+            "create beyond {} sessions was served; is --pool-size too small?",
+            held.len()
+        ));
+    }
+    util::check_error_schema(&resp).map_err(|e| format!("POST /sessions: {e}"))
+}
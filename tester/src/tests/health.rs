@@ -0,0 +1,105 @@
+use crate::client::{HealthStatus, OrchestratorClient};
+use crate::runner::{GroupHooks, TestCase};
+use crate::tests::{capacity, util};
+use std::time::Duration;
+
+/// How long workers get to become available again after their sessions are
+/// deleted.
+const RECOVERY_TIMEOUT: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Statuses that contradict a 2xx health response.
+const UNHEALTHY: &[&str] = &["unhealthy", "down", "error", "fail"];
+
+/// Register test cases checking the worker counts in GET /health. The
+/// occupancy test fills every worker, so the group assumes nothing else holds
+/// sessions while it runs.
+pub fn tests() -> Vec<TestCase> {
+    vec![
+        TestCase::new("Health worker counts are consistent", |client| {
+            Box::pin(test_consistent(client))
+        }),
+        TestCase::new("Health tracks worker occupancy", |client| {
+            Box::pin(test_occupancy(client))
+        }),
+    ]
+}
+
+/// Setup skipping the group unless /health reports worker counts.
+pub fn hooks() -> GroupHooks {
+    GroupHooks {
+        setup: Some(Box::new(|client| {
+            Box::pin(async { counts(client).await.map(|_| ()) })
+        })),
+        teardown: None,
+    }
+}
+
+/// Available workers can never outnumber the pool, and a status, if given,
+/// must not contradict the 2xx it came with.
+async fn test_consistent(client: &OrchestratorClient) -> Result<(), String> {
+    let health = client
+        .health_detailed()
+        .await
+        .map_err(|e| format!("GET /health failed: {e}"))?;
+    if let Some(status) = health.and_then(|h| h.status) {
+        if UNHEALTHY.iter().any(|s| status.eq_ignore_ascii_case(s)) {
+            return Err(format!("GET /health answered 2xx with status {status:?}"));
+        }
+    }
+    let (total, available) = counts(client).await?;
+    if available > total {
+        return Err(format!(
+            "workers_available ({available}) exceeds workers_total ({total})"
+        ));
+    }
+    Ok(())
+}
+
+/// With every worker holding a session none may be available, and all must
+/// be available again once the sessions are deleted.
+async fn test_occupancy(client: &OrchestratorClient) -> Result<(), String> {
+    let (total, available) = counts(client).await?;
+    if available != total {
+        return Err(format!(
+            "pool is not idle ({available}/{total} workers available); \
+             the test needs every worker free"
+        ));
+    }
+
+    let held = capacity::fill_pool(client, Some(total as usize)).await?;
+    let during = counts(client).await;
+    capacity::release(client, &held).await;
+    let (_, during) = during?;
+    if during != 0 {
+        return Err(format!(
+            "workers_available is {during} with all {total} workers holding a session, expected 0"
+        ));
+    }
+
+    util::poll_until(RECOVERY_TIMEOUT, POLL_INTERVAL, || async {
+        match counts(client).await? {
+            (_, available) if available == total => Ok(()),
+            (_, available) => Err(format!("{available}/{total} workers available")),
+        }
+    })
+    .await
+    .map_err(|e| format!("workers did not become available after cleanup: {e}"))
+}
+
+/// (workers_total, workers_available) from GET /health.
+async fn counts(client: &OrchestratorClient) -> Result<(u64, u64), String> {
+    let health = client
+        .health_detailed()
+        .await
+        .map_err(|e| format!("GET /health failed: {e}"))?;
+    match health {
+        Some(HealthStatus {
+            workers_total: Some(total),
+            workers_available: Some(available),
+            ..
+        }) => Ok((total, available)),
+        Some(_) => Err("GET /health does not report workers_total and workers_available".into()),
+        None => Err("GET /health is not JSON, so it reports no worker counts".into()),
+    }
+}
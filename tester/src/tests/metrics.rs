@@ -0,0 +1,125 @@
+use crate::capabilities::Capability;
+use crate::client::{OrchestratorClient, Session};
+use crate::runner::TestCase;
+use crate::tests::{capacity, util};
+
+/// Sessions each test creates and deletes.
+const SESSIONS: usize = 5;
+
+const CREATED_TOTAL: &str = "sessions_created_total";
+const DELETED_TOTAL: &str = "sessions_deleted_total";
+const ACTIVE_SESSIONS: &str = "active_sessions";
+
+/// Register test cases checking GET /metrics against what the tests did. Each
+/// compares a counter or gauge before and after, so the group assumes nothing
+/// else creates or deletes sessions while it runs.
+pub fn tests() -> Vec<TestCase> {
+    vec![
+        TestCase::new(format!("{CREATED_TOTAL} counts creates"), |client| {
+            Box::pin(test_created_total(client))
+        })
+        .requires(Capability::Metrics),
+        TestCase::new(format!("{DELETED_TOTAL} counts deletes"), |client| {
+            Box::pin(test_deleted_total(client))
+        })
+        .requires(Capability::Metrics),
+        TestCase::new(
+            format!("{ACTIVE_SESSIONS} tracks live sessions"),
+            |client| Box::pin(test_active_sessions(client)),
+        )
+        .requires(Capability::Metrics),
+    ]
+}
+
+async fn test_created_total(client: &OrchestratorClient) -> Result<(), String> {
+    let before = read(client, CREATED_TOTAL).await?;
+    let sessions = create_sessions(client).await?;
+    let after = read(client, CREATED_TOTAL).await;
+    capacity::release(client, &sessions).await;
+    check_increase(CREATED_TOTAL, before, after?)
+}
+
+async fn test_deleted_total(client: &OrchestratorClient) -> Result<(), String> {
+    let sessions = create_sessions(client).await?;
+    let before = match read(client, DELETED_TOTAL).await {
+        Ok(before) => before,
+        Err(e) => {
+            capacity::release(client, &sessions).await;
+            return Err(e);
+        }
+    };
+    delete_sessions(client, &sessions).await?;
+    let after = read(client, DELETED_TOTAL).await?;
+    check_increase(DELETED_TOTAL, before, after)
+}
+
+/// The gauge must rise by exactly the sessions created and return to its
+/// baseline once they are deleted.
+async fn test_active_sessions(client: &OrchestratorClient) -> Result<(), String> {
+    let baseline = read(client, ACTIVE_SESSIONS).await?;
+    let sessions = create_sessions(client).await?;
+    let during = read(client, ACTIVE_SESSIONS).await;
+    delete_sessions(client, &sessions).await?;
+    let during = during?;
+    let after = read(client, ACTIVE_SESSIONS).await?;
+
+    if during != baseline + SESSIONS as f64 {
+        return Err(format!(
+            "{ACTIVE_SESSIONS} was {during} with {SESSIONS} new sessions, expected {}",
+            baseline + SESSIONS as f64
+        ));
+    }
+    if after != baseline {
+        return Err(format!(
+            "{ACTIVE_SESSIONS} was {after} after cleanup, expected its baseline {baseline}"
+        ));
+    }
+    Ok(())
+}
+
+/// Current value of `name`, summed over its label sets.
+async fn read(client: &OrchestratorClient, name: &str) -> Result<f64, String> {
+    client
+        .metrics()
+        .await
+        .map_err(|e| format!("GET /metrics failed: {e}"))?
+        .total(name)
+        .ok_or_else(|| format!("{name} is not exposed by /metrics"))
+}
+
+fn check_increase(name: &str, before: f64, after: f64) -> Result<(), String> {
+    if after - before != SESSIONS as f64 {
+        return Err(format!(
+            "{name} went from {before} to {after}, expected an increase of exactly {SESSIONS}"
+        ));
+    }
+    Ok(())
+}
+
+async fn create_sessions(client: &OrchestratorClient) -> Result<Vec<Session>, String> {
+    let mut sessions = Vec::with_capacity(SESSIONS);
+    for _ in 0..SESSIONS {
+        let data = serde_json::json!({"user": util::unique("metrics")});
+        match client.create_session(data).await {
+            Ok(session) => sessions.push(session),
+            Err(e) => {
+                capacity::release(client, &sessions).await;
+                return Err(format!("create failed: {e}"));
+            }
+        }
+    }
+    Ok(sessions)
+}
+
+async fn delete_sessions(client: &OrchestratorClient, sessions: &[Session]) -> Result<(), String> {
+    for session in sessions {
+        let status = client
+            .delete_session(&session.id)
+            .await
+            .map_err(|e| format!("failed to delete session {}: {e}", session.id))?;
+        if !status.is_success() {
+            return Err(format!("DELETE {} returned {status}", session.id));
+        }
+    }
+    Ok(())
+}
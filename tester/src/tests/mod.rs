@@ -2,3 +2,18 @@ pub mod crud;
 pub mod concurrent;
 pub mod ttl;
 pub mod recovery;
+pub mod util;
+pub mod timeouts;
+pub mod auth;
+pub mod capacity;
+pub mod soak;
+pub mod validation;
+pub mod protocol;
+pub mod errors;
+pub mod metrics;
+pub mod health;
+pub mod restart;
+pub mod shutdown;
+pub mod chaos;
+pub mod rate_limit;
+pub mod cors;
@@ -0,0 +1,601 @@
+use crate::client::{ClientError, OrchestratorClient, RawResponse, Session, Stalled};
+use crate::context;
+use crate::runner::TestCase;
+use crate::stats::millis;
+use crate::tests::util;
+use reqwest::header::{HeaderName, ALLOW, CONNECTION, CONTENT_TYPE, LOCATION};
+use reqwest::{Method, Response, StatusCode, Url, Version};
+use std::time::{Duration, Instant};
+
+/// The orchestrator answers unsupported methods with `http.Error(..., 405)`,
+/// which sets no Allow header.
+const NO_ALLOW: &str = "orchestrator's 405 responses carry no Allow header";
+
+/// The /sessions/:id handler switches on the method and has no HEAD case.
+const NO_HEAD: &str = "orchestrator does not route HEAD /sessions/:id";
+
+/// Create responses carry the worker's body but no Location header.
+const NO_LOCATION: &str = "orchestrator sets no Location header on create";
+
+/// Reported when a repeated create with the same Idempotency-Key makes a
+/// second session.
+const NO_IDEMPOTENCY: &str = "orchestrator ignores the Idempotency-Key header";
+
+/// Reported when responses don't carry the request ID header back.
+const NO_REQUEST_ID_ECHO: &str = "orchestrator does not echo the request ID header";
+
+/// Sequential GETs of one session in the keep-alive test.
+const KEEP_ALIVE_REQUESTS: usize = 20;
+
+/// Cache-busting and unknown parameters a frontend might append.
+const EXTRA_QUERY: &str = "foo=bar&_=123";
+
+/// Times GET /sessions is repeated to check its status is stable.
+const GET_REPEATS: usize = 3;
+
+/// The orchestrator serves with `http.ListenAndServe`, which sets no read
+/// timeout, so a body that stops arriving is waited for forever.
+const NO_READ_TIMEOUT: &str = "orchestrator sets no server read timeout";
+
+/// Slack past `--stall-timeout` before a stalled create counts as held.
+const STALL_SLACK: Duration = Duration::from_secs(5);
+
+/// How long into the stall a normal create is tried alongside it.
+const STALL_HEAD_START: Duration = Duration::from_secs(1);
+
+/// Register protocol conformance test cases: status codes and headers of
+/// create and delete, idempotent creates, request ID echoes, keep-alive,
+/// unknown query parameters, stalled bodies, then unsupported methods, HEAD,
+/// and OPTIONS on the session endpoints. With `require_idempotency`, an
+/// orchestrator ignoring Idempotency-Key fails instead of being unsupported.
+/// A half-sent create must be given up on within `stall_timeout`.
+pub fn tests(require_idempotency: bool, stall_timeout: Duration) -> Vec<TestCase> {
+    vec![
+        TestCase::new("Create returns 201 Created", |client| {
+            Box::pin(test_create_status(client))
+        }),
+        TestCase::new("Create returns application/json", |client| {
+            Box::pin(test_create_content_type(client))
+        }),
+        TestCase::new("Create sets Location to the new session", |client| {
+            Box::pin(test_create_location(client))
+        })
+        .expected_failure(NO_LOCATION),
+        TestCase::new("Delete returns 204 with no body", |client| {
+            Box::pin(test_delete_response(client))
+        }),
+        TestCase::new(
+            "Create with an Idempotency-Key is not repeated",
+            move |client| Box::pin(test_idempotent_create(client, require_idempotency)),
+        )
+        .not_retryable(),
+        TestCase::new("Responses echo the request ID", |client| {
+            Box::pin(test_request_id_echo(client))
+        }),
+        TestCase::new("Connections are kept alive", |client| {
+            Box::pin(test_keep_alive(client))
+        }),
+        TestCase::new("Unknown query parameters are ignored", |client| {
+            Box::pin(test_unknown_query(client))
+        }),
+        TestCase::new(
+            format!("Stalled create is timed out ({}s)", stall_timeout.as_secs()),
+            move |client| Box::pin(test_stalled_create(client, stall_timeout)),
+        )
+        .with_timeout(stall_timeout + STALL_SLACK * 4)
+        .with_tags(&["slow", "protocol"])
+        .expected_failure(NO_READ_TIMEOUT),
+        TestCase::new("PATCH /sessions is not allowed", |client| {
+            Box::pin(test_not_allowed(client, Method::PATCH))
+        })
+        .expected_failure(NO_ALLOW),
+        TestCase::new("PUT /sessions is not allowed", |client| {
+            Box::pin(test_not_allowed(client, Method::PUT))
+        })
+        .expected_failure(NO_ALLOW),
+        TestCase::new("GET /sessions answers consistently", |client| {
+            Box::pin(test_get_root(client))
+        }),
+        TestCase::new("HEAD /sessions/:id matches GET", |client| {
+            Box::pin(test_head(client))
+        })
+        .expected_failure(NO_HEAD),
+        TestCase::new("OPTIONS /sessions does not fail", |client| {
+            Box::pin(test_options(client))
+        }),
+    ]
+}
+
+/// Create a session and run `check` on the raw response and the decoded
+/// session, deleting the session afterwards.
+async fn create_checked(
+    client: &OrchestratorClient,
+    check: impl FnOnce(&RawResponse, &Session) -> Result<(), String>,
+) -> Result<(), String> {
+    let body = serde_json::json!({"user": util::unique("protocol_create")}).to_string();
+    let resp = client
+        .create_session_raw(body.as_bytes(), "application/json")
+        .await?;
+    if !resp.status.is_success() {
+        return Err(format!("POST /sessions: create failed: {resp}"));
+    }
+    let session: Session = serde_json::from_str(&resp.body)
+        .map_err(|e| format!("POST /sessions: undecodable session ({e}): {resp}"))?;
+    let result = check(&resp, &session);
+    let _ = client.delete_session(&session.id).await;
+    result
+}
+
+/// A successful create must say so with 201, not just any 2xx.
+async fn test_create_status(client: &OrchestratorClient) -> Result<(), String> {
+    create_checked(client, |resp, _| {
+        if resp.status != StatusCode::CREATED {
+            return Err(format!("POST /sessions: expected 201, got {}", resp.status));
+        }
+        Ok(())
+    })
+    .await
+}
+
+async fn test_create_content_type(client: &OrchestratorClient) -> Result<(), String> {
+    create_checked(client, |resp, _| {
+        let content_type = header(resp, CONTENT_TYPE);
+        let essence = content_type.split(';').next().unwrap_or_default().trim();
+        if !essence.eq_ignore_ascii_case("application/json") {
+            return Err(format!(
+                "POST /sessions: expected Content-Type application/json, got {content_type:?}"
+            ));
+        }
+        Ok(())
+    })
+    .await
+}
+
+/// Location, absolute or relative, must resolve to /sessions/<id> of the
+/// session in the body.
+async fn test_create_location(client: &OrchestratorClient) -> Result<(), String> {
+    let base = Url::parse(client.base_url()).map_err(|e| format!("invalid --url: {e}"))?;
+    create_checked(client, |resp, session| {
+        let location = header(resp, LOCATION);
+        if location.is_empty() {
+            return Err("POST /sessions: no Location header".to_string());
+        }
+        let expected = format!("/sessions/{}", session.id);
+        match base.join(location) {
+            Ok(url) if url.path() == expected => Ok(()),
+            _ => Err(format!(
+                "POST /sessions: Location {location:?} does not point at {expected}"
+            )),
+        }
+    })
+    .await
+}
+
+/// DELETE must answer 204 with neither a body nor a Content-Type.
+async fn test_delete_response(client: &OrchestratorClient) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("protocol_delete")}))
+        .await?;
+    let path = format!("/sessions/{}", session.id);
+    let resp = client.delete_session_raw(&session.id).await?;
+    if resp.status != StatusCode::NO_CONTENT {
+        return Err(format!("DELETE {path}: expected 204, got {resp}"));
+    }
+    if !resp.body.is_empty() {
+        return Err(format!("DELETE {path}: 204 with a body: {:?}", resp.body));
+    }
+    if let Some(content_type) = resp.headers.get(CONTENT_TYPE) {
+        return Err(format!(
+            "DELETE {path}: 204 with Content-Type {content_type:?}"
+        ));
+    }
+    Ok(())
+}
+
+/// Value of header `name`, or "" if it is missing or not text.
+fn header(resp: &RawResponse, name: HeaderName) -> &str {
+    resp.headers
+        .get(name)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+}
+
+/// `method` on the collection must get 405 with an Allow header, or 404.
+async fn test_not_allowed(client: &OrchestratorClient, method: Method) -> Result<(), String> {
+    let body = serde_json::json!({"user": util::unique("protocol")});
+    let resp = client
+        .request_raw(method.clone(), "/sessions", Some(&body))
+        .await?;
+    check_not_allowed(&method, "/sessions", &resp)
+}
+
+/// GET on the collection must answer the same way every time. If listing is
+/// not supported, it must be refused like any other unsupported method.
+async fn test_get_root(client: &OrchestratorClient) -> Result<(), String> {
+    let mut responses = Vec::with_capacity(GET_REPEATS);
+    for _ in 0..GET_REPEATS {
+        responses.push(client.request_raw(Method::GET, "/sessions", None).await?);
+    }
+    let first = &responses[0];
+    if let Some(other) = responses.iter().find(|r| r.status != first.status) {
+        return Err(format!(
+            "GET /sessions: answered {} and then {}",
+            first.status, other.status
+        ));
+    }
+    if first.status.is_success() {
+        return Ok(());
+    }
+    if first.status.is_server_error() {
+        return Err(format!("GET /sessions: got {first}"));
+    }
+
+    let patch = client.request_raw(Method::PATCH, "/sessions", None).await?;
+    if patch.status != first.status {
+        return Err(format!(
+            "GET /sessions: refused with {} but PATCH /sessions got {}",
+            first.status, patch.status
+        ));
+    }
+    Ok(())
+}
+
+/// HEAD must get the same status as GET and no body, for both an existing
+/// and a missing session.
+async fn test_head(client: &OrchestratorClient) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("protocol_head")}))
+        .await?;
+    let result = check_head(client, &format!("/sessions/{}", session.id)).await;
+    let _ = client.delete_session(&session.id).await;
+    result?;
+    check_head(client, "/sessions/nonexistent-session-id-12345").await
+}
+
+async fn check_head(client: &OrchestratorClient, path: &str) -> Result<(), String> {
+    let get = client.request_raw(Method::GET, path, None).await?;
+    let head = client.request_raw(Method::HEAD, path, None).await?;
+    if head.status != get.status {
+        return Err(format!(
+            "HEAD {path}: got {}, but GET got {}",
+            head.status, get.status
+        ));
+    }
+    if !head.body.is_empty() {
+        return Err(format!(
+            "HEAD {path}: returned a {}-byte body",
+            head.body.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Two creates with the same Idempotency-Key must return the same session,
+/// and only one may be listed; a different key must make a new one.
+async fn test_idempotent_create(
+    client: &OrchestratorClient,
+    require_idempotency: bool,
+) -> Result<(), String> {
+    let key = util::unique("protocol_idempotency");
+    let data = serde_json::json!({"user": key});
+    let mut created = Vec::new();
+    let result = check_idempotent_create(client, &key, &data, &mut created).await;
+
+    // Cleanup
+    created.dedup();
+    for id in &created {
+        let _ = client.delete_session(id).await;
+    }
+    match result? {
+        true => Ok(()),
+        false if require_idempotency => Err(format!("{NO_IDEMPOTENCY} (--require-idempotency)")),
+        false => {
+            context::unsupported(NO_IDEMPOTENCY);
+            Ok(())
+        }
+    }
+}
+
+/// Whether the orchestrator honoured the key; errors if it did so wrongly.
+/// IDs of the sessions made are pushed onto `created`.
+async fn check_idempotent_create(
+    client: &OrchestratorClient,
+    key: &str,
+    data: &serde_json::Value,
+    created: &mut Vec<String>,
+) -> Result<bool, String> {
+    let first = client.create_session_with(data.clone(), Some(key)).await?;
+    created.push(first.id.clone());
+    let second = client.create_session_with(data.clone(), Some(key)).await?;
+    created.push(second.id.clone());
+    if second.id != first.id {
+        return Ok(false);
+    }
+
+    // Listing is optional; when served, the key must not have made a twin.
+    match client.list_sessions(None, None).await {
+        Ok(listed) => {
+            let twins = listed.iter().filter(|s| s.data == *data).count();
+            if twins != 1 {
+                return Err(format!(
+                    "same Idempotency-Key returned {} both times, but {twins} sessions with its data are listed",
+                    first.id
+                ));
+            }
+        }
+        Err(ClientError::Http { status, .. }) if status.is_client_error() => {}
+        Err(ClientError::NotFound { .. }) => {}
+        Err(e) => return Err(format!("GET /sessions: {e}")),
+    }
+
+    let other_key = format!("{key}_other");
+    let third = client
+        .create_session_with(data.clone(), Some(&other_key))
+        .await?;
+    created.push(third.id.clone());
+    if third.id == first.id {
+        return Err(format!(
+            "a different Idempotency-Key returned the same session {}",
+            first.id
+        ));
+    }
+    Ok(true)
+}
+
+/// A success and an error response must carry back the request ID the
+/// client sent; if neither carries the header at all, the orchestrator is
+/// reported as not supporting it.
+async fn test_request_id_echo(client: &OrchestratorClient) -> Result<(), String> {
+    let name = client.request_id_header();
+    let mut echoed = false;
+    for path in ["/health", "/sessions/nonexistent-session-id-12345"] {
+        let resp = client.request_raw(Method::GET, path, None).await?;
+        let sent = resp.request_id.as_deref().unwrap_or_default();
+        match resp.headers.get(name) {
+            None => {}
+            Some(value) if value.to_str().ok() == Some(sent) => echoed = true,
+            Some(value) => {
+                return Err(format!(
+                    "GET {path}: sent {name} {sent}, got back {value:?}"
+                ))
+            }
+        }
+    }
+    if !echoed {
+        context::unsupported(NO_REQUEST_ID_ECHO);
+    }
+    Ok(())
+}
+
+/// Sequential GETs of one session through the shared client must be able to
+/// reuse its connection. reqwest doesn't say whether it reused one, so two
+/// things are recorded instead: how many responses told the client to close
+/// (`header_close_responses`), and the first request's latency against the
+/// mean of the others (`latency_first_ms`, `latency_later_mean_ms`), which
+/// only includes connection setup if the pool had nothing idle. Only a
+/// server closing after every response fails; closing after some warns.
+async fn test_keep_alive(client: &OrchestratorClient) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("protocol_keep_alive")}))
+        .await?;
+    let result = check_keep_alive(client, &format!("/sessions/{}", session.id)).await;
+    let _ = client.delete_session(&session.id).await;
+    result
+}
+
+async fn check_keep_alive(client: &OrchestratorClient, path: &str) -> Result<(), String> {
+    let mut latencies = Vec::with_capacity(KEEP_ALIVE_REQUESTS);
+    let mut closing = 0;
+    for _ in 0..KEEP_ALIVE_REQUESTS {
+        let start = Instant::now();
+        let resp = client.send(client.request(Method::GET, path)).await?;
+        latencies.push(start.elapsed());
+        if !resp.status().is_success() {
+            return Err(format!("GET {path}: got {}", resp.status()));
+        }
+        closing += usize::from(closes_connection(&resp));
+        // Read the body, or the connection can't go back to the pool.
+        resp.bytes().await.map_err(|e| format!("GET {path}: {e}"))?;
+    }
+
+    let later = latencies[1..].iter().sum::<Duration>() / (KEEP_ALIVE_REQUESTS as u32 - 1);
+    context::record_metric("header_close_responses", closing as f64);
+    context::record_metric("latency_first_ms", millis(latencies[0]));
+    context::record_metric("latency_later_mean_ms", millis(later));
+    match closing {
+        0 => Ok(()),
+        KEEP_ALIVE_REQUESTS => Err(format!(
+            "all {KEEP_ALIVE_REQUESTS} responses to GET {path} closed their connection (Connection: close, or HTTP/1.0 without keep-alive)"
+        )),
+        _ => {
+            context::warn(format!(
+                "{closing} of {KEEP_ALIVE_REQUESTS} responses to GET {path} closed their connection"
+            ));
+            Ok(())
+        }
+    }
+}
+
+/// Whether a response ends its connection: it says `Connection: close`, or
+/// is HTTP/1.0, which closes unless it says `Connection: keep-alive`.
+fn closes_connection(resp: &Response) -> bool {
+    let connection = resp
+        .headers()
+        .get(CONNECTION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_ascii_lowercase();
+    let says = |token: &str| connection.split(',').any(|t| t.trim() == token);
+    says("close") || (resp.version() <= Version::HTTP_10 && !says("keep-alive"))
+}
+
+/// GET and DELETE of a session with `?foo=bar&_=123` appended, and a create
+/// on `/sessions?dry_run=true`, must behave exactly as without the query. A
+/// create that answers without making a session is reported as honouring
+/// `dry_run`, not passed.
+async fn test_unknown_query(client: &OrchestratorClient) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("protocol_query")}))
+        .await?;
+    let mut created = vec![session.id.clone()];
+    let result = check_unknown_query(client, &session.id, &mut created).await;
+
+    // Cleanup
+    for id in &created {
+        let _ = client.delete_session(id).await;
+    }
+    result
+}
+
+/// A create that announces its body and sends only half must be answered
+/// 408 (or 400, for a body read that timed out) or closed within
+/// `stall_timeout`, not held open. Creates made while it stalls and once it
+/// is over must still succeed, so it didn't take a worker with it.
+async fn test_stalled_create(
+    client: &OrchestratorClient,
+    stall_timeout: Duration,
+) -> Result<(), String> {
+    let body = serde_json::json!({
+        "user": util::unique("stalled"),
+        "padding": "x".repeat(1024),
+    })
+    .to_string();
+    let during = async {
+        tokio::time::sleep(STALL_HEAD_START).await;
+        create_and_delete(client, "stalled_neighbour").await
+    };
+    let (stalled, during) = tokio::join!(
+        client.create_session_stalled(body.as_bytes(), stall_timeout + STALL_SLACK),
+        during
+    );
+
+    let after = match stalled? {
+        Stalled::Answered { status, after }
+            if status == StatusCode::REQUEST_TIMEOUT || status == StatusCode::BAD_REQUEST =>
+        {
+            after
+        }
+        Stalled::Answered { status, .. } => {
+            return Err(format!("a half-sent body was answered {status}"))
+        }
+        Stalled::Closed { after } => after,
+        Stalled::Held => {
+            return Err(format!(
+                "a half-sent body was neither answered nor closed within {}s",
+                (stall_timeout + STALL_SLACK).as_secs()
+            ))
+        }
+    };
+    context::record_metric("stall_ended_after_ms", after.as_millis() as f64);
+    if after > stall_timeout {
+        return Err(format!(
+            "a half-sent body was given up on after {}s, over {}s",
+            after.as_secs(),
+            stall_timeout.as_secs()
+        ));
+    }
+    during.map_err(|e| format!("create during the stall failed: {e}"))?;
+    create_and_delete(client, "after_stall")
+        .await
+        .map_err(|e| format!("create after the stall failed: {e}"))
+}
+
+async fn create_and_delete(client: &OrchestratorClient, prefix: &str) -> Result<(), ClientError> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique(prefix)}))
+        .await?;
+    client.delete_session(&session.id).await?;
+    Ok(())
+}
+
+/// IDs of the sessions made are pushed onto `created`.
+async fn check_unknown_query(
+    client: &OrchestratorClient,
+    id: &str,
+    created: &mut Vec<String>,
+) -> Result<(), String> {
+    let path = format!("/sessions/{id}");
+    let plain = client.request_raw(Method::GET, &path, None).await?;
+    let queried = client
+        .request_raw(Method::GET, &format!("{path}?{EXTRA_QUERY}"), None)
+        .await?;
+    let as_json = |resp: &RawResponse| serde_json::from_str::<serde_json::Value>(&resp.body).ok();
+    if queried.status != plain.status || as_json(&queried) != as_json(&plain) {
+        return Err(format!(
+            "GET {path}?{EXTRA_QUERY}: got {queried}, but without the query {plain}"
+        ));
+    }
+
+    let data = serde_json::json!({"user": util::unique("protocol_dry_run")});
+    let resp = client
+        .request_raw(Method::POST, "/sessions?dry_run=true", Some(&data))
+        .await?;
+    if !resp.status.is_success() {
+        return Err(format!("POST /sessions?dry_run=true: got {resp}"));
+    }
+    let made = serde_json::from_str::<Session>(&resp.body).ok();
+    if let Some(session) = &made {
+        created.push(session.id.clone());
+    }
+    let exists = match &made {
+        Some(session) => client.get_session(&session.id).await.is_ok(),
+        None => false,
+    };
+    if !exists {
+        return Err(format!(
+            "POST /sessions?dry_run=true answered {} but no session exists: dry_run is honoured, not ignored",
+            resp.status
+        ));
+    }
+
+    let deleted = client
+        .request_raw(Method::DELETE, &format!("{path}?{EXTRA_QUERY}"), None)
+        .await?;
+    if !deleted.status.is_success() {
+        return Err(format!("DELETE {path}?{EXTRA_QUERY}: got {deleted}"));
+    }
+    match client.get_session(id).await {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(_) => Err(format!(
+            "DELETE {path}?{EXTRA_QUERY} answered {} but the session is still there",
+            deleted.status
+        )),
+        Err(e) => Err(format!("GET {path} after DELETE: {e}")),
+    }
+}
+
+/// OPTIONS on the collection may be supported or refused, but must not fail.
+async fn test_options(client: &OrchestratorClient) -> Result<(), String> {
+    let resp = client
+        .request_raw(Method::OPTIONS, "/sessions", None)
+        .await?;
+    if resp.status.is_success() || resp.status == StatusCode::METHOD_NOT_ALLOWED {
+        return Ok(());
+    }
+    Err(format!(
+        "OPTIONS /sessions: expected 2xx or 405, got {resp}"
+    ))
+}
+
+/// 404, or 405 whose Allow header is present and does not list `method`.
+fn check_not_allowed(method: &Method, path: &str, resp: &RawResponse) -> Result<(), String> {
+    let endpoint = format!("{method} {path}");
+    match resp.status {
+        StatusCode::NOT_FOUND => Ok(()),
+        StatusCode::METHOD_NOT_ALLOWED => {
+            let Some(allow) = resp.headers.get(ALLOW).and_then(|v| v.to_str().ok()) else {
+                return Err(format!("{endpoint}: 405 without an Allow header"));
+            };
+            if allow
+                .split(',')
+                .any(|m| m.trim().eq_ignore_ascii_case(method.as_str()))
+            {
+                return Err(format!("{endpoint}: 405 but Allow lists it: {allow}"));
+            }
+            Ok(())
+        }
+        _ => Err(format!(
+            "{endpoint}: expected 405 with an Allow header or 404, got {resp}"
+        )),
+    }
+}
@@ -0,0 +1,201 @@
+use crate::client::{ClientError, OrchestratorClient};
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::util;
+use reqwest::StatusCode;
+use std::time::{Duration, Instant};
+
+/// How many times the declared limit the burst is sent at.
+const OVERLOAD: u32 = 3;
+
+/// How long the burst lasts: long enough to drain any reasonable bucket.
+const BURST_DURATION: Duration = Duration::from_secs(3);
+
+/// How long traffic at half the limit is sent once the limit has cleared.
+const RECOVERY_DURATION: Duration = Duration::from_secs(2);
+
+/// Longest `Retry-After` the recovery test waits out; anything longer fails.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The request rate the orchestrator is expected to limit clients to.
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimitSettings {
+    /// Requests per second; None leaves the group skipped.
+    pub rps: Option<u32>,
+}
+
+/// Register rate limiting tests. A deployment without a limit would only
+/// fail them, so they are skipped unless a limit was declared.
+pub fn tests(settings: &RateLimitSettings) -> Vec<TestCase> {
+    let Some(rps) = settings.rps else {
+        return [
+            "Requests over the limit get 429 with Retry-After",
+            "Waiting Retry-After clears the limit",
+        ]
+        .into_iter()
+        .map(|name| {
+            TestCase::new(name, |_| Box::pin(async { Ok(()) })).skip("requires --rate-limit-rps")
+        })
+        .collect();
+    };
+    vec![
+        TestCase::new(
+            format!("Requests over the limit get 429 with Retry-After ({rps}/s)"),
+            move |client| Box::pin(test_over_limit(client, rps)),
+        ),
+        TestCase::new(
+            format!("Waiting Retry-After clears the limit ({rps}/s)"),
+            move |client| Box::pin(test_limit_clears(client, rps)),
+        )
+        .with_timeout(BURST_DURATION + MAX_RETRY_AFTER + RECOVERY_DURATION * 4),
+    ]
+}
+
+/// How one GET of the burst was answered.
+enum Answer {
+    Served,
+    Limited(Option<Duration>),
+    /// Neither: a 5xx, another error status, or no response at all.
+    Broken(String),
+}
+
+/// GET `id` at `rate` requests per second for `duration`, each on its own
+/// task so a slow response doesn't hold back the rest.
+async fn burst(
+    client: &OrchestratorClient,
+    id: &str,
+    rate: u32,
+    duration: Duration,
+) -> Result<Vec<Answer>, String> {
+    let period = Duration::from_secs(1) / rate;
+    let start = Instant::now();
+    let mut next = start;
+    let mut handles = Vec::new();
+    while start.elapsed() < duration {
+        tokio::time::sleep_until(next.into()).await;
+        let client = client.clone();
+        let id = id.to_string();
+        handles.push(context::spawn(async move {
+            match client.get_session(&id).await {
+                Ok(_) => Answer::Served,
+                Err(ClientError::Http {
+                    status: StatusCode::TOO_MANY_REQUESTS,
+                    retry_after,
+                    ..
+                }) => Answer::Limited(retry_after),
+                Err(e) => Answer::Broken(e.to_string()),
+            }
+        }));
+        next += period;
+    }
+    let mut answers = Vec::with_capacity(handles.len());
+    for handle in futures::future::join_all(handles).await {
+        answers.push(handle.map_err(|e| format!("task join error: {e}"))?);
+    }
+    Ok(answers)
+}
+
+/// A `OVERLOAD` times over-limit burst must be partly refused with 429,
+/// each carrying a `Retry-After`, and must never get a 5xx or a dropped
+/// connection.
+async fn test_over_limit(client: &OrchestratorClient, rps: u32) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("rate_limit")}))
+        .await?;
+    let answers = burst(client, &session.id, rps * OVERLOAD, BURST_DURATION).await;
+    let _ = client.delete_session(&session.id).await;
+    let answers = answers?;
+
+    let served = answers
+        .iter()
+        .filter(|a| matches!(a, Answer::Served))
+        .count();
+    let limited: Vec<_> = answers
+        .iter()
+        .filter_map(|a| match a {
+            Answer::Limited(retry_after) => Some(*retry_after),
+            _ => None,
+        })
+        .collect();
+    context::record_metric("requests", answers.len() as f64);
+    context::record_metric("served", served as f64);
+    context::record_metric("limited", limited.len() as f64);
+    if let Some(longest) = limited.iter().flatten().max() {
+        context::record_metric("retry_after_max_s", longest.as_secs_f64());
+    }
+
+    if let Some(Answer::Broken(error)) = answers.iter().find(|a| matches!(a, Answer::Broken(_))) {
+        let broken = answers.len() - served - limited.len();
+        return Err(format!(
+            "{broken}/{} requests got neither a session nor 429, e.g. {error}",
+            answers.len()
+        ));
+    }
+    if limited.is_empty() {
+        return Err(format!(
+            "none of {} requests at {}/s was limited",
+            answers.len(),
+            rps * OVERLOAD
+        ));
+    }
+    let bare = limited.iter().filter(|r| r.is_none()).count();
+    if bare > 0 {
+        return Err(format!(
+            "{bare}/{} 429 responses had no valid Retry-After",
+            limited.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Once limited, waiting the advertised `Retry-After` must be enough: traffic
+/// at half the limit straight afterwards is all served.
+async fn test_limit_clears(client: &OrchestratorClient, rps: u32) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("rate_limit")}))
+        .await?;
+    let result = limit_clears(client, &session.id, rps).await;
+    let _ = client.delete_session(&session.id).await;
+    result
+}
+
+async fn limit_clears(client: &OrchestratorClient, id: &str, rps: u32) -> Result<(), String> {
+    let answers = burst(client, id, rps * OVERLOAD, BURST_DURATION).await?;
+    // The last 429 is the one sent closest to the end of the burst.
+    let retry_after = match answers.iter().rev().find_map(|a| match a {
+        Answer::Limited(retry_after) => Some(*retry_after),
+        _ => None,
+    }) {
+        Some(Some(retry_after)) => retry_after,
+        Some(None) => return Err("the 429 had no valid Retry-After to wait for".into()),
+        None => return Err(format!("not limited at {}/s", rps * OVERLOAD)),
+    };
+    if retry_after > MAX_RETRY_AFTER {
+        return Err(format!(
+            "Retry-After asked for {}s, more than the {}s this test waits",
+            retry_after.as_secs(),
+            MAX_RETRY_AFTER.as_secs()
+        ));
+    }
+    context::record_metric("retry_after_s", retry_after.as_secs_f64());
+    tokio::time::sleep(retry_after).await;
+
+    let answers = burst(client, id, (rps / 2).max(1), RECOVERY_DURATION).await?;
+    let refused: Vec<_> = answers
+        .iter()
+        .filter_map(|a| match a {
+            Answer::Served => None,
+            Answer::Limited(_) => Some(StatusCode::TOO_MANY_REQUESTS.to_string()),
+            Answer::Broken(error) => Some(error.clone()),
+        })
+        .collect();
+    if let Some(first) = refused.first() {
+        return Err(format!(
+            "{}/{} requests at half the limit failed after waiting {}s, e.g. {first}",
+            refused.len(),
+            answers.len(),
+            retry_after.as_secs()
+        ));
+    }
+    Ok(())
+}
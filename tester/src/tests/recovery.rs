@@ -1,14 +1,69 @@
-use crate::client::OrchestratorClient;
+use crate::capabilities::Capability;
+use crate::client::{ClientError, OrchestratorClient, Session};
+use crate::context;
 use crate::runner::TestCase;
+use crate::tests::util;
+use std::time::{Duration, Instant};
+
+/// How often recovery tests re-check the orchestrator while it recovers.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How the recovery tests crash workers and how long they wait for the pool.
+#[derive(Debug, Clone, Copy)]
+pub struct RecoverySettings {
+    /// Workers the multi-crash test kills at once.
+    pub crash_workers: usize,
+    /// Limit on each wait for the orchestrator to clean up or recover.
+    pub timeout: Duration,
+    /// Fail the recovery time test above this; None only reports the time.
+    pub slo: Option<Duration>,
+}
 
 /// Register worker failure recovery test cases.
-pub fn tests() -> Vec<TestCase> {
-    vec![TestCase {
-        name: "Worker failure recovery".to_string(),
-        func: Box::new(|client: &OrchestratorClient| {
-            Box::pin(test_worker_recovery(client))
-        }),
-    }]
+pub fn tests(settings: &RecoverySettings) -> Vec<TestCase> {
+    let settings = *settings;
+    let crash_count = settings.crash_workers;
+    vec![
+        TestCase::new("Worker failure recovery", move |client| {
+            Box::pin(test_worker_recovery(client, settings))
+        })
+        // Re-running would crash another worker on top of the one already killed.
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+        TestCase::new("Worker recovery time", move |client| {
+            Box::pin(test_recovery_time(client, settings))
+        })
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+        TestCase::new("Crash during concurrent creates", |client| {
+            Box::pin(test_crash_during_creates(client))
+        })
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+        TestCase::new(
+            format!("Create right after crash ({RACE_ITERATIONS} iterations)"),
+            |client| Box::pin(test_crash_then_create(client)),
+        )
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+        TestCase::new("Crash leaves other sessions intact", move |client| {
+            Box::pin(test_crash_isolation(client, settings))
+        })
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+        TestCase::new(
+            format!("Simultaneous worker failures ({crash_count} workers)"),
+            move |client| Box::pin(test_multiple_crashes(client, settings)),
+        )
+        .not_retryable()
+        .with_tags(&["destructive", "debug-endpoint"])
+        .requires(Capability::CrashWorker),
+    ]
 }
 
 /// Verify the orchestrator recovers when a worker process is killed mid-session.
@@ -16,11 +71,14 @@ pub fn tests() -> Vec<TestCase> {
 /// Strategy:
 ///   1. Create a session to occupy a worker.
 ///   2. Kill that worker via the debug endpoint — exercises the OnCrash path.
-///   3. Verify the crashed session returns 404 (stale mapping cleaned up).
-///   4. Verify the pool recovered and can serve new sessions.
-async fn test_worker_recovery(client: &OrchestratorClient) -> Result<(), String> {
+///   3. Poll until the crashed session returns 404 (stale mapping cleaned up).
+///   4. Poll until the pool recovered and can serve new sessions.
+async fn test_worker_recovery(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+) -> Result<(), String> {
     // Phase 1: Create a session so a worker is busy
-    let data = serde_json::json!({"user": "crash_test"});
+    let data = serde_json::json!({"user": util::unique("crash_test")});
     let session = client
         .create_session(data)
         .await
@@ -32,25 +90,357 @@ async fn test_worker_recovery(client: &OrchestratorClient) -> Result<(), String>
         .await
         .map_err(|e| format!("phase 2: failed to crash worker: {e}"))?;
 
-    // Phase 3: Give the orchestrator time to detect the crash and restart the worker
-    tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
+    // Phase 3: The crashed session should soon return 404
+    util::wait_for_404(client, &session.id, Instant::now() + settings.timeout)
+        .await
+        .map_err(|e| format!("phase 3: crashed session was not cleaned up: {e}"))?;
 
-    // Phase 4: The crashed session should now return 404
-    match client.get_session(&session.id).await {
-        Err(e) if e.contains("404") => {}
-        Ok(_) => return Err("phase 4: expected 404 for crashed session, got 200".to_string()),
-        Err(e) => return Err(format!("phase 4: unexpected error: {e}")),
-    }
+    // Phase 4: Pool should recover — new sessions must become creatable
+    let new_session = util::poll_until(settings.timeout, POLL_INTERVAL, || {
+        let data = serde_json::json!({"user": util::unique("post_crash")});
+        client.create_session(data)
+    })
+    .await
+    .map_err(|e| format!("phase 4: pool did not recover after crash: {e}"))?;
+
+    // Cleanup
+    let _ = client.delete_session(&new_session.id).await;
+
+    Ok(())
+}
 
-    // Phase 5: Pool should have recovered — new sessions must be creatable
-    let data = serde_json::json!({"user": "post_crash"});
-    let new_session = client
+/// Measure the time from crashing a worker until the crashed session is gone
+/// and a new session can be created, recorded as `recovery_secs`. Fails only
+/// when it exceeds `--recovery-slo`.
+async fn test_recovery_time(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("crash_timed")});
+    let session = client
         .create_session(data)
         .await
-        .map_err(|e| format!("phase 5: pool did not recover after crash: {e}"))?;
+        .map_err(|e| format!("failed to create session: {e}"))?;
 
-    // Cleanup
+    let crashed_at = Instant::now();
+    client
+        .crash_worker(&session.id)
+        .await
+        .map_err(|e| format!("failed to crash worker: {e}"))?;
+    util::wait_for_404(client, &session.id, Instant::now() + settings.timeout)
+        .await
+        .map_err(|e| format!("crashed session was not cleaned up: {e}"))?;
+    let new_session = util::poll_until(settings.timeout, POLL_INTERVAL, || {
+        let data = serde_json::json!({"user": util::unique("crash_timed_after")});
+        client.create_session(data)
+    })
+    .await
+    .map_err(|e| format!("pool did not recover after crash: {e}"))?;
+    let recovery = crashed_at.elapsed();
     let _ = client.delete_session(&new_session.id).await;
 
+    // Millisecond precision is plenty and keeps the report readable.
+    context::record_metric("recovery_secs", recovery.as_millis() as f64 / 1000.0);
+    match settings.slo {
+        Some(slo) if recovery > slo => Err(format!(
+            "recovery took {:.2}s, over the {:.2}s SLO",
+            recovery.as_secs_f64(),
+            slo.as_secs_f64()
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Creates issued while the crash is handled in `test_crash_during_creates`.
+const CONCURRENT_CREATES: usize = 10;
+
+/// Crash a worker while the orchestrator is busy assigning new sessions. Every
+/// create must still succeed; one may wait for the crashed worker's slot.
+async fn test_crash_during_creates(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("crash_inflight")});
+    let victim = client
+        .create_session(data)
+        .await
+        .map_err(|e| format!("failed to create session: {e}"))?;
+
+    let handles: Vec<_> = (0..CONCURRENT_CREATES)
+        .map(|i| {
+            let client = client.clone();
+            context::spawn(async move {
+                let data = serde_json::json!({"user": util::unique(&format!("inflight_{i}"))});
+                client.create_session(data).await
+            })
+        })
+        .collect();
+    let (crash, outcomes) = tokio::join!(
+        client.crash_worker(&victim.id),
+        futures::future::join_all(handles)
+    );
+
+    let mut failures = Vec::new();
+    for (i, outcome) in outcomes.into_iter().enumerate() {
+        match outcome {
+            Ok(Ok(session)) => {
+                let _ = client.delete_session(&session.id).await;
+            }
+            Ok(Err(e)) => failures.push(format!("request {i}: {e}")),
+            Err(e) => failures.push(format!("request {i}: task join error: {e}")),
+        }
+    }
+    let _ = client.delete_session(&victim.id).await;
+
+    crash.map_err(|e| format!("failed to crash worker: {e}"))?;
+    if !failures.is_empty() {
+        return Err(format!(
+            "{}/{CONCURRENT_CREATES} creates failed while a worker crashed: {}",
+            failures.len(),
+            failures.join("; ")
+        ));
+    }
+    Ok(())
+}
+
+/// Crash/create cycles run by `test_crash_then_create`.
+const RACE_ITERATIONS: usize = 5;
+
+/// Create a session the moment its worker was crashed, with no pause, to hit
+/// the window where the pool may still count the dead worker as available.
+/// The create may queue but must not fail. Each create's latency is recorded
+/// as `iteration_N_secs`.
+async fn test_crash_then_create(client: &OrchestratorClient) -> Result<(), String> {
+    for iteration in 1..=RACE_ITERATIONS {
+        let data = serde_json::json!({"user": util::unique("race_victim")});
+        let victim = client
+            .create_session(data)
+            .await
+            .map_err(|e| format!("iteration {iteration}: failed to create session: {e}"))?;
+        client
+            .crash_worker(&victim.id)
+            .await
+            .map_err(|e| format!("iteration {iteration}: failed to crash worker: {e}"))?;
+
+        let start = Instant::now();
+        let data = serde_json::json!({"user": util::unique("race_after")});
+        let result = client.create_session(data).await;
+        let latency = start.elapsed();
+        context::record_metric(
+            &format!("iteration_{iteration}_secs"),
+            latency.as_millis() as f64 / 1000.0,
+        );
+        let _ = client.delete_session(&victim.id).await;
+        let session = result.map_err(|e| {
+            format!(
+                "iteration {iteration}: create right after crash failed after {:.2}s: {e}",
+                latency.as_secs_f64()
+            )
+        })?;
+        let _ = client.delete_session(&session.id).await;
+    }
+    Ok(())
+}
+
+/// Sessions kept alive next to the crashed one in `test_crash_isolation`.
+const BYSTANDERS: usize = 3;
+
+/// Crash one worker and verify sessions on other workers keep their data,
+/// both while the orchestrator recovers and afterwards.
+///
+/// Each worker holds one session, so concurrent sessions are on different
+/// workers by construction; when GET /status is available the placement is
+/// checked as well.
+async fn test_crash_isolation(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("isolation_victim")});
+    let victim = client
+        .create_session(data)
+        .await
+        .map_err(|e| format!("failed to create session: {e}"))?;
+    let mut bystanders = Vec::with_capacity(BYSTANDERS);
+    for i in 0..BYSTANDERS {
+        let data = serde_json::json!({"user": util::unique(&format!("isolation_{i}"))});
+        match client.create_session(data).await {
+            Ok(session) => bystanders.push(session),
+            Err(e) => {
+                cleanup_sessions(client, &bystanders).await;
+                let _ = client.delete_session(&victim.id).await;
+                return Err(format!("failed to create bystander session {i}: {e}"));
+            }
+        }
+    }
+
+    let result = crash_beside(client, settings, &victim, &bystanders).await;
+    cleanup_sessions(client, &bystanders).await;
+    let _ = client.delete_session(&victim.id).await;
+    result
+}
+
+/// Crash `victim`'s worker and check on `bystanders` for `test_crash_isolation`.
+async fn crash_beside(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+    victim: &Session,
+    bystanders: &[Session],
+) -> Result<(), String> {
+    match client.status().await {
+        Ok(status) => {
+            let victim_worker = status
+                .worker_for(&victim.id)
+                .ok_or_else(|| format!("/status lists no worker for session {}", victim.id))?;
+            for session in bystanders {
+                if status.worker_for(&session.id) == Some(victim_worker) {
+                    return Err(format!(
+                        "sessions {} and {} share worker {victim_worker}",
+                        victim.id, session.id
+                    ));
+                }
+            }
+        }
+        // No placement info; rely on one session per worker.
+        Err(ClientError::NotFound { .. }) => {}
+        Err(e) => return Err(format!("GET /status failed: {e}")),
+    }
+
+    client
+        .crash_worker(&victim.id)
+        .await
+        .map_err(|e| format!("failed to crash worker: {e}"))?;
+    check_intact(client, bystanders)
+        .await
+        .map_err(|e| format!("during recovery: {e}"))?;
+
+    util::wait_for_404(client, &victim.id, Instant::now() + settings.timeout)
+        .await
+        .map_err(|e| format!("crashed session was not cleaned up: {e}"))?;
+    check_intact(client, bystanders)
+        .await
+        .map_err(|e| format!("after recovery: {e}"))
+}
+
+/// Every session in `sessions` is still retrievable with its original data.
+async fn check_intact(client: &OrchestratorClient, sessions: &[Session]) -> Result<(), String> {
+    for session in sessions {
+        let fetched = client
+            .get_session(&session.id)
+            .await
+            .map_err(|e| format!("session {} on a healthy worker: {e}", session.id))?;
+        if fetched.data != session.data {
+            return Err(format!(
+                "session {} data changed: {:?} -> {:?}",
+                session.id, session.data, fetched.data
+            ));
+        }
+    }
+    Ok(())
+}
+
+async fn cleanup_sessions(client: &OrchestratorClient, sessions: &[Session]) {
+    for session in sessions {
+        let _ = client.delete_session(&session.id).await;
+    }
+}
+
+/// Kill several workers back-to-back so the orchestrator has to restart them
+/// together, not one at a time.
+///
+/// Strategy:
+///   1. Create a bystander session that is never crashed.
+///   2. Create `count` sessions and crash all their workers in a row.
+///   3. Verify every crashed session returns 404.
+///   4. Verify `count` new sessions can be created concurrently.
+///   5. Verify the bystander session survived the crash storm.
+async fn test_multiple_crashes(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("crash_bystander")});
+    let bystander = client
+        .create_session(data)
+        .await
+        .map_err(|e| format!("phase 1: failed to create bystander session: {e}"))?;
+
+    let result = crash_storm(client, settings).await;
+    let bystander_check = client.get_session(&bystander.id).await;
+    let _ = client.delete_session(&bystander.id).await;
+    result?;
+
+    match bystander_check {
+        Ok(_) => Ok(()),
+        Err(ClientError::NotFound { .. }) => {
+            Err("phase 5: unrelated session was lost in the crash storm".to_string())
+        }
+        Err(e) => Err(format!("phase 5: failed to fetch unrelated session: {e}")),
+    }
+}
+
+/// Phases 2-4 of `test_multiple_crashes`.
+async fn crash_storm(
+    client: &OrchestratorClient,
+    settings: RecoverySettings,
+) -> Result<(), String> {
+    let count = settings.crash_workers;
+    let mut victims = Vec::with_capacity(count);
+    for i in 0..count {
+        let data = serde_json::json!({"user": util::unique(&format!("crash_multi_{i}"))});
+        let session = client
+            .create_session(data)
+            .await
+            .map_err(|e| format!("phase 2: failed to create session {i}: {e}"))?;
+        victims.push(session.id);
+    }
+    for id in &victims {
+        client
+            .crash_worker(id)
+            .await
+            .map_err(|e| format!("phase 2: failed to crash worker for {id}: {e}"))?;
+    }
+
+    // One deadline for the batch: sessions still there once it passes get
+    // a single check each.
+    let deadline = Instant::now() + settings.timeout;
+    let mut not_gone = Vec::new();
+    for id in &victims {
+        if let Err(e) = util::wait_for_404(client, id, deadline).await {
+            not_gone.push(format!("{id} ({e})"));
+        }
+    }
+    if !not_gone.is_empty() {
+        return Err(format!(
+            "phase 3: {}/{count} crashed sessions not gone: {}",
+            not_gone.len(),
+            not_gone.join(", ")
+        ));
+    }
+
+    let handles: Vec<_> = (0..count)
+        .map(|i| {
+            let client = client.clone();
+            context::spawn(async move {
+                util::poll_until(settings.timeout, POLL_INTERVAL, || {
+                    let data =
+                        serde_json::json!({"user": util::unique(&format!("post_crash_{i}"))});
+                    client.create_session(data)
+                })
+                .await
+            })
+        })
+        .collect();
+    let mut errors = Vec::new();
+    for handle in handles {
+        match handle.await {
+            Ok(Ok(session)) => {
+                let _ = client.delete_session(&session.id).await;
+            }
+            Ok(Err(e)) => errors.push(e),
+            Err(e) => errors.push(format!("task join error: {e}")),
+        }
+    }
+    if let Some(first) = errors.first() {
+        return Err(format!(
+            "phase 4: {}/{count} creates failed after the crashes: {first}",
+            errors.len()
+        ));
+    }
     Ok(())
 }
@@ -0,0 +1,135 @@
+use crate::client::{ClientError, OrchestratorClient};
+use crate::runner::{GroupHooks, TestCase};
+use crate::tests::util;
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+/// How often the restart test re-checks the orchestrator while it comes back.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// GETs of the old session that must all 404 when sessions are dropped.
+const DROP_CHECKS: usize = 3;
+
+/// What an orchestrator restart does to existing sessions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PersistenceMode {
+    /// Sessions survive the restart with their data.
+    Keep,
+    /// Sessions are lost; the orchestrator starts from a clean slate.
+    Drop,
+}
+
+/// Expected restart behaviour and how long the orchestrator gets to come back.
+#[derive(Debug, Clone, Copy)]
+pub struct RestartSettings {
+    pub mode: PersistenceMode,
+    /// Limit on waiting for /health to answer 2xx after the restart.
+    pub timeout: Duration,
+}
+
+/// Register the orchestrator restart test case.
+pub fn tests(settings: &RestartSettings) -> Vec<TestCase> {
+    let settings = *settings;
+    vec![TestCase::new(
+        format!(
+            "Sessions across a restart ({})",
+            match settings.mode {
+                PersistenceMode::Keep => "kept",
+                PersistenceMode::Drop => "dropped",
+            }
+        ),
+        move |client| Box::pin(test_restart(client, settings)),
+    )
+    // Re-running would restart the orchestrator a second time.
+    .not_retryable()
+    .with_tags(&["destructive", "debug-endpoint"])]
+}
+
+/// Setup skipping the group when the orchestrator has no /debug/restart
+/// endpoint. The probe is a GET, which a restart handler must refuse.
+pub fn hooks() -> GroupHooks {
+    GroupHooks {
+        setup: Some(Box::new(|client| Box::pin(probe(client)))),
+        teardown: None,
+    }
+}
+
+async fn probe(client: &OrchestratorClient) -> Result<(), String> {
+    let resp = client
+        .request_raw(Method::GET, "/debug/restart", None)
+        .await
+        .map_err(|e| format!("GET /debug/restart failed: {e}"))?;
+    if resp.status == StatusCode::NOT_FOUND {
+        return Err("the orchestrator has no /debug/restart endpoint".to_string());
+    }
+    Ok(())
+}
+
+/// Create a session, restart the orchestrator, and wait for /health. Then,
+/// per `settings.mode`, the session must come back with identical data, or
+/// consistently 404 while new sessions can still be created.
+async fn test_restart(
+    client: &OrchestratorClient,
+    settings: RestartSettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({
+        "user": util::unique("restart"),
+        "nested": {"list": [1, 2, 3], "flag": true},
+    });
+    let session = client
+        .create_session(data.clone())
+        .await
+        .map_err(|e| format!("failed to create session: {e}"))?;
+
+    match client.restart().await {
+        // The orchestrator may go down before it finishes answering.
+        Ok(()) | Err(ClientError::Transport { .. }) => {}
+        Err(e) => return Err(format!("POST /debug/restart failed: {e}")),
+    }
+    util::poll_until(settings.timeout, POLL_INTERVAL, || client.health_detailed())
+        .await
+        .map_err(|e| format!("orchestrator did not come back after the restart: {e}"))?;
+
+    match settings.mode {
+        PersistenceMode::Keep => {
+            let fetched = client
+                .get_session(&session.id)
+                .await
+                .map_err(|e| format!("GET {} after the restart: {e}", session.id))?;
+            let result = match util::json_diff(&data, &fetched.data) {
+                Some(diff) => Err(format!("session data changed across the restart: {diff}")),
+                None => Ok(()),
+            };
+            let _ = client.delete_session(&session.id).await;
+            result
+        }
+        PersistenceMode::Drop => {
+            for check in 1..=DROP_CHECKS {
+                match client.get_session(&session.id).await {
+                    Err(ClientError::NotFound { .. }) => {}
+                    Ok(_) => {
+                        let _ = client.delete_session(&session.id).await;
+                        return Err(format!(
+                            "GET {} check {check}/{DROP_CHECKS}: session survived the restart",
+                            session.id
+                        ));
+                    }
+                    Err(e) => {
+                        return Err(format!(
+                            "GET {} check {check}/{DROP_CHECKS}: expected 404, got {e}",
+                            session.id
+                        ))
+                    }
+                }
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            let fresh = client
+                .create_session(serde_json::json!({"user": util::unique("restart_after")}))
+                .await
+                .map_err(|e| format!("create after the restart failed: {e}"))?;
+            let _ = client.delete_session(&fresh.id).await;
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,144 @@
+use crate::client::{ClientError, OrchestratorClient};
+use crate::context;
+use crate::runner::{GroupHooks, TestCase};
+use crate::tests::{capacity, util};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+
+/// How often the shutdown test re-checks the orchestrator while it comes back.
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long the extra create gets to reach the orchestrator's queue before
+/// the shutdown is triggered.
+const QUEUE_SETTLE: Duration = Duration::from_millis(200);
+
+/// How long a graceful shutdown may take to answer the queued create.
+const DRAIN_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Pool size and how long the orchestrator gets to come back.
+#[derive(Debug, Clone, Copy)]
+pub struct ShutdownSettings {
+    /// Overrides the `max_workers` reported by GET /status.
+    pub pool_size: Option<usize>,
+    /// Limit on waiting for /health to answer 2xx after the shutdown.
+    pub timeout: Duration,
+}
+
+/// Register the graceful shutdown test case. It fills the pool, so the group
+/// assumes nothing else holds sessions while it runs, and it needs a
+/// supervisor that brings the orchestrator back after it exits.
+pub fn tests(settings: &ShutdownSettings) -> Vec<TestCase> {
+    let settings = *settings;
+    vec![TestCase::new(
+        "Graceful shutdown answers in-flight requests",
+        move |client| Box::pin(test_graceful_shutdown(client, settings)),
+    )
+    // Re-running would shut the orchestrator down a second time.
+    .not_retryable()
+    .with_tags(&["destructive", "debug-endpoint"])]
+}
+
+/// Setup skipping the group when the orchestrator has no /debug/shutdown
+/// endpoint. The probe is a GET, which a shutdown handler must refuse.
+pub fn hooks() -> GroupHooks {
+    GroupHooks {
+        setup: Some(Box::new(|client| Box::pin(probe(client)))),
+        teardown: None,
+    }
+}
+
+async fn probe(client: &OrchestratorClient) -> Result<(), String> {
+    let resp = client
+        .request_raw(Method::GET, "/debug/shutdown", None)
+        .await
+        .map_err(|e| format!("GET /debug/shutdown failed: {e}"))?;
+    if resp.status == StatusCode::NOT_FOUND {
+        return Err("the orchestrator has no /debug/shutdown endpoint".to_string());
+    }
+    Ok(())
+}
+
+/// Fill the pool and queue one more create, then shut down gracefully. The
+/// queued create must be served or refused with a clean 503, never cut off.
+/// Once /health answers again, a create → GET → delete cycle must work.
+async fn test_graceful_shutdown(
+    client: &OrchestratorClient,
+    settings: ShutdownSettings,
+) -> Result<(), String> {
+    let held = capacity::fill_pool(client, settings.pool_size).await?;
+    let mut pending = {
+        let client = client.clone();
+        context::spawn(async move {
+            let data = serde_json::json!({"user": util::unique("shutdown_queued")});
+            client.create_session(data).await
+        })
+    };
+    tokio::time::sleep(QUEUE_SETTLE).await;
+    if pending.is_finished() {
+        context::warn("the extra create was not queued, so nothing was in flight at shutdown");
+    }
+
+    let in_flight = match client.shutdown(true).await {
+        // The orchestrator may go down before it finishes answering.
+        Ok(()) | Err(ClientError::Transport { .. }) => {
+            tokio::select! {
+                joined = &mut pending => Some(joined),
+                _ = tokio::time::sleep(DRAIN_TIMEOUT) => None,
+            }
+        }
+        Err(e) => {
+            pending.abort();
+            capacity::release(client, &held).await;
+            return Err(format!("POST /debug/shutdown?graceful=true failed: {e}"));
+        }
+    };
+
+    let back = util::poll_until(settings.timeout, POLL_INTERVAL, || client.health_detailed())
+        .await
+        .map_err(|e| format!("orchestrator did not come back after the shutdown: {e}"));
+    capacity::release(client, &held).await;
+
+    let served = match in_flight {
+        None => {
+            pending.abort();
+            return Err(format!(
+                "the queued create got no answer within {}s of the shutdown",
+                DRAIN_TIMEOUT.as_secs()
+            ));
+        }
+        Some(Err(e)) => return Err(format!("task join error: {e}")),
+        Some(Ok(result)) => result,
+    };
+    match served {
+        Ok(session) => {
+            let _ = client.delete_session(&session.id).await;
+        }
+        Err(ClientError::Http { status, .. }) if status == StatusCode::SERVICE_UNAVAILABLE => {}
+        Err(ClientError::Transport { source: e, .. }) => {
+            return Err(format!(
+                "the queued create was cut off by the shutdown instead of served or refused with 503: {e}"
+            ))
+        }
+        Err(e) => {
+            return Err(format!(
+                "the queued create failed during the shutdown, expected success or 503: {e}"
+            ))
+        }
+    }
+
+    back?;
+    check_cycle(client).await
+}
+
+/// One create → GET → delete round trip.
+async fn check_cycle(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("shutdown_after")});
+    let session = client
+        .create_session(data)
+        .await
+        .map_err(|e| format!("create after the shutdown failed: {e}"))?;
+    let fetched = client.get_session(&session.id).await;
+    let _ = client.delete_session(&session.id).await;
+    fetched.map_err(|e| format!("GET {} after the shutdown failed: {e}", session.id))?;
+    Ok(())
+}
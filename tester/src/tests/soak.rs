@@ -0,0 +1,167 @@
+use crate::client::OrchestratorClient;
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::util;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
+
+/// Cycles allowed in flight at once, kept below the default pool of 10 so the
+/// soak measures the orchestrator rather than its queue.
+const MAX_IN_FLIGHT: usize = 8;
+
+/// Fraction of failed cycles above which the soak fails.
+const MAX_ERROR_RATE: f64 = 0.01;
+
+/// Slowdown of p95 latency, last quarter over first, that counts as degradation.
+const MAX_P95_GROWTH: f64 = 2.0;
+
+/// Extra time on top of the soak duration for in-flight cycles to finish.
+const DRAIN_ALLOWANCE: Duration = Duration::from_secs(60);
+
+/// Length and pace of the soak run.
+#[derive(Debug, Clone, Copy)]
+pub struct SoakSettings {
+    /// None leaves the soak test skipped.
+    pub duration: Option<Duration>,
+    /// Target create/get/delete cycles per second.
+    pub rps: u32,
+}
+
+/// Register the soak test. It runs for as long as it is told to, so it is
+/// skipped unless a duration was given.
+pub fn tests(settings: &SoakSettings) -> Vec<TestCase> {
+    let rps = settings.rps;
+    let Some(duration) = settings.duration else {
+        return vec![
+            TestCase::new("Sustained load soak", |_| Box::pin(async { Ok(()) }))
+                .with_tags(&["slow"])
+                .skip("requires --soak-duration"),
+        ];
+    };
+    vec![TestCase::new(
+        format!("Sustained load soak ({}s at {rps}/s)", duration.as_secs()),
+        move |client| Box::pin(test_soak(client, duration, rps)),
+    )
+    .with_timeout(duration + DRAIN_ALLOWANCE)
+    .with_tags(&["slow"])]
+}
+
+/// Outcome of one create/get/delete cycle.
+struct Sample {
+    /// When the cycle started, relative to the start of the soak.
+    started: Duration,
+    latency: Duration,
+    ok: bool,
+}
+
+/// Run create → GET → delete cycles at `rps` for `duration`, then check the
+/// error rate and that p95 latency did not degrade over the run. Percentiles,
+/// error rate, and achieved rate are recorded as metrics either way.
+async fn test_soak(
+    client: &OrchestratorClient,
+    duration: Duration,
+    rps: u32,
+) -> Result<(), String> {
+    let period = Duration::from_secs(1) / rps;
+    let permits = Arc::new(Semaphore::new(MAX_IN_FLIGHT));
+    let start = Instant::now();
+    let mut next = start;
+    let mut handles = Vec::new();
+
+    // Token bucket with a burst of one second's worth of cycles: falling
+    // behind briefly is made up, a long stall is not.
+    while start.elapsed() < duration {
+        tokio::time::sleep_until(next.into()).await;
+        let permit = permits
+            .clone()
+            .acquire_owned()
+            .await
+            .expect("semaphore is never closed");
+        let client = client.clone();
+        handles.push(context::spawn(async move {
+            let started = start.elapsed();
+            let ok = soak_cycle(&client).await.is_ok();
+            drop(permit);
+            Sample {
+                started,
+                latency: start.elapsed() - started,
+                ok,
+            }
+        }));
+        next = (next + period).max(Instant::now() - Duration::from_secs(1));
+    }
+
+    let mut samples = Vec::with_capacity(handles.len());
+    for handle in futures::future::join_all(handles).await {
+        samples.push(handle.map_err(|e| format!("task join error: {e}"))?);
+    }
+    let elapsed = start.elapsed();
+
+    let failed = samples.iter().filter(|s| !s.ok).count();
+    let error_rate = failed as f64 / samples.len().max(1) as f64;
+    let mut latencies: Vec<_> = samples.iter().filter(|s| s.ok).map(|s| s.latency).collect();
+    latencies.sort_unstable();
+    context::record_metric("cycles", samples.len() as f64);
+    context::record_metric(
+        "achieved_rps",
+        round2(samples.len() as f64 / elapsed.as_secs_f64()),
+    );
+    context::record_metric("error_rate", round2(error_rate));
+    for (name, p) in [("p50_ms", 50.0), ("p95_ms", 95.0), ("p99_ms", 99.0)] {
+        if let Some(latency) = util::percentile(&latencies, p) {
+            context::record_metric(name, latency.as_millis() as f64);
+        }
+    }
+
+    if error_rate > MAX_ERROR_RATE {
+        return Err(format!(
+            "{failed}/{} cycles failed ({:.1}%, limit {:.1}%)",
+            samples.len(),
+            error_rate * 100.0,
+            MAX_ERROR_RATE * 100.0
+        ));
+    }
+
+    let quarter = duration / 4;
+    let first = quarter_p95(&samples, Duration::ZERO, quarter);
+    let last = quarter_p95(&samples, duration - quarter, duration);
+    if let (Some(first), Some(last)) = (first, last) {
+        if last.as_secs_f64() > first.as_secs_f64() * MAX_P95_GROWTH {
+            return Err(format!(
+                "p95 latency degraded from {}ms in the first quarter to {}ms in the last",
+                first.as_millis(),
+                last.as_millis()
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// One create → GET → delete round trip.
+async fn soak_cycle(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("soak")});
+    let session = client.create_session(data).await?;
+    let fetched = client.get_session(&session.id).await;
+    let deleted = client.delete_session(&session.id).await?;
+    fetched?;
+    if !deleted.is_success() {
+        return Err(format!("DELETE returned {deleted}"));
+    }
+    Ok(())
+}
+
+/// p95 latency of the successful cycles started in `[from, to)`.
+fn quarter_p95(samples: &[Sample], from: Duration, to: Duration) -> Option<Duration> {
+    let mut latencies: Vec<_> = samples
+        .iter()
+        .filter(|s| s.ok && s.started >= from && s.started < to)
+        .map(|s| s.latency)
+        .collect();
+    latencies.sort_unstable();
+    util::percentile(&latencies, 95.0)
+}
+
+fn round2(value: f64) -> f64 {
+    (value * 100.0).round() / 100.0
+}
@@ -0,0 +1,45 @@
+use crate::client::{ClientConfig, ClientError, OrchestratorClient};
+use crate::runner::TestCase;
+use std::time::{Duration, Instant};
+
+/// TEST-NET-1 (RFC 5737): never routed, so SYNs are dropped rather than refused.
+const NON_ROUTABLE_URL: &str = "http://192.0.2.1:8080";
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Slack for scheduling delays on a busy machine.
+const TOLERANCE: Duration = Duration::from_secs(2);
+
+/// Register client timeout test cases.
+pub fn tests() -> Vec<TestCase> {
+    vec![TestCase::new("Connect timeout is honoured", |client| {
+        Box::pin(test_connect_timeout(client))
+    })
+    .with_tags(&["network"])]
+}
+
+/// A client aimed at a black-holed address must fail within its connect
+/// timeout, not wait out the much longer request timeout.
+async fn test_connect_timeout(_client: &OrchestratorClient) -> Result<(), String> {
+    let config = ClientConfig {
+        connect_timeout: Some(CONNECT_TIMEOUT),
+        ..ClientConfig::default()
+    };
+    let unreachable = OrchestratorClient::with_config(NON_ROUTABLE_URL, &config);
+
+    let start = Instant::now();
+    let result = unreachable.health().await;
+    let elapsed = start.elapsed();
+
+    match result {
+        Err(ClientError::Transport { source: e, .. }) if e.is_connect() || e.is_timeout() => {}
+        Err(e) => return Err(format!("expected a connect error, got: {e}")),
+        Ok(body) => return Err(format!("non-routable address answered: {body:?}")),
+    }
+    if elapsed > CONNECT_TIMEOUT + TOLERANCE {
+        return Err(format!(
+            "connect timeout of {CONNECT_TIMEOUT:?} not honoured: gave up after {elapsed:?}"
+        ));
+    }
+    Ok(())
+}
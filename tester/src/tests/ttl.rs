@@ -1,28 +1,472 @@
-use crate::client::OrchestratorClient;
+use crate::client::{ClientError, IdEncoding, OrchestratorClient, RawResponse};
+use crate::context;
 use crate::runner::TestCase;
+use crate::tests::util::{self, Check};
+use futures::stream::{FuturesUnordered, StreamExt};
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::time::{Duration, Instant};
+
+/// How often the boundary test checks that a session is still alive.
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How often the expiration test checks whether an absolute-TTL session is
+/// gone yet.
+const EXPIRY_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Margin kept before the earliest moment a sweep may legitimately reap.
+const BOUNDARY_MARGIN: Duration = Duration::from_secs(2);
+
+/// Allowance past TTL + one sweep for the reaping request itself.
+const EXPIRY_SLACK: Duration = Duration::from_secs(2);
+
+/// Concurrent creates the bulk test issues once the expired sessions are gone.
+const REFILL_SESSIONS: usize = 10;
+
+/// How long those creates may take before the pool counts as not released.
+const REFILL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Whether activity on a session pushes its expiry back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum TtlMode {
+    /// Each access restarts the TTL (idle timeout).
+    Sliding,
+    /// The TTL runs from creation regardless of activity.
+    Absolute,
+}
+
+/// TTL behaviour the orchestrator under test is configured with.
+#[derive(Debug, Clone, Copy)]
+pub struct TtlSettings {
+    /// Idle time after which a session expires.
+    pub ttl: Duration,
+    /// How often the orchestrator sweeps for expired sessions.
+    pub sweep_interval: Duration,
+    pub mode: TtlMode,
+    /// Sessions the bulk expiration test creates at once.
+    pub bulk_sessions: usize,
+    /// Status an expired session must get, where it is told apart from one
+    /// that never existed; None only reports whether it is.
+    pub expired_status: Option<StatusCode>,
+}
+
+impl TtlSettings {
+    /// How long to wait before an idle session must be gone: the TTL plus two
+    /// sweep intervals, covering a sweep that just missed the deadline.
+    pub fn expiry_wait(&self) -> Duration {
+        self.ttl + 2 * self.sweep_interval
+    }
+
+    /// How long a session must certainly survive: a sweep may run up to one
+    /// interval early relative to the deadline, less a small margin.
+    fn alive_window(&self) -> Duration {
+        self.ttl
+            .saturating_sub(self.sweep_interval)
+            .saturating_sub(BOUNDARY_MARGIN)
+    }
+
+    /// Latest a session may still be alive after its TTL started, once
+    /// the sweeper has had a chance to run.
+    fn expiry_deadline(&self) -> Duration {
+        self.ttl + self.sweep_interval + EXPIRY_SLACK
+    }
+
+    /// Time the bulk test allows for creating its sessions, so setup does not
+    /// eat far into the TTL window it is measuring.
+    fn bulk_setup_budget(&self) -> Duration {
+        (self.ttl / 4).max(REFILL_TIMEOUT)
+    }
+
+    /// Worst-case run time of the slowest TTL test, so callers can check it
+    /// fits in the per-test timeout.
+    pub fn longest_test(&self) -> Duration {
+        // Boundary test: polls for the alive window, then waits out the TTL
+        // from the last poll.
+        let boundary = self.alive_window() + self.expiry_wait();
+        // Refresh test: keeps the session busy for two TTLs, then lets it expire.
+        let refresh = match self.mode {
+            TtlMode::Sliding => 2 * self.ttl + self.expiry_deadline(),
+            TtlMode::Absolute => self.expiry_deadline(),
+        };
+        // Bulk test: setup, expiry, then refilling the pool.
+        let bulk = self.bulk_setup_budget() + self.expiry_wait() + REFILL_TIMEOUT;
+        boundary.max(refresh).max(bulk)
+    }
+}
 
 /// Register TTL test cases.
-pub fn tests() -> Vec<TestCase> {
-    vec![TestCase {
-        name: "Session TTL expiration (60s)".to_string(),
-        func: Box::new(|client: &OrchestratorClient| {
-            Box::pin(test_session_ttl(client))
-        }),
-    }]
-}
-
-/// Create a session, wait for the TTL to expire (~65s), then verify GET returns 404.
-async fn test_session_ttl(client: &OrchestratorClient) -> Result<(), String> {
-    let data = serde_json::json!({"user": "ttl_test"});
+pub fn tests(settings: &TtlSettings) -> Vec<TestCase> {
+    let settings = *settings;
+    let wait = settings.expiry_wait();
+    vec![
+        TestCase::new(
+            format!("Session TTL expiration ({}s)", wait.as_secs()),
+            move |client| Box::pin(test_session_ttl(client, settings)),
+        )
+        .with_tags(&["slow"]),
+        TestCase::new("Expired session vs one that never existed", move |client| {
+            Box::pin(test_expired_vs_missing(client, settings))
+        })
+        .with_tags(&["slow"]),
+        TestCase::new(
+            format!(
+                "Session alive until TTL boundary ({}s)",
+                settings.alive_window().as_secs()
+            ),
+            move |client| Box::pin(test_ttl_boundary(client, settings)),
+        )
+        .with_tags(&["slow"]),
+        TestCase::new(
+            match settings.mode {
+                TtlMode::Sliding => "Activity extends session TTL (sliding)",
+                TtlMode::Absolute => "Activity does not extend session TTL (absolute)",
+            },
+            move |client| Box::pin(test_ttl_refresh(client, settings)),
+        )
+        .with_tags(&["slow"]),
+        TestCase::new(
+            format!("Bulk TTL expiration ({} sessions)", settings.bulk_sessions),
+            move |client| Box::pin(test_bulk_ttl(client, settings)),
+        )
+        .with_tags(&["slow"]),
+    ]
+}
+
+/// Create a session and verify GET returns 404 once the TTL has expired.
+/// Under an absolute TTL the GETs don't extend it, so the session is checked
+/// from the earliest moment a sweep may reap it and the test ends when it is
+/// gone; under a sliding TTL each GET would restart the timer, so it is only
+/// checked once the wait is over.
+async fn test_session_ttl(
+    client: &OrchestratorClient,
+    settings: TtlSettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("ttl_test")});
     let session = client.create_session(data).await?;
+    let created = Instant::now();
+    let deadline = created + settings.expiry_wait();
 
-    // The orchestrator TTL is 60s with a 5s sweep interval,
-    // so we need to wait at least 65 seconds.
-    tokio::time::sleep(tokio::time::Duration::from_secs(67)).await;
+    tokio::time::sleep_until(first_expiry_check(settings, created, deadline).into()).await;
+    util::wait_for(deadline, EXPIRY_INTERVAL, || async {
+        match client.get_session(&session.id).await {
+            Err(ClientError::NotFound { .. }) => Check::Done,
+            Err(ClientError::Http { status, .. }) if Some(status) == settings.expired_status => {
+                Check::Done
+            }
+            Ok(_) => Check::NotYet("session still alive after TTL — expected 404".into()),
+            Err(e) => Check::Fatal(format!("unexpected error: {e}")),
+        }
+    })
+    .await?;
+    Ok(())
+}
+
+/// When to start checking whether sessions created from `created` on have
+/// expired: under an absolute TTL from the earliest moment a sweep may reap
+/// them; under a sliding TTL, where each GET would restart the timer, only
+/// at `deadline`.
+fn first_expiry_check(settings: TtlSettings, created: Instant, deadline: Instant) -> Instant {
+    match settings.mode {
+        TtlMode::Absolute => created + settings.alive_window(),
+        TtlMode::Sliding => deadline,
+    }
+}
+
+/// Wait for a session to expire, then GET it and a random ID that never
+/// existed. Both must get a 4xx. With `expired_status` the expired one must get it
+/// and the other must not; otherwise whether and how they differ, by status
+/// or error body, is noted.
+async fn test_expired_vs_missing(
+    client: &OrchestratorClient,
+    settings: TtlSettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("ttl_expired")});
+    let session = client.create_session(data).await?;
+    let created = Instant::now();
+    let deadline = created + settings.expiry_wait();
+
+    tokio::time::sleep_until(first_expiry_check(settings, created, deadline).into()).await;
+    util::wait_for(deadline, EXPIRY_INTERVAL, || async {
+        match client
+            .get_session_raw(&session.id, IdEncoding::Encoded)
+            .await
+        {
+            Ok(resp) if settings.expired_status.is_some_and(|s| resp.status == s) => Check::Done,
+            Ok(resp) if settings.expired_status.is_none() && resp.status.is_client_error() => {
+                Check::Done
+            }
+            Ok(resp) => Check::NotYet(format!("the expired session got {resp}")),
+            Err(e) => Check::Fatal(format!("GET {} failed: {e}", session.id)),
+        }
+    })
+    .await?;
+
+    let expired = client
+        .get_session_raw(&session.id, IdEncoding::Encoded)
+        .await?;
+    let missing_id = uuid::Uuid::new_v4().to_string();
+    let missing = client
+        .get_session_raw(&missing_id, IdEncoding::Encoded)
+        .await?;
+    if expired.status.is_success() {
+        return Err("session still alive after TTL".into());
+    }
+    for (what, resp) in [("expired", &expired), ("never-existed", &missing)] {
+        if !resp.status.is_client_error() {
+            return Err(format!("expected a 4xx for the {what} session, got {resp}"));
+        }
+    }
+
+    if let Some(status) = settings.expired_status {
+        if expired.status != status {
+            return Err(format!(
+                "expected {status} for the expired session, got {expired}"
+            ));
+        }
+        if missing.status == status {
+            return Err(format!("the never-existed session also got {missing}"));
+        }
+        return Ok(());
+    }
+    let expired_body = error_body(&expired, &session.id);
+    let missing_body = error_body(&missing, &missing_id);
+    if expired.status == missing.status && expired_body == missing_body {
+        context::note(format!(
+            "expired and never-existed sessions are answered alike: {} {expired_body}",
+            expired.status.as_u16()
+        ));
+    } else {
+        context::note(format!(
+            "expired: {} {expired_body}; never existed: {} {missing_body}",
+            expired.status.as_u16(),
+            missing.status.as_u16()
+        ));
+    }
+    Ok(())
+}
+
+/// An error body with `id` replaced, parsed as JSON if it is, so bodies
+/// differing only in the ID they name compare equal.
+fn error_body(resp: &RawResponse, id: &str) -> Value {
+    let body = resp.body.replace(id, "<id>");
+    serde_json::from_str(&body).unwrap_or(Value::String(body))
+}
+
+/// A session must not be reaped early: poll it until just before the earliest
+/// legitimate expiry, then stop touching it and verify it does expire.
+///
+/// GET refreshes the orchestrator's idle timer, so expiry is measured from the
+/// last successful poll rather than from creation.
+async fn test_ttl_boundary(
+    client: &OrchestratorClient,
+    settings: TtlSettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("ttl_boundary")});
+    let session = client.create_session(data).await?;
+    let created = Instant::now();
+    let alive_until = created + settings.alive_window();
+    let mut last_touch = created;
+
+    loop {
+        match client.get_session(&session.id).await {
+            Ok(_) => last_touch = Instant::now(),
+            Err(ClientError::NotFound { .. }) => {
+                return Err(format!(
+                    "session reaped early: first 404 at {:.1}s after creation, \
+                     {:.1}s after last access (expected alive for {}s)",
+                    created.elapsed().as_secs_f64(),
+                    last_touch.elapsed().as_secs_f64(),
+                    settings.alive_window().as_secs()
+                ));
+            }
+            Err(e) => return Err(format!("poll failed: {e}")),
+        }
+        let now = Instant::now();
+        if now >= alive_until {
+            break;
+        }
+        tokio::time::sleep((alive_until - now).min(POLL_INTERVAL)).await;
+    }
+
+    tokio::time::sleep_until((last_touch + settings.expiry_wait()).into()).await;
 
     match client.get_session(&session.id).await {
-        Err(e) if e == "404" => Ok(()),
-        Ok(_) => Err("session still alive after TTL — expected 404".into()),
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(_) => Err(format!(
+            "session still alive {:.1}s after last access (TTL {}s, sweep {}s)",
+            last_touch.elapsed().as_secs_f64(),
+            settings.ttl.as_secs(),
+            settings.sweep_interval.as_secs()
+        )),
         Err(e) => Err(format!("unexpected error: {e}")),
     }
 }
+
+/// Touch a session every ttl/2 for two TTLs. Under a sliding TTL it must stay
+/// alive throughout and expire once left alone; under an absolute TTL it must
+/// expire on schedule despite the activity.
+async fn test_ttl_refresh(
+    client: &OrchestratorClient,
+    settings: TtlSettings,
+) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("ttl_refresh")});
+    let session = client.create_session(data).await?;
+    let created = Instant::now();
+    let touch_interval = settings.ttl / 2;
+    let busy_until = created + 2 * settings.ttl;
+    let mut last_touch = created;
+
+    while Instant::now() < busy_until {
+        tokio::time::sleep(touch_interval).await;
+        match client.get_session(&session.id).await {
+            Ok(_) => last_touch = Instant::now(),
+            Err(ClientError::NotFound { .. }) => {
+                let died = created.elapsed();
+                return match settings.mode {
+                    TtlMode::Sliding => Err(format!(
+                        "session expired {:.1}s after creation despite a GET every {}s; \
+                         the TTL looks absolute (run with --ttl-mode absolute)",
+                        died.as_secs_f64(),
+                        touch_interval.as_secs_f64()
+                    )),
+                    TtlMode::Absolute if died < settings.alive_window() => Err(format!(
+                        "session reaped early: 404 at {:.1}s after creation (TTL {}s)",
+                        died.as_secs_f64(),
+                        settings.ttl.as_secs()
+                    )),
+                    TtlMode::Absolute => Ok(()),
+                };
+            }
+            Err(e) => return Err(format!("GET failed: {e}")),
+        }
+        if settings.mode == TtlMode::Absolute && created.elapsed() > settings.expiry_deadline() {
+            return Err(format!(
+                "session still alive {:.1}s after creation with regular activity; \
+                 the TTL looks sliding (run with --ttl-mode sliding)",
+                created.elapsed().as_secs_f64()
+            ));
+        }
+    }
+
+    // Sliding: leave the session alone and let the idle timer run out. Any
+    // GET now would restart it, so check only once at the deadline.
+    tokio::time::sleep_until((last_touch + settings.expiry_deadline()).into()).await;
+    match client.get_session(&session.id).await {
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Ok(_) => Err(format!(
+            "session still alive {:.1}s after its last access (TTL {}s, sweep {}s)",
+            last_touch.elapsed().as_secs_f64(),
+            settings.ttl.as_secs(),
+            settings.sweep_interval.as_secs()
+        )),
+        Err(e) => Err(format!("unexpected error: {e}")),
+    }
+}
+
+/// Expire many sessions in the same sweep, waiting for each to get a 404,
+/// then check the pool can serve fresh creates right away, i.e. expiry
+/// released the workers and not only the session mappings.
+async fn test_bulk_ttl(client: &OrchestratorClient, settings: TtlSettings) -> Result<(), String> {
+    let count = settings.bulk_sessions;
+    let budget = settings.bulk_setup_budget();
+    let started = Instant::now();
+    let (ids, errors) = create_many(client, count, "ttl_bulk", budget).await;
+    if ids.len() < count {
+        let cause = errors.first().map_or_else(
+            || {
+                format!(
+                    "timed out after {}s; is the worker pool smaller than --bulk-sessions?",
+                    budget.as_secs()
+                )
+            },
+            String::clone,
+        );
+        return Err(format!(
+            "created only {}/{count} sessions: {cause}",
+            ids.len()
+        ));
+    }
+
+    let deadline = Instant::now() + settings.expiry_wait();
+    tokio::time::sleep_until(first_expiry_check(settings, started, deadline).into()).await;
+    let mut checks: FuturesUnordered<_> = ids
+        .iter()
+        .map(|id| async move { (id, util::wait_for_404(client, id, deadline).await) })
+        .collect();
+    let mut survivors = Vec::new();
+    while let Some((id, result)) = checks.next().await {
+        match result {
+            Ok(_) => {}
+            Err(util::WaitError::TimedOut { .. }) => survivors.push(id.as_str()),
+            Err(e) => return Err(format!("GET {id} failed: {e}")),
+        }
+    }
+    drop(checks);
+    if !survivors.is_empty() {
+        survivors.sort_unstable();
+        let mut shown = survivors[..survivors.len().min(5)].join(", ");
+        if survivors.len() > 5 {
+            shown.push_str(&format!(", ... ({} more)", survivors.len() - 5));
+        }
+        return Err(format!(
+            "{}/{count} sessions still alive after {}s: {shown}",
+            survivors.len(),
+            settings.expiry_wait().as_secs()
+        ));
+    }
+
+    let (refill, errors) = create_many(client, REFILL_SESSIONS, "ttl_refill", REFILL_TIMEOUT).await;
+    for id in &refill {
+        let _ = client.delete_session(id).await;
+    }
+    if refill.len() < REFILL_SESSIONS {
+        let cause = errors.first().map_or_else(
+            || {
+                format!(
+                    "the rest were still waiting for a worker after {}s",
+                    REFILL_TIMEOUT.as_secs()
+                )
+            },
+            String::clone,
+        );
+        return Err(format!(
+            "expired sessions did not free their workers: only {}/{REFILL_SESSIONS} new \
+             sessions created: {cause}",
+            refill.len()
+        ));
+    }
+    Ok(())
+}
+
+/// Create `count` sessions in parallel, giving up on any not created within
+/// `budget`. Returns the IDs created and the errors of those that failed.
+async fn create_many(
+    client: &OrchestratorClient,
+    count: usize,
+    prefix: &str,
+    budget: Duration,
+) -> (Vec<String>, Vec<String>) {
+    let mut handles: FuturesUnordered<_> = (0..count)
+        .map(|i| {
+            let client = client.clone();
+            let data = serde_json::json!({"user": util::unique(&format!("{prefix}_{i}"))});
+            context::spawn(async move { client.create_session(data).await })
+        })
+        .collect();
+
+    let deadline = tokio::time::Instant::now() + budget;
+    let mut ids = Vec::new();
+    let mut errors = Vec::new();
+    while let Ok(Some(joined)) = tokio::time::timeout_at(deadline, handles.next()).await {
+        match joined {
+            Ok(Ok(session)) => ids.push(session.id),
+            Ok(Err(e)) => errors.push(e.to_string()),
+            Err(e) => errors.push(format!("task join error: {e}")),
+        }
+    }
+    for handle in handles.iter() {
+        handle.abort();
+    }
+    (ids, errors)
+}
@@ -0,0 +1,529 @@
+use std::fmt;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::client::{ClientError, OrchestratorClient, RawResponse};
+use crate::context;
+use reqwest::StatusCode;
+
+static NEXT_MARKER: AtomicU64 = AtomicU64::new(1);
+
+/// Returns `prefix` with a process-unique suffix, e.g. "test_create_7", so
+/// payloads stay distinct when the suite runs more than once (`--repeat`).
+pub fn unique(prefix: &str) -> String {
+    format!("{prefix}_{}", NEXT_MARKER.fetch_add(1, Ordering::Relaxed))
+}
+
+/// Check an error response against the schema clients rely on: a JSON object
+/// with a non-empty string `error` and optionally a string or numeric `code`,
+/// served as `application/json`. Failures include the raw body.
+pub fn check_error_schema(resp: &RawResponse) -> Result<(), String> {
+    let content_type = resp
+        .headers
+        .get(reqwest::header::CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    let essence = content_type.split(';').next().unwrap_or_default().trim();
+    if !essence.eq_ignore_ascii_case("application/json") {
+        return Err(format!(
+            "{} error has Content-Type {content_type:?}, not application/json; body: {:?}",
+            resp.status, resp.body
+        ));
+    }
+    let problem = match serde_json::from_str::<serde_json::Value>(&resp.body) {
+        Err(e) => Some(format!("body is not JSON ({e})")),
+        Ok(serde_json::Value::Object(fields)) => schema_problem(&fields),
+        Ok(_) => Some("body is not a JSON object".to_string()),
+    };
+    match problem {
+        Some(problem) => Err(format!(
+            "{} error: {problem}; body: {:?}",
+            resp.status, resp.body
+        )),
+        None => Ok(()),
+    }
+}
+
+/// What is wrong with the fields of an error object, if anything.
+fn schema_problem(fields: &serde_json::Map<String, serde_json::Value>) -> Option<String> {
+    match fields.get("error") {
+        None => return Some("`error` field is missing".to_string()),
+        Some(serde_json::Value::String(error)) if error.trim().is_empty() => {
+            return Some("`error` field is empty".to_string())
+        }
+        Some(serde_json::Value::String(_)) => {}
+        Some(other) => return Some(format!("`error` field is {other}, not a string")),
+    }
+    match fields.get("code") {
+        Some(code) if !code.is_string() && !code.is_number() => {
+            Some(format!("`code` field is {code}, not a string or number"))
+        }
+        _ => None,
+    }
+}
+
+/// `expected` and `actual` must be equal JSON; otherwise the error is the
+/// first difference, as `json_diff` describes it.
+pub fn assert_json_eq(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+) -> Result<(), String> {
+    match json_diff(expected, actual) {
+        Some(diff) => Err(diff),
+        None => Ok(()),
+    }
+}
+
+/// The first place where `actual` differs from `expected`, as a JSONPath-like
+/// location with both values, or None if they are equal. A key only one side
+/// has is reported as missing or added rather than as a changed value.
+pub fn json_diff(expected: &serde_json::Value, actual: &serde_json::Value) -> Option<String> {
+    diff_at("$", expected, actual, 0.0)
+}
+
+/// `json_diff`, but floats within `tolerance` of each other, relative to the
+/// larger of the two, count as equal. Integers are still compared exactly.
+pub fn json_diff_within(
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    tolerance: f64,
+) -> Option<String> {
+    diff_at("$", expected, actual, tolerance)
+}
+
+fn diff_at(
+    path: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    tolerance: f64,
+) -> Option<String> {
+    use serde_json::Value;
+    match (expected, actual) {
+        (Value::Object(want), Value::Object(got)) => {
+            for (key, value) in want {
+                let child = format!("{path}{}", path_key(key));
+                match got.get(key) {
+                    Some(other) => {
+                        if let Some(diff) = diff_at(&child, value, other, tolerance) {
+                            return Some(diff);
+                        }
+                    }
+                    None => return Some(format!("{child} is missing")),
+                }
+            }
+            got.keys()
+                .find(|key| !want.contains_key(*key))
+                .map(|key| format!("{path}{} was added", path_key(key)))
+        }
+        (Value::Array(want), Value::Array(got)) => {
+            for (i, (value, other)) in want.iter().zip(got).enumerate() {
+                if let Some(diff) = diff_at(&format!("{path}[{i}]"), value, other, tolerance) {
+                    return Some(diff);
+                }
+            }
+            (want.len() != got.len())
+                .then(|| format!("{path} has {} elements, expected {}", got.len(), want.len()))
+        }
+        _ if expected == actual => None,
+        (Value::Number(want), Value::Number(got))
+            if (want.is_f64() || got.is_f64()) && tolerance > 0.0 =>
+        {
+            match (want.as_f64(), got.as_f64()) {
+                (Some(a), Some(b)) if (a - b).abs() <= tolerance * a.abs().max(b.abs()) => None,
+                _ => Some(format!(
+                    "at {path}: expected {want}, got {got}, beyond the {tolerance:e} tolerance"
+                )),
+            }
+        }
+        _ if json_type(expected) != json_type(actual) => Some(format!(
+            "at {path}: expected {}, got {}",
+            typed(expected),
+            typed(actual)
+        )),
+        _ => Some(format!(
+            "at {path}: expected {}, got {}",
+            truncate(&expected.to_string()),
+            truncate(&actual.to_string())
+        )),
+    }
+}
+
+/// A value with its type for type mismatches, e.g. `a string "1"`.
+fn typed(value: &serde_json::Value) -> String {
+    match json_type(value) {
+        "null" => "null".to_string(),
+        kind => format!("{kind} {}", truncate(&value.to_string())),
+    }
+}
+
+fn json_type(value: &serde_json::Value) -> &'static str {
+    use serde_json::Value;
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+/// Every key in `expected`, at any depth, that `actual` lacks, as JSONPath-like
+/// locations. Keys under a missing one aren't listed separately.
+pub fn missing_keys(expected: &serde_json::Value, actual: &serde_json::Value) -> Vec<String> {
+    let mut missing = Vec::new();
+    missing_at("$", expected, actual, &mut missing);
+    missing
+}
+
+fn missing_at(
+    path: &str,
+    expected: &serde_json::Value,
+    actual: &serde_json::Value,
+    missing: &mut Vec<String>,
+) {
+    use serde_json::Value;
+    match (expected, actual) {
+        (Value::Object(want), Value::Object(got)) => {
+            for (key, value) in want {
+                let child = format!("{path}{}", path_key(key));
+                match got.get(key) {
+                    Some(other) => missing_at(&child, value, other, missing),
+                    None => missing.push(child),
+                }
+            }
+        }
+        (Value::Array(want), Value::Array(got)) => {
+            for (i, (value, other)) in want.iter().zip(got).enumerate() {
+                missing_at(&format!("{path}[{i}]"), value, other, missing);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// `.key` for plain identifiers, `["key"]` otherwise, shortened if very long.
+fn path_key(key: &str) -> String {
+    let plain = !key.is_empty()
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !key.starts_with(|c: char| c.is_ascii_digit());
+    if plain {
+        format!(".{}", truncate(key))
+    } else {
+        format!("[{}]", truncate(&serde_json::Value::from(key).to_string()))
+    }
+}
+
+/// Cut `text` to a length that fits in a failure message.
+fn truncate(text: &str) -> String {
+    const LIMIT: usize = 60;
+    match text.char_indices().nth(LIMIT) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text.to_string(),
+    }
+}
+
+/// A `len`-character alphanumeric string that is the same for the same `seed`.
+pub fn seeded_string(seed: u64, len: usize) -> String {
+    const ALPHABET: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+    let mut rng = Rng::new(seed);
+    (0..len)
+        .map(|_| ALPHABET[(rng.next_u64() >> 58) as usize % ALPHABET.len()] as char)
+        .collect()
+}
+
+/// Small deterministic generator (xorshift64*) for randomized tests: the same
+/// seed gives the same sequence, so a failing run can be replayed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // The state must never be zero.
+        Self { state: seed | 1 }
+    }
+
+    /// A generator for one named consumer of the run seed, so consumers draw
+    /// independent sequences and adding one doesn't shift the others.
+    pub fn derived(seed: u64, stream: &str) -> Self {
+        Self::new(seed ^ fnv1a(stream))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// A value in `0..n`; `n` must not be zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A value drawn uniformly from `[0, 1)`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// A duration drawn uniformly from `[low, high)`.
+    pub fn duration_between(&mut self, low: Duration, high: Duration) -> Duration {
+        let span = (high - low).as_millis().max(1) as u64;
+        low + Duration::from_millis(self.next_u64() % span)
+    }
+
+    /// Put `items` in a random order (Fisher-Yates).
+    pub fn shuffle<T>(&mut self, items: &mut [T]) {
+        for i in (1..items.len()).rev() {
+            items.swap(i, self.below(i + 1));
+        }
+    }
+}
+
+/// 64-bit FNV-1a hash, enough to tell whether a payload changed.
+pub fn fnv1a(text: &str) -> u64 {
+    text.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `bytes` compressed as a gzip stream.
+pub fn gzip(bytes: &[u8]) -> Vec<u8> {
+    use std::io::Write;
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder
+        .write_all(bytes)
+        .and_then(|()| encoder.finish())
+        .expect("writing to a Vec cannot fail")
+}
+
+/// The bytes a gzip stream decompresses to.
+pub fn gunzip(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(bytes)
+        .read_to_end(&mut decoded)
+        .map_err(|e| format!("not a valid gzip stream: {e}"))?;
+    Ok(decoded)
+}
+
+/// A seed for when `--seed` is not given. Kept below 2^53 so it stays exact
+/// in tools that read the JSON report's numbers as doubles.
+pub fn random_seed() -> u64 {
+    let nanos = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos() as u64;
+    Rng::new(nanos).next_u64() >> 11
+}
+
+/// Nearest-rank percentile of already sorted values.
+pub fn percentile(sorted: &[Duration], p: f64) -> Option<Duration> {
+    if sorted.is_empty() {
+        return None;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
+
+/// A byte count for test names, e.g. "64 KiB" or "10 MiB".
+pub fn format_size(bytes: usize) -> String {
+    const KIB: usize = 1024;
+    const MIB: usize = 1024 * KIB;
+    if bytes >= MIB && bytes.is_multiple_of(MIB) {
+        format!("{} MiB", bytes / MIB)
+    } else if bytes >= KIB && bytes.is_multiple_of(KIB) {
+        format!("{} KiB", bytes / KIB)
+    } else {
+        format!("{bytes} B")
+    }
+}
+
+/// A byte count shown as `format_size` shows it, for naming parameterized
+/// tests after their size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ByteSize(pub usize);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&format_size(self.0))
+    }
+}
+
+/// Call `attempt` every `interval` until it succeeds or `timeout` elapses,
+/// and return what it succeeded with: a `wait_for` where every error is
+/// worth another try. On timeout, the error says how long it polled and
+/// what the last attempt returned.
+pub async fn poll_until<T, E, F, Fut>(
+    timeout: Duration,
+    interval: Duration,
+    mut attempt: F,
+) -> Result<T, String>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    E: fmt::Display,
+{
+    // A Mutex rather than a RefCell keeps the caller's future Send.
+    let value = Mutex::new(None);
+    wait_for(tokio::time::Instant::now() + timeout, interval, || {
+        let attempt = attempt();
+        let value = &value;
+        async move {
+            match attempt.await {
+                Ok(v) => {
+                    *value.lock().unwrap() = Some(v);
+                    Check::Done
+                }
+                Err(e) => Check::NotYet(e.to_string()),
+            }
+        }
+    })
+    .await?;
+    Ok(value
+        .into_inner()
+        .unwrap()
+        .expect("wait_for is done only once a value arrived"))
+}
+
+/// Pause between `wait_for_404` checks.
+const GONE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What one check of a `wait_for` condition found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// The condition holds.
+    Done,
+    /// Not yet, for this reason; check again.
+    NotYet(String),
+    /// It never will, for this reason; stop waiting.
+    Fatal(String),
+}
+
+/// Why `wait_for` stopped before its condition held.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum WaitError {
+    /// The deadline passed; `last` is what the final check found.
+    TimedOut {
+        waited: Duration,
+        checks: u32,
+        last: String,
+    },
+    /// A check returned `Check::Fatal`.
+    Fatal(String),
+}
+
+impl fmt::Display for WaitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::TimedOut {
+                waited,
+                checks,
+                last,
+            } => write!(
+                f,
+                "gave up after {:.1}s ({checks} checks), last: {last}",
+                waited.as_secs_f64()
+            ),
+            Self::Fatal(reason) => f.write_str(reason),
+        }
+    }
+}
+
+impl From<WaitError> for String {
+    fn from(e: WaitError) -> Self {
+        e.to_string()
+    }
+}
+
+/// Check `condition` every `interval` until it is done, with a last check
+/// at `deadline` itself, and return how long that took. Instead of sleeping
+/// for the worst case, a test finishes as soon as the condition holds.
+pub async fn wait_for<F, Fut>(
+    deadline: impl Into<tokio::time::Instant>,
+    interval: Duration,
+    mut condition: F,
+) -> Result<Duration, WaitError>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Check>,
+{
+    let deadline = deadline.into();
+    let start = tokio::time::Instant::now();
+    let mut checks = 0;
+    loop {
+        checks += 1;
+        let last = match condition().await {
+            Check::Done => return Ok(start.elapsed()),
+            Check::NotYet(reason) => reason,
+            Check::Fatal(reason) => return Err(WaitError::Fatal(reason)),
+        };
+        let now = tokio::time::Instant::now();
+        if now >= deadline {
+            return Err(WaitError::TimedOut {
+                waited: now - start,
+                checks,
+                last,
+            });
+        }
+        tokio::time::sleep_until((now + interval).min(deadline)).await;
+    }
+}
+
+/// `wait_for` session `id` to get a 404. A 200 or an error that may pass,
+/// like a 429, a 5xx or no response, is checked again; any other 4xx is
+/// fatal.
+pub async fn wait_for_404(
+    client: &OrchestratorClient,
+    id: &str,
+    deadline: impl Into<tokio::time::Instant>,
+) -> Result<Duration, WaitError> {
+    wait_for(deadline, GONE_INTERVAL, || async {
+        match client.get_session(id).await {
+            Err(ClientError::NotFound { .. }) => Check::Done,
+            Ok(_) => Check::NotYet("session still returns 200".to_string()),
+            Err(ClientError::Http { status, .. })
+                if status.is_client_error() && status != StatusCode::TOO_MANY_REQUESTS =>
+            {
+                Check::Fatal(format!("expected 404, got {status}"))
+            }
+            Err(e) => Check::NotYet(e.to_string()),
+        }
+    })
+    .await
+}
+
+/// Start `a` and `b` on their own tasks at the same moment and wait for both.
+/// Either side that does not finish within `timeout` is reported as hung.
+pub async fn race<A, B>(
+    timeout: Duration,
+    a: A,
+    b: B,
+) -> (Result<A::Output, String>, Result<B::Output, String>)
+where
+    A: Future + Send + 'static,
+    A::Output: Send + 'static,
+    B: Future + Send + 'static,
+    B::Output: Send + 'static,
+{
+    let a = context::spawn(tokio::time::timeout(timeout, a));
+    let b = context::spawn(tokio::time::timeout(timeout, b));
+    let (a, b) = tokio::join!(a, b);
+    (settle(a, timeout), settle(b, timeout))
+}
+
+/// Result of one side of `race`.
+fn settle<T>(
+    joined: Result<Result<T, tokio::time::error::Elapsed>, tokio::task::JoinError>,
+    timeout: Duration,
+) -> Result<T, String> {
+    match joined {
+        Ok(Ok(output)) => Ok(output),
+        Ok(Err(_)) => Err(format!("hung for more than {}s", timeout.as_secs())),
+        Err(e) => Err(format!("task join error: {e}")),
+    }
+}
@@ -0,0 +1,915 @@
+use crate::client::{ClientError, IdEncoding, OrchestratorClient, RawResponse, Session};
+use crate::context;
+use crate::runner::TestCase;
+use crate::tests::util::{self, Rng};
+use reqwest::header::{ACCEPT_ENCODING, CONTENT_ENCODING, LOCATION};
+use reqwest::{Method, StatusCode};
+use serde_json::{Map, Value};
+use std::time::Duration;
+
+/// Payload sizes that must round-trip, as long as they fit in `--max-payload`.
+const PAYLOAD_SIZES: &[usize] = &[64 * 1024, 1024 * 1024];
+
+/// Request timeout for big payloads, well above what small requests need.
+const PAYLOAD_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Test timeout of the payload tests: a big create and a big GET may each
+/// take the whole `PAYLOAD_TIMEOUT`, plus a minute for the small requests.
+const PAYLOAD_TEST_TIMEOUT: Duration = Duration::from_secs(2 * PAYLOAD_TIMEOUT.as_secs() + 60);
+
+/// Size of the blob in the gzip-encoded create.
+const GZIP_CREATE_SIZE: usize = 16 * 1024;
+
+/// Size of the blob whose GET should come back compressed.
+const GZIP_RESPONSE_SIZE: usize = 100 * 1024;
+
+/// Responses are written by Go's net/http, which doesn't compress them.
+const NO_GZIP_RESPONSES: &str = "orchestrator does not compress responses";
+
+/// Generated documents the fuzz test round-trips.
+const FUZZ_DOCUMENTS: u64 = 50;
+
+/// Deepest nesting of a generated document.
+const FUZZ_MAX_DEPTH: usize = 6;
+
+/// How much of an offending document a failure prints.
+const FUZZ_PRINT_LIMIT: usize = 2048;
+
+/// Characters generated strings and keys are drawn from: ASCII, characters
+/// JSON must escape, and multi-byte text including combining marks, RTL
+/// script, and astral-plane emoji.
+const FUZZ_CHARS: &[char] = &[
+    'a', 'b', 'z', 'A', 'Z', '0', '9', ' ', '_', '-', '.', '/', '"', '\\', '\n', '\t', '\r',
+    '\u{1}', '\u{1f}', '\u{7f}', 'é', 'ß', 'ñ', '中', '文', 'ا', 'ل', '\u{301}', '\u{200d}',
+    '\u{feff}', '€', '😀', '🦀', '𝄞',
+];
+
+/// Malformed create requests: (test name, body, content type).
+const BAD_CREATES: &[(&str, &[u8], &str)] = &[
+    ("Empty body is rejected", b"", "application/json"),
+    (
+        "Truncated JSON is rejected",
+        b"{\"user\":",
+        "application/json",
+    ),
+    (
+        "JSON body with form content type is rejected",
+        b"{\"user\": \"validation\"}",
+        "application/x-www-form-urlencoded",
+    ),
+    (
+        "JSON body with text/plain content type is rejected",
+        b"{\"user\": \"validation\"}",
+        "text/plain",
+    ),
+];
+
+/// Whole create bodies of each non-object JSON type: (type, body).
+const TOP_LEVEL_BODIES: &[(&str, &str)] = &[
+    ("array", "[1,2,3]"),
+    ("string", "\"just a string\""),
+    ("number", "42"),
+    ("boolean", "true"),
+    ("null", "null"),
+];
+
+/// Fields beside `user` the unknown-fields create sends, one of which a
+/// refusal must name.
+const SPECULATIVE_FIELDS: &[&str] = &["labels", "priority", "tags"];
+
+/// Which top-level JSON types a create body may have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum PayloadPolicy {
+    /// Only objects; any other body must get a 4xx with a JSON error body.
+    ObjectOnly,
+    /// Any JSON value is stored as the session's data.
+    Any,
+}
+
+/// How a create with a non-object body was handled.
+enum TopLevel {
+    /// Created a session whose data is the body.
+    Accepted,
+    /// Refused with this 4xx and a JSON error body.
+    Rejected(StatusCode),
+    /// Anything else: a 5xx, a plain-text 4xx, or data that changed.
+    Broken(String),
+}
+
+/// Spellings of a session's path a proxy might pass on unnormalized: (test
+/// name, prefix the session ID is appended to).
+const PATH_VARIANTS: &[(&str, &str)] = &[
+    ("GET /sessions//:id matches /sessions/:id", "/sessions//"),
+    ("GET /Sessions/:id matches /sessions/:id", "/Sessions/"),
+];
+
+/// ID no session has, for comparing how paths answer a miss.
+const MISSING_ID: &str = "nonexistent-session-id-12345";
+
+/// Length of the oversized session ID.
+const LONG_ID_LEN: usize = 4096;
+
+/// Session IDs that are hostile to URL paths: (description, ID, how it is put
+/// into the path). Verbatim IDs reach the orchestrator unresolved.
+fn hostile_ids() -> Vec<(&'static str, String, IdEncoding)> {
+    vec![
+        ("a ../ ID", "../".to_string(), IdEncoding::Verbatim),
+        (
+            "a %2e%2e%2f ID",
+            "%2e%2e%2f".to_string(),
+            IdEncoding::Verbatim,
+        ),
+        ("an encoded ../ ID", "../".to_string(), IdEncoding::Encoded),
+        (
+            "an ID with spaces",
+            "has spaces".to_string(),
+            IdEncoding::Encoded,
+        ),
+        ("an ID with #", "frag#ment".to_string(), IdEncoding::Encoded),
+        ("an ID with ?", "query?x=1".to_string(), IdEncoding::Encoded),
+        ("a 4 KiB ID", "a".repeat(LONG_ID_LEN), IdEncoding::Encoded),
+        ("an empty ID", String::new(), IdEncoding::Encoded),
+    ]
+}
+
+/// Register request validation test cases: one per malformed create, one
+/// per top-level JSON type as `payload_policy` expects it plus one that they
+/// are handled alike, duplicate keys, a BOM and unknown fields, one per
+/// path-hostile session ID, path spellings a proxy might not normalize, then
+/// payload size checks against the orchestrator's `max_payload` body limit,
+/// gzip in both directions, and generated documents of up to `fuzz_max_size`
+/// bytes. Payloads are derived from `seed`.
+pub fn tests(
+    max_payload: usize,
+    fuzz_max_size: usize,
+    payload_policy: PayloadPolicy,
+    seed: u64,
+) -> Vec<TestCase> {
+    let mut tests: Vec<_> = BAD_CREATES
+        .iter()
+        .map(|&(name, body, content_type)| {
+            TestCase::new(name, move |client| {
+                Box::pin(test_bad_create(client, body, content_type))
+            })
+        })
+        .collect();
+
+    for &(kind, body) in TOP_LEVEL_BODIES {
+        let name = match payload_policy {
+            PayloadPolicy::ObjectOnly => format!("Top-level {kind} body is rejected"),
+            PayloadPolicy::Any => format!("Top-level {kind} body round-trips"),
+        };
+        tests.push(TestCase::new(name, move |client| {
+            Box::pin(test_top_level(client, body, payload_policy))
+        }));
+    }
+    tests.push(TestCase::new(
+        "Top-level JSON types are handled alike",
+        |client| Box::pin(test_top_level_consistency(client)),
+    ));
+    tests.push(TestCase::new(
+        "Duplicate keys are rejected or the last one wins",
+        |client| Box::pin(test_duplicate_keys(client)),
+    ));
+    tests.push(TestCase::new(
+        "Body with a UTF-8 BOM is accepted or rejected cleanly",
+        |client| Box::pin(test_bom(client)),
+    ));
+    tests.push(TestCase::new(
+        "Unknown fields are preserved or rejected",
+        |client| Box::pin(test_unknown_fields(client)),
+    ));
+
+    for (label, id, encoding) in hostile_ids() {
+        tests.push(TestCase::new(
+            format!("GET for {label} is not found"),
+            move |client| {
+                let id = id.clone();
+                Box::pin(async move { test_hostile_id(client, &id, encoding).await })
+            },
+        ));
+    }
+    tests.push(TestCase::new(
+        "GET /sessions/../health is not served as a session",
+        |client| Box::pin(test_traversal_to_health(client)),
+    ));
+    tests.push(TestCase::new(
+        "POST /sessions/ matches POST /sessions",
+        |client| Box::pin(test_trailing_slash_create(client)),
+    ));
+    for &(name, prefix) in PATH_VARIANTS {
+        tests.push(TestCase::new(name, move |client| {
+            Box::pin(test_path_variant(client, prefix))
+        }));
+    }
+
+    let round_trips = TestCase::parameterized(
+        "{} payload round-trips",
+        PAYLOAD_SIZES.iter().copied().map(util::ByteSize),
+        move |client, size| Box::pin(test_payload_round_trip(client, size.0, seed)),
+    );
+    for (test, &size) in round_trips.into_iter().zip(PAYLOAD_SIZES) {
+        let test = test.with_timeout(PAYLOAD_TEST_TIMEOUT);
+        tests.push(if size < max_payload {
+            test
+        } else {
+            test.skip("exceeds --max-payload")
+        });
+    }
+    tests.push(TestCase::new(
+        "Gzip-encoded create is decoded or refused",
+        move |client| Box::pin(test_gzip_create(client, seed)),
+    ));
+    let test = TestCase::new(
+        format!(
+            "{} response is gzip-compressed when accepted",
+            util::format_size(GZIP_RESPONSE_SIZE)
+        ),
+        move |client| Box::pin(test_gzip_response(client, seed)),
+    )
+    .with_timeout(PAYLOAD_TEST_TIMEOUT)
+    .expected_failure(NO_GZIP_RESPONSES);
+    tests.push(if GZIP_RESPONSE_SIZE < max_payload {
+        test
+    } else {
+        test.skip("exceeds --max-payload")
+    });
+    tests.push(
+        TestCase::new(
+            format!(
+                "Payload over {} is rejected",
+                util::format_size(max_payload)
+            ),
+            move |client| Box::pin(test_payload_too_large(client, max_payload, seed)),
+        )
+        .with_timeout(PAYLOAD_TEST_TIMEOUT),
+    );
+    tests.push(TestCase::new(
+        format!("{FUZZ_DOCUMENTS} generated documents round-trip"),
+        move |client| Box::pin(test_fuzz_round_trip(client, fuzz_max_size, seed)),
+    ));
+    tests
+}
+
+/// A malformed create must get a 4xx with a JSON error body, not a 5xx or a
+/// session.
+async fn test_bad_create(
+    client: &OrchestratorClient,
+    body: &'static [u8],
+    content_type: &'static str,
+) -> Result<(), String> {
+    let resp = client.create_session_raw(body, content_type).await?;
+
+    if resp.status.is_success() {
+        if let Ok(session) = serde_json::from_str::<Session>(&resp.body) {
+            let _ = client.delete_session(&session.id).await;
+        }
+        return Err(format!("accepted with {}", resp.status));
+    }
+    if !resp.status.is_client_error() {
+        return Err(format!("expected 4xx, got {resp}"));
+    }
+    if serde_json::from_str::<serde_json::Value>(&resp.body).is_err() {
+        return Err(format!("error body is not JSON: {:?}", resp.body));
+    }
+    Ok(())
+}
+
+/// Create with `body` as the whole request and see what became of it.
+async fn create_top_level(client: &OrchestratorClient, body: &str) -> Result<TopLevel, String> {
+    let resp = client
+        .create_session_raw(body.as_bytes(), "application/json")
+        .await?;
+    if resp.status.is_client_error() {
+        if serde_json::from_str::<Value>(&resp.body).is_err() {
+            return Ok(TopLevel::Broken(format!(
+                "{} with an error body that is not JSON: {:?}",
+                resp.status, resp.body
+            )));
+        }
+        return Ok(TopLevel::Rejected(resp.status));
+    }
+    if !resp.status.is_success() {
+        return Ok(TopLevel::Broken(resp.to_string()));
+    }
+    let Ok(session) = serde_json::from_str::<Session>(&resp.body) else {
+        return Ok(TopLevel::Broken(format!(
+            "{} without a session: {:?}",
+            resp.status, resp.body
+        )));
+    };
+    let fetched = client.get_session(&session.id).await;
+    let _ = client.delete_session(&session.id).await;
+    let sent: Value = serde_json::from_str(body).expect("top-level bodies are JSON");
+    Ok(match util::json_diff(&sent, &fetched?.data) {
+        None => TopLevel::Accepted,
+        Some(diff) => TopLevel::Broken(format!("accepted, but stored data differs: {diff}")),
+    })
+}
+
+/// A non-object body must be rejected with a 4xx and a JSON error body under
+/// `PayloadPolicy::ObjectOnly`, and stored unchanged under `Any`.
+async fn test_top_level(
+    client: &OrchestratorClient,
+    body: &str,
+    policy: PayloadPolicy,
+) -> Result<(), String> {
+    match (create_top_level(client, body).await?, policy) {
+        (TopLevel::Rejected(_), PayloadPolicy::ObjectOnly)
+        | (TopLevel::Accepted, PayloadPolicy::Any) => Ok(()),
+        (TopLevel::Accepted, PayloadPolicy::ObjectOnly) => Err(format!(
+            "accepted {body} as session data (--payload-policy object-only)"
+        )),
+        (TopLevel::Rejected(status), PayloadPolicy::Any) => Err(format!(
+            "rejected {body} with {status} (--payload-policy any)"
+        )),
+        (TopLevel::Broken(problem), _) => Err(problem),
+    }
+}
+
+/// Whatever the policy, every non-object type must get the same treatment:
+/// all stored unchanged or all rejected cleanly. Arrays working while strings
+/// get a 500 is a bug in either deployment.
+async fn test_top_level_consistency(client: &OrchestratorClient) -> Result<(), String> {
+    let mut outcomes = Vec::new();
+    for &(kind, body) in TOP_LEVEL_BODIES {
+        let outcome = match create_top_level(client, body).await? {
+            TopLevel::Accepted => "accepted".to_string(),
+            TopLevel::Rejected(status) => format!("rejected with {}", status.as_u16()),
+            TopLevel::Broken(problem) => format!("broken ({problem})"),
+        };
+        outcomes.push((kind, outcome));
+    }
+    let accepted = outcomes.iter().all(|(_, o)| o == "accepted");
+    let rejected = outcomes.iter().all(|(_, o)| o.starts_with("rejected"));
+    if accepted || rejected {
+        return Ok(());
+    }
+    Err(format!(
+        "top-level types are handled differently: {}",
+        outcomes
+            .iter()
+            .map(|(kind, outcome)| format!("{kind} {outcome}"))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+}
+
+/// `{"user": a, "user": b}`, built by hand since serde_json won't, must be
+/// rejected with 400 or stored as `b`, and the same way both times it is
+/// sent. Which one is noted.
+async fn test_duplicate_keys(client: &OrchestratorClient) -> Result<(), String> {
+    let mut outcomes = Vec::new();
+    for _ in 0..2 {
+        let marker = util::unique("duplicate_keys");
+        let body = format!(r#"{{"user":"{marker}_first","user":"{marker}_last"}}"#);
+        let resp = client
+            .create_session_raw(body.as_bytes(), "application/json")
+            .await?;
+        let outcome = if resp.status == StatusCode::BAD_REQUEST {
+            "rejected with 400".to_string()
+        } else if resp.status.is_success() {
+            let stored = stored_data(client, &resp).await?;
+            match stored.get("user").and_then(Value::as_str) {
+                Some(user) if user == format!("{marker}_last") => "last value wins".to_string(),
+                Some(user) if user == format!("{marker}_first") => {
+                    return Err("accepted, but the first value won, not the last".to_string())
+                }
+                _ => return Err(format!("accepted, but stored {stored}")),
+            }
+        } else {
+            return Err(format!("expected 400 or a session, got {resp}"));
+        };
+        outcomes.push(outcome);
+    }
+    if outcomes[0] != outcomes[1] {
+        return Err(format!(
+            "not deterministic: {} the first time, {} the second",
+            outcomes[0], outcomes[1]
+        ));
+    }
+    context::note(format!("duplicate keys: {}", outcomes[0]));
+    Ok(())
+}
+
+/// A JSON object prefixed with a UTF-8 byte order mark must be stored as if
+/// the BOM weren't there, or rejected with a 4xx; never a 5xx or data that
+/// includes it. Which one is noted.
+async fn test_bom(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("bom")});
+    let mut body = b"\xef\xbb\xbf".to_vec();
+    body.extend_from_slice(data.to_string().as_bytes());
+    let resp = client.create_session_raw(&body, "application/json").await?;
+
+    if resp.status.is_client_error() {
+        context::note(format!("UTF-8 BOM: rejected with {}", resp.status.as_u16()));
+        return Ok(());
+    }
+    if !resp.status.is_success() {
+        return Err(format!("expected a session or a 4xx, got {resp}"));
+    }
+    let stored = stored_data(client, &resp).await?;
+    if let Some(diff) = util::json_diff(&data, &stored) {
+        return Err(format!("accepted, but stored data differs: {diff}"));
+    }
+    context::note("UTF-8 BOM: accepted and ignored");
+    Ok(())
+}
+
+/// A create mixing the usual `user` with fields no client sends today must
+/// come back with every one of them, nested ones included, or be refused
+/// with a 400 that names one. Dropping some silently fails, listing each
+/// key that vanished.
+async fn test_unknown_fields(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({
+        "user": util::unique("unknown_fields"),
+        "labels": {"team": "infra", "tier": {"name": "gold", "rank": 1}},
+        "priority": 5,
+        "tags": ["smoke", {"source": "steel-tester"}],
+    });
+    let resp = client
+        .create_session_raw(data.to_string().as_bytes(), "application/json")
+        .await?;
+
+    if resp.status == StatusCode::BAD_REQUEST {
+        return match SPECULATIVE_FIELDS
+            .iter()
+            .find(|field| resp.body.contains(*field))
+        {
+            Some(field) => {
+                context::note(format!("unknown fields: rejected, naming {field}"));
+                Ok(())
+            }
+            None => Err(format!(
+                "rejected without naming the offending field: {resp}"
+            )),
+        };
+    }
+    if !resp.status.is_success() {
+        return Err(format!("expected a session or 400, got {resp}"));
+    }
+    let stored = stored_data(client, &resp).await?;
+    let missing = util::missing_keys(&data, &stored);
+    if !missing.is_empty() {
+        return Err(format!("accepted, but dropped {}", missing.join(", ")));
+    }
+    if let Some(diff) = util::json_diff(&data, &stored) {
+        return Err(format!("accepted, but stored data differs: {diff}"));
+    }
+    context::note("unknown fields: preserved");
+    Ok(())
+}
+
+/// The data of the session a raw create made, fetched back, after which the
+/// session is deleted.
+async fn stored_data(client: &OrchestratorClient, resp: &RawResponse) -> Result<Value, String> {
+    let session = serde_json::from_str::<Session>(&resp.body)
+        .map_err(|e| format!("accepted, but the body is not a session ({e}): {resp}"))?;
+    let fetched = client.get_session(&session.id).await;
+    let _ = client.delete_session(&session.id).await;
+    Ok(fetched?.data)
+}
+
+/// A GET for an ID that no session can have must be answered 404 or 400,
+/// never 5xx or a session.
+async fn test_hostile_id(
+    client: &OrchestratorClient,
+    id: &str,
+    encoding: IdEncoding,
+) -> Result<(), String> {
+    let resp = client.get_session_raw(id, encoding).await?;
+    check_not_served(&resp, encoding)
+}
+
+/// Dot segments must not walk out of /sessions: the health endpoint's body
+/// must not come back from a session lookup.
+async fn test_traversal_to_health(client: &OrchestratorClient) -> Result<(), String> {
+    let health = client.health().await?;
+    let resp = client
+        .get_session_raw("../health", IdEncoding::Verbatim)
+        .await?;
+    if !health.is_empty() && resp.body == health {
+        return Err(format!("health endpoint body leaked: {resp}"));
+    }
+    check_not_served(&resp, IdEncoding::Verbatim)
+}
+
+/// 404 or 400 is a pass. A verbatim dot-segment path may also be redirected to
+/// its cleaned form, as Go's ServeMux does, since nothing is served for it.
+fn check_not_served(resp: &RawResponse, encoding: IdEncoding) -> Result<(), String> {
+    let status = resp.status;
+    if status == StatusCode::NOT_FOUND || status == StatusCode::BAD_REQUEST {
+        return Ok(());
+    }
+    if status.is_redirection() && encoding == IdEncoding::Verbatim {
+        return Ok(());
+    }
+    if status.is_success() {
+        return Err(format!("served as a session: {resp}"));
+    }
+    Err(format!("expected 404 or 400, got {resp}"))
+}
+
+/// A create on `/sessions/` must do what one on `/sessions` does, redirect
+/// there, or be refused with 400, 404 or 405. Both are sent verbatim.
+async fn test_trailing_slash_create(client: &OrchestratorClient) -> Result<(), String> {
+    let data = serde_json::json!({"user": util::unique("path_trailing_slash")});
+    let canonical = client
+        .request_verbatim(Method::POST, "/sessions", Some(&data))
+        .await?;
+    if let Ok(session) = serde_json::from_str::<Session>(&canonical.body) {
+        let _ = client.delete_session(&session.id).await;
+    }
+    let variant = client
+        .request_verbatim(Method::POST, "/sessions/", Some(&data))
+        .await?;
+
+    if variant.status.is_success() {
+        let Ok(session) = serde_json::from_str::<Session>(&variant.body) else {
+            return Err(format!(
+                "POST /sessions got {}, POST /sessions/ got {variant}",
+                canonical.status
+            ));
+        };
+        let _ = client.delete_session(&session.id).await;
+        if let Some(diff) = util::json_diff(&data, &session.data) {
+            return Err(format!("POST /sessions/ created a session, but {diff}"));
+        }
+        return Ok(());
+    }
+    if redirects_to(&variant, "/sessions") || refuses(&variant) {
+        return Ok(());
+    }
+    Err(format!(
+        "POST /sessions got {}, POST /sessions/ got {variant}",
+        canonical.status
+    ))
+}
+
+/// GET `<prefix><id>`, for an existing and a missing session, must answer
+/// exactly as `/sessions/<id>` does, redirect there, or be refused with the
+/// same 400, 404 or 405 both times. All are sent verbatim.
+async fn test_path_variant(client: &OrchestratorClient, prefix: &str) -> Result<(), String> {
+    let session = client
+        .create_session(serde_json::json!({"user": util::unique("path_variant")}))
+        .await?;
+    let result = check_path_variant(client, prefix, &session.id).await;
+    let _ = client.delete_session(&session.id).await;
+    result
+}
+
+async fn check_path_variant(
+    client: &OrchestratorClient,
+    prefix: &str,
+    id: &str,
+) -> Result<(), String> {
+    let mut answers = Vec::new();
+    for id in [id, MISSING_ID] {
+        let path = format!("/sessions/{id}");
+        let canonical = client.request_verbatim(Method::GET, &path, None).await?;
+        let variant = client
+            .request_verbatim(Method::GET, &format!("{prefix}{id}"), None)
+            .await?;
+        answers.push((path, canonical, variant));
+    }
+
+    let same = answers.iter().all(|(_, canonical, variant)| {
+        variant.status == canonical.status && variant.body == canonical.body
+    });
+    let redirected = answers
+        .iter()
+        .all(|(path, _, variant)| redirects_to(variant, path));
+    let refused = answers
+        .iter()
+        .all(|(_, _, variant)| refuses(variant) && variant.status == answers[0].2.status);
+    if same || redirected || refused {
+        return Ok(());
+    }
+    let statuses = |pick: fn(&(String, RawResponse, RawResponse)) -> &RawResponse| {
+        answers
+            .iter()
+            .map(|answer| pick(answer).status.as_u16().to_string())
+            .collect::<Vec<_>>()
+            .join("/")
+    };
+    Err(format!(
+        "existing/missing session: /sessions/:id got {}, {prefix}:id got {}",
+        statuses(|answer| &answer.1),
+        statuses(|answer| &answer.2)
+    ))
+}
+
+/// A redirect whose Location is `path`, relative or absolute.
+fn redirects_to(resp: &RawResponse, path: &str) -> bool {
+    let location = resp
+        .headers
+        .get(LOCATION)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default();
+    resp.status.is_redirection() && !location.is_empty() && location.ends_with(path)
+}
+
+/// A clean refusal of a path the orchestrator doesn't route.
+fn refuses(resp: &RawResponse) -> bool {
+    matches!(
+        resp.status,
+        StatusCode::BAD_REQUEST | StatusCode::NOT_FOUND | StatusCode::METHOD_NOT_ALLOWED
+    )
+}
+
+/// A session whose `blob` field is `size` bytes must come back unchanged.
+/// Compared by checksum so a failure doesn't print megabytes.
+async fn test_payload_round_trip(
+    client: &OrchestratorClient,
+    size: usize,
+    seed: u64,
+) -> Result<(), String> {
+    let client = client.with_request_timeout(PAYLOAD_TIMEOUT);
+    // Seeded by the run seed and size, so a failing payload can be
+    // regenerated exactly.
+    let blob = util::seeded_string(seed ^ size as u64, size);
+    let sent = util::fnv1a(&blob);
+    let data = serde_json::json!({"user": util::unique("payload"), "blob": blob});
+    drop(blob);
+
+    let session = client.create_session(data.clone()).await?;
+    let fetched = client.get_session(&session.id).await;
+    let _ = client.delete_session(&session.id).await;
+
+    let fetched = fetched?;
+    let Some(returned) = fetched.data.get("blob").and_then(|v| v.as_str()) else {
+        return Err("GET returned no blob field".to_string());
+    };
+    if returned.len() != size || util::fnv1a(returned) != sent {
+        return Err(format!(
+            "blob changed: sent {size} bytes (fnv1a {sent:016x}), got {} bytes (fnv1a {:016x})",
+            returned.len(),
+            util::fnv1a(returned)
+        ));
+    }
+    // The blob is intact; the rest of the document must be too.
+    util::assert_json_eq(&data, &fetched.data)
+        .map_err(|diff| format!("data around the blob changed: {diff}"))
+}
+
+/// A create sent as `Content-Encoding: gzip` must either be decompressed, so
+/// the stored data is exactly what was compressed, or be refused with 415
+/// Unsupported Media Type. Another 4xx is tolerated with a warning; a 5xx or
+/// stored gzip bytes fail.
+async fn test_gzip_create(client: &OrchestratorClient, seed: u64) -> Result<(), String> {
+    let data = serde_json::json!({
+        "user": util::unique("gzip_create"),
+        "blob": util::seeded_string(seed ^ GZIP_CREATE_SIZE as u64, GZIP_CREATE_SIZE),
+    });
+    let resp = client.create_session_gzip(&data).await?;
+
+    if resp.status == StatusCode::UNSUPPORTED_MEDIA_TYPE {
+        return Ok(());
+    }
+    if resp.status.is_client_error() {
+        context::warn(format!(
+            "gzip create refused with {}, not 415 Unsupported Media Type",
+            resp.status
+        ));
+        return Ok(());
+    }
+    if !resp.status.is_success() {
+        return Err(format!("expected 2xx or 415, got {resp}"));
+    }
+    let session = serde_json::from_str::<Session>(&resp.body)
+        .map_err(|e| format!("accepted, but the body is not a session ({e}): {resp}"))?;
+    let fetched = client.get_session(&session.id).await;
+    let _ = client.delete_session(&session.id).await;
+    match util::json_diff(&data, &fetched?.data) {
+        None => Ok(()),
+        Some(diff) => Err(format!("accepted, but stored data differs: {diff}")),
+    }
+}
+
+/// GET of a session with a `GZIP_RESPONSE_SIZE` blob, sent with
+/// `Accept-Encoding: gzip`, must come back gzip-compressed and decompress to
+/// the session. The encoding and both sizes are recorded either way.
+async fn test_gzip_response(client: &OrchestratorClient, seed: u64) -> Result<(), String> {
+    let client = client.with_request_timeout(PAYLOAD_TIMEOUT);
+    let data = serde_json::json!({
+        "user": util::unique("gzip_response"),
+        "blob": util::seeded_string(seed ^ GZIP_RESPONSE_SIZE as u64, GZIP_RESPONSE_SIZE),
+    });
+    let session = client.create_session(data.clone()).await?;
+    let result = check_gzip_response(&client, &session.id, &data).await;
+    let _ = client.delete_session(&session.id).await;
+    result
+}
+
+async fn check_gzip_response(
+    client: &OrchestratorClient,
+    id: &str,
+    data: &Value,
+) -> Result<(), String> {
+    let path = format!("/sessions/{id}");
+    let req = client
+        .request(Method::GET, &path)
+        .header(ACCEPT_ENCODING, "gzip");
+    let resp = client.send(req).await?;
+    let status = resp.status();
+    let encoding = resp
+        .headers()
+        .get(CONTENT_ENCODING)
+        .and_then(|v| v.to_str().ok())
+        .unwrap_or_default()
+        .to_string();
+    let body = resp.bytes().await.map_err(|e| format!("GET {path}: {e}"))?;
+    context::record_metric("wire_bytes", body.len() as f64);
+    if !status.is_success() {
+        return Err(format!("GET {path}: got {status}"));
+    }
+    if !encoding.eq_ignore_ascii_case("gzip") {
+        let shown = if encoding.is_empty() {
+            "none"
+        } else {
+            &encoding
+        };
+        return Err(format!(
+            "GET {path}: {} bytes sent with Content-Encoding {shown}",
+            body.len()
+        ));
+    }
+
+    let decoded = util::gunzip(&body).map_err(|e| format!("GET {path}: {e}"))?;
+    context::record_metric("decoded_bytes", decoded.len() as f64);
+    let session = serde_json::from_slice::<Session>(&decoded)
+        .map_err(|e| format!("GET {path}: decompressed body is not a session: {e}"))?;
+    match util::json_diff(data, &session.data) {
+        None => Ok(()),
+        Some(diff) => Err(format!("GET {path}: decompressed data differs: {diff}")),
+    }
+}
+
+/// A body over the orchestrator's limit must get 413 or another 4xx, not a
+/// 5xx, a session, or a hang.
+async fn test_payload_too_large(
+    client: &OrchestratorClient,
+    max_payload: usize,
+    seed: u64,
+) -> Result<(), String> {
+    let client = client.with_request_timeout(PAYLOAD_TIMEOUT);
+    let data = serde_json::json!({
+        "user": util::unique("payload_over"),
+        "blob": util::seeded_string(seed ^ max_payload as u64, max_payload + 1),
+    });
+
+    match client.create_session(data).await {
+        Ok(session) => {
+            let _ = client.delete_session(&session.id).await;
+            Err(format!(
+                "accepted a body over {}",
+                util::format_size(max_payload)
+            ))
+        }
+        // 413 Payload Too Large is the expected answer, but any 4xx will do.
+        Err(ClientError::Http { status, .. }) if status.is_client_error() => Ok(()),
+        Err(ClientError::NotFound { .. }) => Ok(()),
+        Err(e) => Err(format!("expected 413 or another 4xx, got {e}")),
+    }
+}
+
+/// Create a session from each of `FUZZ_DOCUMENTS` generated documents and
+/// fetch it back; the data must be exactly what was sent. The first failure
+/// stops the test, printing the document and the seed that regenerates it.
+async fn test_fuzz_round_trip(
+    client: &OrchestratorClient,
+    max_size: usize,
+    seed: u64,
+) -> Result<(), String> {
+    for i in 0..FUZZ_DOCUMENTS {
+        let mut rng = Rng::derived(seed, &format!("fuzz document {i}"));
+        let document = FuzzDocument::generate(&mut rng, max_size);
+        let failure = match client.create_session(document.clone()).await {
+            Err(ClientError::Http { status, body, .. }) if status.is_server_error() => {
+                Some(format!("create got {status}: {body}"))
+            }
+            Err(e) => Some(format!("create failed: {e}")),
+            Ok(session) => {
+                let fetched = client.get_session(&session.id).await;
+                let _ = client.delete_session(&session.id).await;
+                match fetched {
+                    Ok(fetched) => util::json_diff(&document, &fetched.data)
+                        .map(|diff| format!("data changed {diff}")),
+                    Err(e) => Some(format!("GET {} failed: {e}", session.id)),
+                }
+            }
+        };
+        if let Some(failure) = failure {
+            return Err(format!(
+                "document {}/{FUZZ_DOCUMENTS} (--seed {seed}): {failure}\n{}",
+                i + 1,
+                pretty_truncated(&document)
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Random JSON objects within a byte budget. Numbers are kept to values every
+/// JSON implementation holds exactly: integers within ±2^53 and floats with a
+/// fraction in eighths, never integral or negative zero.
+struct FuzzDocument<'a> {
+    rng: &'a mut Rng,
+    /// Bytes left before containers stop growing, roughly as serialized.
+    budget: usize,
+}
+
+impl FuzzDocument<'_> {
+    /// A document of at most about `max_size` bytes once serialized.
+    fn generate(rng: &mut Rng, max_size: usize) -> Value {
+        let depth = 1 + rng.below(FUZZ_MAX_DEPTH);
+        let mut generator = FuzzDocument {
+            rng,
+            budget: max_size.saturating_sub(2),
+        };
+        Value::Object(generator.object(depth))
+    }
+
+    fn value(&mut self, depth: usize) -> Value {
+        let kinds = if depth == 0 { 5 } else { 7 };
+        match self.rng.below(kinds) {
+            0 => Value::Null,
+            1 => Value::Bool(self.rng.below(2) == 1),
+            2 => {
+                let value = (self.rng.next_u64() >> 11) as i64;
+                let value = if self.rng.below(2) == 1 {
+                    -value
+                } else {
+                    value
+                };
+                self.spend(20);
+                value.into()
+            }
+            3 => {
+                let whole = self.rng.below(1_000_000) as f64;
+                let eighths = (1 + self.rng.below(7)) as f64;
+                let value = whole + eighths / 8.0;
+                self.spend(20);
+                (if self.rng.below(2) == 1 {
+                    -value
+                } else {
+                    value
+                })
+                .into()
+            }
+            4 => Value::String(self.string(24)),
+            5 => Value::Array(self.array(depth - 1)),
+            _ => Value::Object(self.object(depth - 1)),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Vec<Value> {
+        let len = self.rng.below(8);
+        let mut items = Vec::with_capacity(len);
+        while items.len() < len && self.budget > 0 {
+            self.spend(1);
+            items.push(self.value(depth));
+        }
+        items
+    }
+
+    fn object(&mut self, depth: usize) -> Map<String, Value> {
+        let len = 1 + self.rng.below(8);
+        let mut members = Map::new();
+        while members.len() < len && self.budget > 0 {
+            let key = self.string(32);
+            self.spend(2);
+            let value = self.value(depth);
+            members.insert(key, value);
+        }
+        members
+    }
+
+    /// A string of up to `max_len` characters, occasionally empty.
+    fn string(&mut self, max_len: usize) -> String {
+        let len = self.rng.below(max_len + 1);
+        let text: String = (0..len)
+            .map(|_| FUZZ_CHARS[self.rng.below(FUZZ_CHARS.len())])
+            .collect();
+        self.spend(Value::from(text.as_str()).to_string().len());
+        text
+    }
+
+    fn spend(&mut self, bytes: usize) {
+        self.budget = self.budget.saturating_sub(bytes);
+    }
+}
+
+/// `value` pretty-printed, cut to `FUZZ_PRINT_LIMIT` bytes.
+fn pretty_truncated(value: &Value) -> String {
+    let text = serde_json::to_string_pretty(value).expect("JSON values always serialize");
+    if text.len() <= FUZZ_PRINT_LIMIT {
+        return text;
+    }
+    let mut end = FUZZ_PRINT_LIMIT;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}… ({} bytes in all)", &text[..end], text.len())
+}
@@ -0,0 +1,38 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+/// Records sessions created during the run so any left behind by failing or
+/// careless tests can be swept afterwards. Clones share the same registry.
+#[derive(Clone, Default)]
+pub struct SessionTracker {
+    /// session ID → name of the test that created it
+    sessions: Arc<Mutex<HashMap<String, String>>>,
+}
+
+impl SessionTracker {
+    /// Remember a newly created session and the test that created it.
+    pub fn record(&self, session_id: &str, test: &str) {
+        self.sessions
+            .lock()
+            .unwrap()
+            .insert(session_id.to_string(), test.to_string());
+    }
+
+    /// Forget a session that is known to be gone.
+    pub fn forget(&self, session_id: &str) {
+        self.sessions.lock().unwrap().remove(session_id);
+    }
+
+    /// Sessions still outstanding, as (session ID, creating test) pairs.
+    pub fn outstanding(&self) -> Vec<(String, String)> {
+        let mut sessions: Vec<_> = self
+            .sessions
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(id, test)| (id.clone(), test.clone()))
+            .collect();
+        sessions.sort_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+        sessions
+    }
+}
@@ -0,0 +1,85 @@
+//! Baseline comparison: which changes since a saved report count as
+//! regressions, and how they affect the outcome.
+
+use steel_tester::report::baseline::{compare, Baseline};
+use steel_tester::report::{GroupReport, Outcome, RunReport, TestResult, TestStatus};
+
+fn result(name: &str, status: TestStatus, duration_ms: u64, attempts: u32) -> TestResult {
+    let mut result = TestResult::not_executed(name, status, "");
+    result.skip_reason = None;
+    result.duration_ms = duration_ms;
+    result.attempts = attempts;
+    result
+}
+
+fn report(tests: Vec<TestResult>) -> RunReport {
+    RunReport::new(
+        chrono::Utc::now(),
+        "http://localhost:8080",
+        1,
+        vec![GroupReport::new("Group", 1, tests)],
+        Vec::new(),
+        false,
+        None,
+    )
+}
+
+/// A baseline as a later run reads it: through the saved JSON.
+fn baseline(tests: Vec<TestResult>) -> Baseline {
+    Baseline::from_json(&report(tests).to_json()).expect("a report parses as a baseline")
+}
+
+#[test]
+fn regressions_are_failures_slowdowns_and_flakiness_in_both_runs() {
+    use TestStatus::*;
+    let before = baseline(vec![
+        result("steady", Passed, 100, 1),
+        result("slower", Passed, 100, 1),
+        result("tiny", Passed, 2, 1),
+        result("breaks", Passed, 100, 1),
+        result("still broken", Failed, 100, 1),
+        result("flaky", Passed, 100, 1),
+        result("was skipped", Skipped, 0, 0),
+        result("gone", Passed, 100, 1),
+    ]);
+    let now = report(vec![
+        result("steady", Passed, 140, 1),
+        result("slower", Passed, 160, 1),
+        result("tiny", Passed, 8, 1),
+        result("breaks", Failed, 100, 1),
+        result("still broken", Failed, 100, 1),
+        result("flaky", Passed, 100, 3),
+        result("was skipped", Failed, 100, 1),
+        result("new", Failed, 100, 1),
+    ]);
+
+    let comparison = compare(&now, &before);
+    assert_eq!(comparison.newly_failing, ["Group / breaks"]);
+    let slower: Vec<_> = comparison
+        .slower
+        .iter()
+        .map(|s| (s.test.as_str(), s.baseline_ms, s.duration_ms))
+        .collect();
+    assert_eq!(slower, [("Group / slower", 100, 160)]);
+    assert_eq!(comparison.newly_flaky, ["Group / flaky"]);
+    assert_eq!(comparison.added, ["Group / new"]);
+    assert_eq!(comparison.removed, ["Group / gone"]);
+    assert_eq!(comparison.regression_count(), 3);
+}
+
+#[test]
+fn regressions_only_fail_a_passing_run_when_asked() {
+    let before = baseline(vec![result("test", TestStatus::Passed, 100, 1)]);
+    let slower = || report(vec![result("test", TestStatus::Passed, 300, 1)]);
+
+    let mut report = slower();
+    report.set_regressions(compare(&report, &before), false);
+    assert_eq!(report.outcome, Outcome::Passed);
+    assert!(report.regressions.as_ref().unwrap().has_regressions());
+
+    let mut report = slower();
+    report.set_regressions(compare(&report, &before), true);
+    assert_eq!(report.outcome, Outcome::Regressed);
+    assert_eq!(report.exit_code, 6);
+    assert!(report.to_json().contains("\"regressions\""));
+}
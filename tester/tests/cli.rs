@@ -0,0 +1,175 @@
+//! `STEEL_*` environment variables behind the CLI's flags, checked through
+//! `--print-config` and `list` so no orchestrator is needed.
+
+use std::process::{Command, Output};
+
+/// Run the binary with only `env` set, outside any directory holding a
+/// `steel-tester.toml`.
+fn steel_tester(args: &[&str], env: &[(&str, &str)]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_steel-tester"))
+        .args(args)
+        .env_clear()
+        .envs(env.iter().copied())
+        .current_dir(std::env::temp_dir())
+        .output()
+        .expect("steel-tester runs")
+}
+
+/// The resolved settings `--print-config` prints.
+fn resolved(args: &[&str], env: &[(&str, &str)]) -> String {
+    let out = steel_tester(args, env);
+    assert!(
+        out.status.success(),
+        "{}",
+        String::from_utf8_lossy(&out.stderr)
+    );
+    String::from_utf8(out.stdout).expect("settings are UTF-8")
+}
+
+/// The usage error the binary exits with.
+fn usage_error(args: &[&str], env: &[(&str, &str)]) -> String {
+    let out = steel_tester(args, env);
+    assert_eq!(out.status.code(), Some(2));
+    String::from_utf8(out.stderr).expect("errors are UTF-8")
+}
+
+#[test]
+fn environment_variables_set_flags() {
+    let settings = resolved(
+        &["run", "--print-config"],
+        &[
+            ("STEEL_URL", "http://staging:8080"),
+            ("STEEL_REQUEST_TIMEOUT", "9"),
+            ("STEEL_FILTER", "ttl"),
+        ],
+    );
+    assert!(
+        settings.contains("url = \"http://staging:8080\"\n"),
+        "{settings}"
+    );
+    assert!(settings.contains("request_timeout = 9\n"), "{settings}");
+    assert!(settings.contains("filter = \"ttl\"\n"), "{settings}");
+}
+
+#[test]
+fn an_explicit_flag_beats_the_environment() {
+    let settings = resolved(
+        &["run", "--print-config", "--request-timeout", "3"],
+        &[("STEEL_REQUEST_TIMEOUT", "9")],
+    );
+    assert!(settings.contains("request_timeout = 3\n"), "{settings}");
+}
+
+#[test]
+fn subcommand_flags_are_prefixed_with_the_subcommand() {
+    let settings = resolved(
+        &["bench", "--print-config"],
+        &[("STEEL_BENCH_ITERATIONS", "7")],
+    );
+    assert!(settings.contains("[bench]\niterations = 7\n"), "{settings}");
+}
+
+#[test]
+fn boolean_variables_accept_common_spellings() {
+    for (value, expected) in [
+        ("1", true),
+        ("true", true),
+        ("on", true),
+        ("0", false),
+        ("no", false),
+    ] {
+        let settings = resolved(&["run", "--print-config"], &[("STEEL_FAIL_FAST", value)]);
+        assert!(
+            settings.contains(&format!("fail_fast = {expected}\n")),
+            "STEEL_FAIL_FAST={value}: {settings}"
+        );
+    }
+}
+
+#[test]
+fn invalid_values_name_the_variable() {
+    let error = usage_error(&["run"], &[("STEEL_TTL", "soon")]);
+    assert!(
+        error.contains("invalid value 'soon' in STEEL_TTL"),
+        "{error}"
+    );
+
+    let error = usage_error(&["run"], &[("STEEL_FAIL_FAST", "maybe")]);
+    assert!(error.contains("STEEL_FAIL_FAST"), "{error}");
+
+    let error = usage_error(&["list"], &[("STEEL_OUTPUT", "xml")]);
+    assert!(error.contains("STEEL_OUTPUT"), "{error}");
+    assert!(error.contains("expected one of text, json"), "{error}");
+}
+
+#[test]
+fn logs_go_to_stderr_and_the_log_file() {
+    let log = std::env::temp_dir().join(format!("steel-tester-cli-{}.log", std::process::id()));
+    let log_arg = log.to_string_lossy().into_owned();
+    // Nothing listens on port 1, so the preflight GET /health fails fast.
+    let out = steel_tester(
+        &[
+            "run",
+            "--url",
+            "http://127.0.0.1:1",
+            "--output",
+            "json",
+            "-vv",
+            "--log-file",
+            &log_arg,
+        ],
+        &[],
+    );
+    let logged = std::fs::read_to_string(&log).expect("log file is written");
+    let _ = std::fs::remove_file(&log);
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let report: serde_json::Value = serde_json::from_str(&stdout).expect("stdout is the report");
+    assert_eq!(report["outcome"], "unreachable");
+    let stderr = String::from_utf8_lossy(&out.stderr);
+    assert!(stderr.contains("request failed"), "{stderr}");
+    assert!(logged.contains("method=GET path=/health"), "{logged}");
+    assert!(!logged.contains('\x1b'), "{logged}");
+}
+
+#[test]
+fn list_shows_a_stress_test_per_concurrency() {
+    let out = steel_tester(
+        &[
+            "list",
+            "--group",
+            "Concurrency",
+            "--filter",
+            "stress",
+            "--stress-concurrency",
+            "10",
+            "--stress-concurrency",
+            "100",
+        ],
+        &[],
+    );
+    assert!(out.status.success());
+    assert_eq!(
+        String::from_utf8_lossy(&out.stdout),
+        "Concurrency / Stress: 10 simultaneous creates\n\
+         Concurrency / Stress: 100 simultaneous creates\n"
+    );
+}
+
+#[test]
+fn a_repeated_stress_concurrency_is_a_usage_error() {
+    let stderr = usage_error(
+        &[
+            "list",
+            "--stress-concurrency",
+            "10",
+            "--stress-concurrency",
+            "10",
+        ],
+        &[],
+    );
+    assert!(
+        stderr.contains("--stress-concurrency 10 is given more than once"),
+        "{stderr}"
+    );
+}
@@ -0,0 +1,128 @@
+//! Config file precedence: a flag beats its environment variable, which
+//! beats the file, which beats the built-in default.
+
+use clap::{Arg, ArgAction, Command};
+use std::io::Write;
+use steel_tester::config::{self, ConfigFile};
+
+fn command() -> Command {
+    Command::new("steel-tester")
+        .arg(
+            Arg::new("url")
+                .long("url")
+                .global(true)
+                .default_value("http://localhost:8080"),
+        )
+        .arg(
+            Arg::new("token")
+                .long("token")
+                .global(true)
+                .env("STEEL_CONFIG_TEST_TOKEN")
+                .hide_env_values(true),
+        )
+        .arg(
+            Arg::new("skip_groups")
+                .long("skip-group")
+                .action(ArgAction::Append),
+        )
+        .subcommand(Command::new("bench").arg(Arg::new("iterations").long("iterations")))
+}
+
+fn file(contents: &str) -> ConfigFile {
+    let path = std::env::temp_dir().join(format!(
+        "steel-tester-config-{}-{}.toml",
+        std::process::id(),
+        steel_tester::tests::util::unique("test")
+    ));
+    std::fs::File::create(&path)
+        .and_then(|mut f| f.write_all(contents.as_bytes()))
+        .expect("config file is written");
+    let file = ConfigFile::load(&path).expect("config file parses");
+    let _ = std::fs::remove_file(&path);
+    file
+}
+
+fn value(matches: &clap::ArgMatches, id: &str) -> Option<String> {
+    matches.get_one::<String>(id).cloned()
+}
+
+#[test]
+fn file_values_replace_defaults_but_not_flags() {
+    let file = file(
+        r#"
+        url = "http://staging:8080"
+        skip-group = ["Soak", "Chaos"]
+        [bench]
+        iterations = 500
+        "#,
+    );
+    let (cmd, warnings) = file.apply(command());
+    assert!(warnings.is_empty(), "{warnings:?}");
+
+    let matches = cmd.clone().get_matches_from(["steel-tester"]);
+    assert_eq!(
+        value(&matches, "url").as_deref(),
+        Some("http://staging:8080")
+    );
+    let skipped: Vec<&String> = matches.get_many("skip_groups").unwrap().collect();
+    assert_eq!(skipped, ["Soak", "Chaos"]);
+
+    let matches = cmd.get_matches_from(["steel-tester", "--url", "http://prod", "bench"]);
+    assert_eq!(value(&matches, "url").as_deref(), Some("http://prod"));
+    let (_, bench) = matches.subcommand().unwrap();
+    assert_eq!(value(bench, "iterations").as_deref(), Some("500"));
+}
+
+#[test]
+fn environment_beats_the_file() {
+    let file = file(r#"token = "from-file""#);
+    let (cmd, _) = file.apply(command());
+    assert_eq!(
+        value(&cmd.get_matches_from(["steel-tester"]), "token").as_deref(),
+        Some("from-file")
+    );
+
+    // clap reads the variable when the argument is defined.
+    std::env::set_var("STEEL_CONFIG_TEST_TOKEN", "from-env");
+    let (cmd, _) = file.apply(command());
+    std::env::remove_var("STEEL_CONFIG_TEST_TOKEN");
+    let matches = cmd.get_matches_from(["steel-tester"]);
+    assert_eq!(value(&matches, "token").as_deref(), Some("from-env"));
+}
+
+#[test]
+fn unknown_keys_and_tables_are_warned_about() {
+    let (_, warnings) = file(
+        r#"
+        tokn = "typo"
+        url = ["a", "b"]
+        [bench]
+        iteration = 5
+        [nightly]
+        url = "x"
+        "#,
+    )
+    .apply(command());
+    assert_eq!(
+        warnings,
+        [
+            "unknown key `bench.iteration`",
+            "unknown table [nightly]",
+            "unknown key `tokn`",
+            "`url` takes a single value",
+        ]
+    );
+}
+
+#[test]
+fn resolved_settings_redact_secrets() {
+    let (mut cmd, _) = file(r#"token = "s3cret""#).apply(command());
+    let matches = cmd
+        .try_get_matches_from_mut(["steel-tester", "bench", "--iterations", "20"])
+        .unwrap();
+    let resolved = config::resolved(&cmd, &matches).to_string();
+
+    assert!(resolved.contains(r#"token = "<redacted>""#), "{resolved}");
+    assert!(!resolved.contains("s3cret"), "{resolved}");
+    assert!(resolved.contains("[bench]\niterations = 20"), "{resolved}");
+}
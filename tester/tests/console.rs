@@ -0,0 +1,71 @@
+//! How the human-readable output is styled: when it is colored, and what
+//! `--ascii` turns it into.
+
+use std::ffi::OsStr;
+use steel_tester::console::{asciify, output_style, ColorChoice, Style};
+
+fn color(choice: ColorChoice, no_color: Option<&str>, terminal: bool) -> bool {
+    output_style(choice, false, no_color.map(OsStr::new), terminal).color
+}
+
+#[test]
+fn auto_colors_only_a_terminal() {
+    assert!(color(ColorChoice::Auto, None, true));
+    assert!(!color(ColorChoice::Auto, None, false));
+}
+
+#[test]
+fn no_color_disables_auto_unless_empty() {
+    assert!(!color(ColorChoice::Auto, Some("1"), true));
+    assert!(!color(ColorChoice::Auto, Some("false"), true));
+    assert!(color(ColorChoice::Auto, Some(""), true));
+}
+
+#[test]
+fn an_explicit_choice_beats_the_environment_and_terminal() {
+    for (no_color, terminal) in [(None, false), (Some("1"), true), (Some("1"), false)] {
+        assert!(color(ColorChoice::Always, no_color, terminal));
+    }
+    for (no_color, terminal) in [(None, true), (Some(""), true)] {
+        assert!(!color(ColorChoice::Never, no_color, terminal));
+    }
+}
+
+#[test]
+fn ascii_is_independent_of_color() {
+    assert_eq!(
+        output_style(ColorChoice::Never, true, None, true),
+        Style {
+            color: false,
+            ascii: true,
+        }
+    );
+    assert_eq!(
+        output_style(ColorChoice::Auto, false, None, true),
+        Style {
+            color: true,
+            ascii: false,
+        }
+    );
+}
+
+#[test]
+fn asciify_replaces_symbols_and_drops_emoji() {
+    assert_eq!(asciify("✓ Create session (2ms)"), "+ Create session (2ms)");
+    assert_eq!(
+        asciify("✗ Get session: HTTP 500"),
+        "x Get session: HTTP 500"
+    );
+    assert_eq!(
+        asciify("⚠ Health: setup failed — skipping"),
+        "! Health: setup failed - skipping"
+    );
+    assert_eq!(asciify("━━━"), "===");
+    assert_eq!(
+        asciify("🧪 ORCHESTRATOR TEST SUITE"),
+        "ORCHESTRATOR TEST SUITE"
+    );
+    assert_eq!(asciify("🏳️‍🌈 flag"), "flag");
+    assert_eq!(asciify("unicode: 中文"), "unicode: ??");
+    assert_eq!(asciify("plain"), "plain");
+}
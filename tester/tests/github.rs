@@ -0,0 +1,65 @@
+//! GitHub Actions annotations: the workflow-command escaping, and which
+//! results get one.
+
+use steel_tester::report::github::{annotations, escape_data, escape_property};
+use steel_tester::report::{GroupReport, RunReport, TestResult, TestStatus};
+
+#[test]
+fn messages_escape_percent_and_line_breaks() {
+    assert_eq!(escape_data("100% done"), "100%25 done");
+    assert_eq!(
+        escape_data("expected 200\r\ngot 500"),
+        "expected 200%0D%0Agot 500"
+    );
+    // A message runs to the end of the line, so `::` and `,` need no escape.
+    assert_eq!(escape_data("GET /a::b, twice"), "GET /a::b, twice");
+    // Already-escaped text must not be decoded on the way back.
+    assert_eq!(escape_data("%0A"), "%250A");
+}
+
+#[test]
+fn properties_also_escape_colons_and_commas() {
+    assert_eq!(
+        escape_property("CRUD / GET :id, twice"),
+        "CRUD / GET %3Aid%2C twice"
+    );
+    assert_eq!(escape_property("a::b"), "a%3A%3Ab");
+    assert_eq!(escape_property("50%\nlater"), "50%25%0Alater");
+}
+
+fn result(name: &str, status: TestStatus, attempts: u32, error: Option<&str>) -> TestResult {
+    let mut result = TestResult::not_executed(name, status, "");
+    result.skip_reason = None;
+    result.attempts = attempts;
+    result.error = error.map(str::to_string);
+    result
+}
+
+#[test]
+fn failures_are_errors_and_flaky_passes_warnings() {
+    let tests = vec![
+        result("passes", TestStatus::Passed, 1, None),
+        result("fails", TestStatus::Failed, 1, Some("HTTP 500\nbody: {}")),
+        result("retried", TestStatus::Passed, 3, None),
+        result("xpass", TestStatus::UnexpectedPass, 1, Some("passed")),
+        result("skipped", TestStatus::Skipped, 0, None),
+    ];
+    let report = RunReport::new(
+        chrono::Utc::now(),
+        "http://localhost:8080",
+        1,
+        vec![GroupReport::new("CRUD: basics", 1, tests)],
+        Vec::new(),
+        false,
+        None,
+    );
+
+    assert_eq!(
+        annotations(&report),
+        [
+            "::error title=CRUD%3A basics / fails::HTTP 500%0Abody: {}",
+            "::warning title=CRUD%3A basics / retried::flaky: passed on attempt 3",
+            "::error title=CRUD%3A basics / xpass::passed",
+        ]
+    );
+}
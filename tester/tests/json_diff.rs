@@ -0,0 +1,100 @@
+//! `util::json_diff` and `assert_json_eq`: where a difference is reported,
+//! how, and when floats count as equal.
+
+use serde_json::json;
+use steel_tester::tests::util::{assert_json_eq, json_diff, json_diff_within};
+
+#[test]
+fn equal_documents_have_no_diff() {
+    let doc = json!({"user": "a", "tags": [1, [2, {"x": null}]], "n": 0.5});
+    assert_eq!(json_diff(&doc, &doc.clone()), None);
+    assert_eq!(assert_json_eq(&doc, &doc.clone()), Ok(()));
+}
+
+#[test]
+fn nested_arrays_report_the_element_path() {
+    let expected = json!({"grid": [[1, 2], [3, [4, 5]]]});
+    let actual = json!({"grid": [[1, 2], [3, [4, 6]]]});
+    assert_eq!(
+        json_diff(&expected, &actual).as_deref(),
+        Some("at $.grid[1][1][1]: expected 5, got 6")
+    );
+
+    let shorter = json!({"grid": [[1, 2], [3]]});
+    assert_eq!(
+        json_diff(&expected, &shorter).as_deref(),
+        Some("$.grid[1] has 1 elements, expected 2")
+    );
+}
+
+#[test]
+fn missing_and_added_keys_are_told_apart() {
+    let expected = json!({"user": "a", "meta": {"k": 1}});
+    assert_eq!(
+        json_diff(&expected, &json!({"user": "a", "meta": {}})).as_deref(),
+        Some("$.meta.k is missing")
+    );
+    assert_eq!(
+        json_diff(
+            &expected,
+            &json!({"user": "a", "meta": {"k": 1, "extra": true}})
+        )
+        .as_deref(),
+        Some("$.meta.extra was added")
+    );
+    assert_eq!(
+        assert_json_eq(&expected, &json!({"meta": {"k": 1}})),
+        Err("$.user is missing".to_string())
+    );
+}
+
+#[test]
+fn type_mismatches_name_both_types() {
+    let cases = [
+        (
+            json!({"n": 1}),
+            json!({"n": "1"}),
+            "at $.n: expected a number 1, got a string \"1\"",
+        ),
+        (
+            json!({"n": [1]}),
+            json!({"n": {"0": 1}}),
+            "at $.n: expected an array [1], got an object {\"0\":1}",
+        ),
+        (
+            json!({"n": null}),
+            json!({"n": false}),
+            "at $.n: expected null, got a boolean false",
+        ),
+    ];
+    for (expected, actual, diff) in cases {
+        assert_eq!(json_diff(&expected, &actual).as_deref(), Some(diff));
+    }
+}
+
+#[test]
+fn odd_keys_are_quoted_and_long_values_truncated() {
+    let long = "x".repeat(100);
+    let diff = json_diff(&json!({"a.b": long}), &json!({"a.b": "y"})).unwrap();
+    assert_eq!(
+        diff,
+        format!("at $[\"a.b\"]: expected \"{}…, got \"y\"", "x".repeat(59))
+    );
+}
+
+#[test]
+fn floats_match_within_a_relative_tolerance() {
+    let sum = json!({"f": 0.1 + 0.2});
+    let exact = json!({"f": 0.3});
+    assert_eq!(
+        json_diff(&sum, &exact).as_deref(),
+        Some("at $.f: expected 0.30000000000000004, got 0.3")
+    );
+    assert_eq!(json_diff_within(&sum, &exact, 1e-12), None);
+    assert_eq!(
+        json_diff_within(&json!({"f": 1.0}), &json!({"f": 1.1}), 1e-12).as_deref(),
+        Some("at $.f: expected 1.0, got 1.1, beyond the 1e-12 tolerance")
+    );
+    // Integers are never loosened, however close.
+    assert!(json_diff_within(&json!(u64::MAX), &json!(u64::MAX - 1), 1e-3).is_some());
+}
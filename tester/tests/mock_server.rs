@@ -0,0 +1,184 @@
+//! Runs the whole suite against the in-memory mock orchestrator, so changes
+//! to the tester can be checked without a live one.
+
+use std::time::Duration;
+use steel_tester::capabilities::Capabilities;
+use steel_tester::client::OrchestratorClient;
+use steel_tester::interrupt::Interrupt;
+use steel_tester::mock_server::{MockConfig, MockServer};
+use steel_tester::report::{Outcome, TestStatus};
+use steel_tester::runner::{RunnerConfig, TestRunner};
+use steel_tester::tests;
+
+/// Short enough that the TTL tests finish in seconds.
+const TTL: Duration = Duration::from_secs(2);
+const SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Mock workers; the bulk TTL test needs a session on each of them.
+const POOL: usize = 10;
+
+/// Groups that must not be skipped against the mock, which implements what
+/// they need.
+const EXERCISED: &[&str] = &[
+    "CRUD Operations",
+    "Validation",
+    "Protocol",
+    "Error Responses",
+    "Concurrency",
+    "TTL Expiration",
+    "Recovery",
+    tests::capacity::GROUP,
+];
+
+/// Every group registered as the CLI registers it with default flags, apart
+/// from the TTL and bulk expiry size matching the mock's. Timeouts is left
+/// out: it probes a black-holed address, not the orchestrator.
+fn register_all(runner: &mut TestRunner, seed: u64) {
+    runner.add_group(
+        "CRUD Operations",
+        tests::crud::tests(&tests::crud::CrudSettings::default()),
+    );
+    runner.add_group(
+        "Validation",
+        tests::validation::tests(
+            10 * 1024 * 1024,
+            16 * 1024,
+            tests::validation::PayloadPolicy::ObjectOnly,
+            seed,
+        ),
+    );
+    runner.add_group(
+        "Protocol",
+        tests::protocol::tests(false, Duration::from_secs(2)),
+    );
+    runner.add_group("Error Responses", tests::errors::tests(None));
+    runner.add_group("Metrics", tests::metrics::tests());
+    runner.add_group_with_hooks("Health", tests::health::tests(), tests::health::hooks());
+    runner.add_group("Concurrency", tests::concurrent::tests(&[50]));
+    runner.add_group(
+        "TTL Expiration",
+        tests::ttl::tests(&tests::ttl::TtlSettings {
+            ttl: TTL,
+            sweep_interval: SWEEP_INTERVAL,
+            mode: tests::ttl::TtlMode::Sliding,
+            bulk_sessions: POOL,
+            expired_status: None,
+        }),
+    );
+    runner.add_group(
+        "Recovery",
+        tests::recovery::tests(&tests::recovery::RecoverySettings {
+            crash_workers: 3,
+            timeout: Duration::from_secs(15),
+            slo: None,
+        }),
+    );
+    runner.add_group_with_hooks(
+        "Restart",
+        tests::restart::tests(&tests::restart::RestartSettings {
+            mode: tests::restart::PersistenceMode::Drop,
+            timeout: Duration::from_secs(15),
+        }),
+        tests::restart::hooks(),
+    );
+    runner.add_group_with_hooks(
+        "Shutdown",
+        tests::shutdown::tests(&tests::shutdown::ShutdownSettings {
+            pool_size: None,
+            timeout: Duration::from_secs(15),
+        }),
+        tests::shutdown::hooks(),
+    );
+    runner.add_group_with_hooks(
+        tests::capacity::GROUP,
+        tests::capacity::tests(None, 200),
+        tests::capacity::hooks(),
+    );
+    runner.add_group(
+        "Soak",
+        tests::soak::tests(&tests::soak::SoakSettings {
+            duration: None,
+            rps: 5,
+        }),
+    );
+    runner.add_group("Auth", tests::auth::tests(false));
+    runner.add_group(
+        "Rate Limiting",
+        tests::rate_limit::tests(&tests::rate_limit::RateLimitSettings { rps: None }),
+    );
+    runner.add_group("CORS", tests::cors::tests(None));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn full_suite_passes_against_the_mock() {
+    let mock = MockServer::start(MockConfig {
+        max_workers: POOL,
+        ttl: TTL,
+        sweep_interval: SWEEP_INTERVAL,
+        ..MockConfig::default()
+    })
+    .await
+    .expect("mock server starts");
+    let client = OrchestratorClient::new(&mock.url());
+
+    let config = RunnerConfig {
+        strict_leaks: true,
+        ..RunnerConfig::default()
+    };
+    let seed = config.seed;
+    let mut runner = TestRunner::new(config);
+    register_all(&mut runner, seed);
+    // The mock has no /metrics or listing, as the CLI's probe would find.
+    runner.set_capabilities(Capabilities::probe(&client).await);
+    let report = runner
+        .run(
+            &client.with_stats(runner.stats().clone()),
+            &Interrupt::never(),
+        )
+        .await;
+    // Connections the pool opened and never used would hold up the graceful
+    // shutdown until they time out, so close them first.
+    drop(client);
+    mock.stop().await;
+
+    let failures: Vec<String> = report
+        .groups
+        .iter()
+        .flat_map(|group| group.tests.iter().map(move |test| (group, test)))
+        .filter(|(_, test)| test.status.is_failure())
+        .map(|(group, test)| {
+            format!(
+                "{} / {}: {}",
+                group.name,
+                test.name,
+                test.error.as_deref().unwrap_or("unexpected pass")
+            )
+        })
+        .collect();
+    assert!(
+        failures.is_empty(),
+        "failed against the mock (seed {seed}):\n{}",
+        failures.join("\n")
+    );
+    assert!(
+        report.leaked_sessions.is_empty(),
+        "sessions leaked: {:?}",
+        report.leaked_sessions
+    );
+    assert_eq!(report.outcome, Outcome::Passed);
+
+    for name in EXERCISED {
+        let group = report
+            .groups
+            .iter()
+            .find(|group| group.name == *name)
+            .unwrap_or_else(|| panic!("group {name} missing from the report"));
+        assert!(
+            group
+                .tests
+                .iter()
+                .any(|test| test.status == TestStatus::Passed),
+            "group {name} ran no tests against the mock"
+        );
+    }
+}
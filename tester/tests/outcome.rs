@@ -0,0 +1,164 @@
+//! Exit codes: what each outcome maps to, the `--help` table that documents
+//! them, and which outcome a run gets when several apply.
+
+use steel_tester::report::{
+    GroupReport, LeakedSession, Outcome, RunReport, RunStop, TestResult, TestStatus,
+    EXIT_CODES_HELP,
+};
+
+const EXIT_CODES: [(Outcome, i32); 8] = [
+    (Outcome::Passed, 0),
+    (Outcome::TestsFailed, 1),
+    (Outcome::Unreachable, 2),
+    (Outcome::LeakedResources, 3),
+    (Outcome::BudgetExceeded, 4),
+    (Outcome::SloExceeded, 5),
+    (Outcome::Regressed, 6),
+    (Outcome::Interrupted, 130),
+];
+
+fn result(name: &str, status: TestStatus) -> TestResult {
+    let mut result = TestResult::not_executed(name, status, "");
+    result.skip_reason = None;
+    result.attempts = 1;
+    result
+}
+
+/// What `RunReport::new` ranks: one group's statuses, leaks, a teardown
+/// warning, `--strict-leaks` and why the run stopped.
+struct Run {
+    statuses: Vec<TestStatus>,
+    leaked: Vec<LeakedSession>,
+    warning: bool,
+    strict_leaks: bool,
+    stopped: Option<RunStop>,
+}
+
+impl Run {
+    /// A run that finished cleanly with these statuses.
+    fn of(statuses: &[TestStatus]) -> Self {
+        Self {
+            statuses: statuses.to_vec(),
+            leaked: Vec::new(),
+            warning: false,
+            strict_leaks: false,
+            stopped: None,
+        }
+    }
+
+    fn leaking(mut self, swept: bool) -> Self {
+        self.leaked
+            .push(LeakedSession::new("s-1", "Group / test 0", swept));
+        self
+    }
+
+    fn outcome(self) -> Outcome {
+        let tests = self
+            .statuses
+            .iter()
+            .enumerate()
+            .map(|(i, status)| result(&format!("test {i}"), *status))
+            .collect();
+        let mut group = GroupReport::new("Group", 1, tests);
+        if self.warning {
+            group.warnings.push("teardown failed: boom".to_string());
+        }
+        let report = RunReport::new(
+            chrono::Utc::now(),
+            "http://localhost:8080",
+            1,
+            vec![group],
+            self.leaked,
+            self.strict_leaks,
+            self.stopped,
+        );
+        assert_eq!(report.exit_code, report.outcome.exit_code());
+        report.outcome
+    }
+}
+
+#[test]
+fn every_outcome_has_its_exit_code() {
+    for (outcome, code) in EXIT_CODES {
+        assert_eq!(outcome.exit_code(), code, "{outcome:?}");
+    }
+}
+
+#[test]
+fn the_help_table_lists_exactly_the_exit_codes() {
+    let documented: Vec<i32> = EXIT_CODES_HELP
+        .lines()
+        .skip(1)
+        .map(|line| {
+            let code = line.split_whitespace().next().unwrap_or_default();
+            code.parse()
+                .unwrap_or_else(|_| panic!("not an exit code line: {line:?}"))
+        })
+        .collect();
+    let mut codes: Vec<i32> = EXIT_CODES.iter().map(|(o, _)| o.exit_code()).collect();
+    codes.sort_unstable();
+    assert_eq!(documented, codes);
+}
+
+#[test]
+fn an_unreachable_orchestrator_exits_2() {
+    let report = RunReport::unreachable(chrono::Utc::now(), "http://x", 1, "refused".into());
+    assert_eq!(
+        (report.outcome, report.exit_code),
+        (Outcome::Unreachable, 2)
+    );
+}
+
+#[test]
+fn a_clean_run_passes() {
+    use TestStatus::*;
+    let outcome = Run::of(&[Passed, Skipped, Unsupported, ExpectedFailure]).outcome();
+    assert_eq!(outcome, Outcome::Passed);
+}
+
+#[test]
+fn a_stop_beats_a_failure() {
+    use TestStatus::*;
+    for (stop, outcome) in [
+        (RunStop::Interrupted, Outcome::Interrupted),
+        (RunStop::BudgetExceeded, Outcome::BudgetExceeded),
+    ] {
+        let run = Run {
+            stopped: Some(stop),
+            ..Run::of(&[Failed, NotRun]).leaking(false)
+        };
+        assert_eq!(run.outcome(), outcome);
+    }
+}
+
+#[test]
+fn a_failure_or_unexpected_pass_beats_a_leak() {
+    use TestStatus::*;
+    for status in [Failed, UnexpectedPass] {
+        let run = Run {
+            warning: true,
+            strict_leaks: true,
+            ..Run::of(&[Passed, status]).leaking(false)
+        };
+        assert_eq!(run.outcome(), Outcome::TestsFailed, "{status:?}");
+    }
+}
+
+#[test]
+fn unswept_leaks_teardown_warnings_and_strict_leaks_are_leaked_resources() {
+    use TestStatus::*;
+    let unswept = Run::of(&[Passed]).leaking(false);
+    let warned = Run {
+        warning: true,
+        ..Run::of(&[Passed])
+    };
+    let strict = Run {
+        strict_leaks: true,
+        ..Run::of(&[Passed]).leaking(true)
+    };
+    for run in [unswept, warned, strict] {
+        assert_eq!(run.outcome(), Outcome::LeakedResources);
+    }
+    // A leak the sweep cleaned up only counts under --strict-leaks.
+    assert_eq!(Run::of(&[Passed]).leaking(true).outcome(), Outcome::Passed);
+}
@@ -0,0 +1,182 @@
+//! Client retries: the backoff schedule, which requests and failures are
+//! retried, and the count a run reports, against a scripted local server.
+
+use reqwest::header::{HeaderMap, HeaderValue, RETRY_AFTER};
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+use steel_tester::client::{ClientConfig, OrchestratorClient};
+use steel_tester::retry::{self, RetryPolicy};
+use steel_tester::stats::Stats;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+/// Retries that don't slow the tests down.
+fn quick(max_retries: u32) -> RetryPolicy {
+    RetryPolicy {
+        max_retries,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(4),
+    }
+}
+
+fn client(url: &str, retry: RetryPolicy) -> OrchestratorClient {
+    let config = ClientConfig {
+        retry,
+        ..ClientConfig::default()
+    };
+    OrchestratorClient::with_config(url, &config)
+}
+
+/// Serve one connection per status in `statuses`, in order, closing each.
+async fn scripted(statuses: &'static [u16]) -> String {
+    scripted_with(statuses, "").await
+}
+
+/// `scripted`, with `headers` (each ending in CRLF) on every response.
+async fn scripted_with(statuses: &'static [u16], headers: &'static str) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        for status in statuses {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut buf = [0; 4096];
+            let _ = stream.read(&mut buf).await;
+            let reply = format!(
+                "HTTP/1.1 {status} Scripted\r\n{headers}Content-Length: 2\r\nConnection: close\r\n\r\nok"
+            );
+            let _ = stream.write_all(reply.as_bytes()).await;
+        }
+    });
+    url
+}
+
+#[test]
+fn backoff_doubles_up_to_the_cap_and_jitter_keeps_half() {
+    let policy = RetryPolicy {
+        max_retries: 6,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(1),
+    };
+    let schedule: Vec<_> = (0..6)
+        .map(|retry| policy.backoff(retry).as_millis())
+        .collect();
+    assert_eq!(schedule, [100, 200, 400, 800, 1000, 1000]);
+    assert_eq!(policy.backoff(u32::MAX), Duration::from_secs(1));
+
+    assert_eq!(policy.delay(2, 0.0), Duration::from_millis(200));
+    assert_eq!(policy.delay(2, 0.5), Duration::from_millis(300));
+    assert!(policy.delay(2, 0.999) < Duration::from_millis(400));
+}
+
+#[test]
+fn only_repeatable_requests_and_transient_failures_are_retried() {
+    assert!(retry::is_repeatable(&Method::GET, "/health", false));
+    assert!(retry::is_repeatable(&Method::DELETE, "/sessions/a", false));
+    assert!(retry::is_repeatable(&Method::POST, "/sessions", true));
+    assert!(!retry::is_repeatable(&Method::POST, "/sessions", false));
+    assert!(!retry::is_repeatable(&Method::POST, "/debug/restart", true));
+    assert!(!retry::is_repeatable(&Method::PUT, "/sessions/a", false));
+
+    for status in [502, 503, 504] {
+        assert!(retry::is_transient_status(
+            StatusCode::from_u16(status).unwrap()
+        ));
+    }
+    for status in [200, 404, 429, 500] {
+        assert!(!retry::is_transient_status(
+            StatusCode::from_u16(status).unwrap()
+        ));
+    }
+}
+
+#[tokio::test]
+async fn gateway_errors_are_retried_and_counted() {
+    let url = scripted(&[502, 503, 200]).await;
+    let client = client(&url, quick(3));
+    assert_eq!(client.health().await.unwrap(), "ok");
+    assert_eq!(client.retried_requests(), 2);
+}
+
+#[tokio::test]
+async fn every_attempt_lands_in_the_injected_stats() {
+    let url = scripted(&[502, 503, 200]).await;
+    let stats = Stats::default();
+    let client = client(&url, quick(3)).with_stats(stats.clone());
+    assert_eq!(client.health().await.unwrap(), "ok");
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.len(), 1);
+    let health = &snapshot[0];
+    assert_eq!(health.endpoint, "GET /health");
+    assert_eq!(health.requests, 3);
+    assert_eq!(health.server_errors, 2);
+    assert_eq!(health.retries, 2);
+    assert_eq!(health.bytes_received, 6);
+    assert_eq!(client.retried_requests(), 2);
+}
+
+#[tokio::test]
+async fn retries_give_up_with_the_last_response() {
+    let url = scripted(&[502, 502, 502]).await;
+    let client = client(&url, quick(2));
+    let resp = client
+        .request_raw(Method::GET, "/health", None)
+        .await
+        .unwrap();
+    assert_eq!(resp.status, StatusCode::BAD_GATEWAY);
+    assert_eq!(client.retried_requests(), 2);
+}
+
+#[tokio::test]
+async fn creates_without_a_key_are_sent_once() {
+    let url = scripted(&[502, 201]).await;
+    let client = client(&url, quick(3));
+    let err = client
+        .create_session(serde_json::json!({}))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().starts_with("HTTP 502"), "{err}");
+    assert_eq!(client.retried_requests(), 0);
+}
+
+#[tokio::test]
+async fn refused_connections_are_retried() {
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    drop(listener);
+    let client = client(&url, quick(2));
+    assert!(client.health().await.is_err());
+    assert_eq!(client.retried_requests(), 2);
+}
+
+#[test]
+fn retry_after_is_delay_seconds_or_a_date() {
+    let parse = |value: &str| {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(value).unwrap());
+        retry::retry_after(&headers)
+    };
+    assert_eq!(parse("7"), Some(Duration::from_secs(7)));
+    assert_eq!(parse(" 0 "), Some(Duration::ZERO));
+    assert_eq!(parse("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+    let later = (chrono::Utc::now() + chrono::Duration::seconds(30)).to_rfc2822();
+    let wait = parse(&later).unwrap();
+    assert!(wait > Duration::from_secs(25) && wait <= Duration::from_secs(30));
+    assert_eq!(parse("soon"), None);
+    assert_eq!(parse("-1"), None);
+    assert_eq!(retry::retry_after(&HeaderMap::new()), None);
+}
+
+#[tokio::test]
+async fn rate_limited_errors_carry_retry_after() {
+    let url = scripted_with(&[429, 429], "Retry-After: 3\r\n").await;
+    let client = client(&url, quick(2));
+    let err = client.get_session("a").await.unwrap_err();
+    assert_eq!(err.retry_after(), Some(Duration::from_secs(3)));
+    let resp = client
+        .request_raw(Method::GET, "/health", None)
+        .await
+        .unwrap();
+    assert_eq!(resp.retry_after(), Some(Duration::from_secs(3)));
+    assert_eq!(client.retried_requests(), 0);
+}
@@ -0,0 +1,766 @@
+//! Runner behaviour — selection, retries, timeouts, hooks, leak sweeping —
+//! checked against a scripted in-process client.
+
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use steel_tester::capabilities::{Capabilities, Capability};
+use steel_tester::client::{ApiClient, ApiFuture, ClientError, Session};
+use steel_tester::context;
+use steel_tester::interrupt::Interrupt;
+use steel_tester::latency;
+use steel_tester::report::{GroupReport, Outcome, RunReport, TestStatus};
+use steel_tester::runner::{GroupHooks, RunnerConfig, TestCase, TestRunner};
+use steel_tester::stats::{self, Stats};
+
+/// What one scripted call does instead of the default behaviour.
+enum Reply {
+    /// Wait, then behave as if unscripted.
+    Delay(Duration),
+    Fail(ClientError),
+}
+
+/// In-memory `ApiClient`. Unscripted calls behave like a working
+/// orchestrator; `script` queues replies for the next calls of one operation
+/// ("create", "get", "delete", "health", or "crash").
+#[derive(Default)]
+struct FakeClient {
+    sessions: Mutex<HashMap<String, Value>>,
+    /// Sessions created and not deleted, as the real client's tracker sees them.
+    outstanding: Mutex<Vec<String>>,
+    script: Mutex<HashMap<&'static str, VecDeque<Reply>>>,
+    calls: Mutex<Vec<String>>,
+    /// Where successful calls are timed; give it a runner's to feed its
+    /// latency table.
+    stats: Stats,
+    next_id: AtomicUsize,
+}
+
+impl FakeClient {
+    fn script(&self, operation: &'static str, reply: Reply) {
+        self.script
+            .lock()
+            .unwrap()
+            .entry(operation)
+            .or_default()
+            .push_back(reply);
+    }
+
+    /// Calls made so far, as "operation" or "operation id".
+    fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    /// Record the call and play its scripted reply, if any, timing it at the
+    /// operation's endpoint when it succeeds.
+    async fn enter(&self, operation: &'static str, id: Option<&str>) -> Result<(), ClientError> {
+        self.calls.lock().unwrap().push(match id {
+            Some(id) => format!("{operation} {id}"),
+            None => operation.to_string(),
+        });
+        let reply = self
+            .script
+            .lock()
+            .unwrap()
+            .get_mut(operation)
+            .and_then(VecDeque::pop_front);
+        let started = Instant::now();
+        let result = match reply {
+            None => Ok(()),
+            Some(Reply::Delay(delay)) => {
+                tokio::time::sleep(delay).await;
+                Ok(())
+            }
+            Some(Reply::Fail(e)) => Err(e),
+        };
+        if let (Ok(()), Some(endpoint)) = (&result, latency::endpoint(operation)) {
+            let reply = stats::Reply::Status(StatusCode::OK);
+            self.stats.record(endpoint, reply, started.elapsed(), 0, 0);
+        }
+        result
+    }
+
+    fn forget(&self, id: &str) -> bool {
+        self.outstanding.lock().unwrap().retain(|o| o != id);
+        self.sessions.lock().unwrap().remove(id).is_some()
+    }
+}
+
+fn session(id: &str, data: &Value) -> Session {
+    serde_json::from_value(json!({
+        "id": id,
+        "created_at": "2024-01-15T10:30:00Z",
+        "data": data,
+    }))
+    .expect("well-formed session")
+}
+
+impl ApiClient for FakeClient {
+    fn base_url(&self) -> &str {
+        "fake://orchestrator"
+    }
+
+    fn create_session(&self, data: Value) -> ApiFuture<'_, Session> {
+        Box::pin(async move {
+            self.enter("create", None).await?;
+            let id = format!("fake-{}", self.next_id.fetch_add(1, Ordering::Relaxed));
+            self.sessions
+                .lock()
+                .unwrap()
+                .insert(id.clone(), data.clone());
+            self.outstanding.lock().unwrap().push(id.clone());
+            Ok(session(&id, &data))
+        })
+    }
+
+    fn get_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, Session> {
+        Box::pin(async move {
+            self.enter("get", Some(id)).await?;
+            match self.sessions.lock().unwrap().get(id) {
+                Some(data) => Ok(session(id, data)),
+                None => Err(ClientError::NotFound { request_id: None }),
+            }
+        })
+    }
+
+    fn delete_session<'a>(&'a self, id: &'a str) -> ApiFuture<'a, StatusCode> {
+        Box::pin(async move {
+            self.enter("delete", Some(id)).await?;
+            Ok(if self.forget(id) {
+                StatusCode::NO_CONTENT
+            } else {
+                StatusCode::NOT_FOUND
+            })
+        })
+    }
+
+    fn health(&self) -> ApiFuture<'_, String> {
+        Box::pin(async move {
+            self.enter("health", None).await?;
+            Ok("ok".to_string())
+        })
+    }
+
+    fn crash_worker<'a>(&'a self, session_id: &'a str) -> ApiFuture<'a, ()> {
+        Box::pin(async move {
+            self.enter("crash", Some(session_id)).await?;
+            self.sessions.lock().unwrap().remove(session_id);
+            Ok(())
+        })
+    }
+
+    fn outstanding_sessions(&self) -> Vec<(String, String)> {
+        self.outstanding
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|id| (id.clone(), "Group / test".to_string()))
+            .collect()
+    }
+}
+
+fn http_error(status: StatusCode) -> ClientError {
+    ClientError::Http {
+        status,
+        body: String::new(),
+        request_id: None,
+        retry_after: None,
+    }
+}
+
+/// A test that checks health and fails if it can't.
+fn health_check(name: &str) -> TestCase<FakeClient> {
+    TestCase::new(name, |client: &FakeClient| {
+        Box::pin(async move {
+            client.health().await?;
+            Ok(())
+        })
+    })
+}
+
+/// A group to register: name, test cases, and hooks.
+type Group = (
+    &'static str,
+    Vec<TestCase<FakeClient>>,
+    GroupHooks<FakeClient>,
+);
+
+async fn run(config: RunnerConfig, client: &FakeClient, groups: Vec<Group>) -> RunReport {
+    let mut runner = TestRunner::new(config);
+    for (name, tests, hooks) in groups {
+        runner.add_group_with_hooks(name, tests, hooks);
+    }
+    runner.run(client, &Interrupt::never()).await
+}
+
+fn statuses(group: &GroupReport) -> Vec<TestStatus> {
+    group.tests.iter().map(|test| test.status).collect()
+}
+
+#[tokio::test]
+async fn a_passing_run_passes() {
+    let client = FakeClient::default();
+    let tests = vec![health_check("first"), health_check("second")];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    assert_eq!(report.outcome, Outcome::Passed);
+    assert_eq!(report.exit_code, 0);
+    assert_eq!(report.totals.passed, 2);
+    assert_eq!(client.calls(), ["health", "health"]);
+}
+
+#[tokio::test]
+async fn parameterized_cases_are_tests_of_their_own() {
+    let client = FakeClient::default();
+    let tests = TestCase::parameterized("Parity of {}", [1, 12, 3], |_: &FakeClient, n| {
+        Box::pin(async move {
+            if n % 2 == 1 {
+                Ok(())
+            } else {
+                Err(format!("{n} is even"))
+            }
+        })
+    });
+    let config = RunnerConfig {
+        filter: Some("of 1".to_string()),
+        ..RunnerConfig::default()
+    };
+    let report = run(
+        config,
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    let group = &report.groups[0];
+    let names: Vec<_> = group.tests.iter().map(|t| t.name.as_str()).collect();
+    assert_eq!(names, ["Parity of 1", "Parity of 12", "Parity of 3"]);
+    assert_eq!(
+        statuses(group),
+        [TestStatus::Passed, TestStatus::Failed, TestStatus::Skipped]
+    );
+    assert_eq!(group.tests[1].error.as_deref(), Some("12 is even"));
+}
+
+#[test]
+#[should_panic(expected = "\"Parity of 1\" would be registered twice")]
+fn parameterized_values_must_name_distinct_tests() {
+    TestCase::parameterized("Parity of {}", [1, 3, 1], |_: &FakeClient, _| {
+        Box::pin(async { Ok(()) })
+    });
+}
+
+#[tokio::test]
+async fn a_timed_out_test_is_reported_as_failed() {
+    let client = FakeClient::default();
+    client.script("health", Reply::Delay(Duration::from_secs(30)));
+    let tests = vec![
+        health_check("hangs").with_timeout(Duration::from_secs(1)),
+        health_check("runs after"),
+    ];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    let group = &report.groups[0];
+    assert_eq!(statuses(group), [TestStatus::Failed, TestStatus::Passed]);
+    let error = group.tests[0].error.as_deref().unwrap_or_default();
+    assert!(error.contains("timed out after 1s"), "error: {error}");
+    assert_eq!(report.outcome, Outcome::TestsFailed);
+}
+
+#[tokio::test]
+async fn teardown_still_runs_when_a_test_fails() {
+    let client = FakeClient::default();
+    let tests = vec![TestCase::new(
+        "creates then fails",
+        |client: &FakeClient| {
+            Box::pin(async move {
+                client.create_session(json!({"user": "teardown"})).await?;
+                Err("failed on purpose".to_string())
+            })
+        },
+    )];
+    let hooks = GroupHooks {
+        setup: None,
+        teardown: Some(Box::new(|client: &FakeClient| {
+            Box::pin(async move {
+                for (id, _) in client.outstanding_sessions() {
+                    client.delete_session(&id).await?;
+                }
+                Ok(())
+            })
+        })),
+    };
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, hooks)],
+    )
+    .await;
+
+    assert_eq!(statuses(&report.groups[0]), [TestStatus::Failed]);
+    assert_eq!(client.calls(), ["create", "delete fake-0"]);
+    assert!(report.groups[0].warnings.is_empty());
+    assert!(report.leaked_sessions.is_empty());
+}
+
+#[tokio::test]
+async fn a_failed_teardown_is_a_warning() {
+    let client = FakeClient::default();
+    client.script("health", Reply::Fail(http_error(StatusCode::BAD_GATEWAY)));
+    let hooks = GroupHooks {
+        setup: None,
+        teardown: Some(Box::new(|client: &FakeClient| {
+            Box::pin(async move {
+                client.health().await?;
+                Ok(())
+            })
+        })),
+    };
+    let tests = vec![TestCase::new("passes", |_: &FakeClient| {
+        Box::pin(async { Ok(()) })
+    })];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, hooks)],
+    )
+    .await;
+
+    // Tests passed, but cleanup may be incomplete.
+    assert_eq!(report.outcome, Outcome::LeakedResources);
+    assert_eq!(report.groups[0].warnings.len(), 1);
+    assert!(report.groups[0].warnings[0].starts_with("teardown failed"));
+}
+
+#[tokio::test]
+async fn a_failed_setup_skips_the_group() {
+    let client = FakeClient::default();
+    let hooks = GroupHooks {
+        setup: Some(Box::new(|_: &FakeClient| {
+            Box::pin(async { Err("no such endpoint".to_string()) })
+        })),
+        teardown: None,
+    };
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", vec![health_check("never runs")], hooks)],
+    )
+    .await;
+
+    let test = &report.groups[0].tests[0];
+    assert_eq!(test.status, TestStatus::Skipped);
+    assert_eq!(
+        test.skip_reason.as_deref(),
+        Some("group setup failed: no such endpoint")
+    );
+    assert!(client.calls().is_empty());
+    assert_eq!(report.outcome, Outcome::Passed);
+}
+
+#[tokio::test]
+async fn a_retried_pass_is_flaky() {
+    let client = FakeClient::default();
+    client.script(
+        "health",
+        Reply::Fail(http_error(StatusCode::SERVICE_UNAVAILABLE)),
+    );
+    let config = RunnerConfig {
+        retries: 2,
+        ..RunnerConfig::default()
+    };
+    let report = run(
+        config,
+        &client,
+        vec![("Group", vec![health_check("flaky")], GroupHooks::default())],
+    )
+    .await;
+
+    let test = &report.groups[0].tests[0];
+    assert_eq!(test.status, TestStatus::Passed);
+    assert_eq!(test.attempts, 2);
+    assert_eq!(report.totals.flaky, 1);
+}
+
+#[tokio::test]
+async fn a_not_retryable_test_gets_one_attempt() {
+    let client = FakeClient::default();
+    client.script(
+        "health",
+        Reply::Fail(http_error(StatusCode::SERVICE_UNAVAILABLE)),
+    );
+    let config = RunnerConfig {
+        retries: 2,
+        ..RunnerConfig::default()
+    };
+    let tests = vec![health_check("once").not_retryable()];
+    let report = run(
+        config,
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    assert_eq!(report.groups[0].tests[0].status, TestStatus::Failed);
+    assert_eq!(report.groups[0].tests[0].attempts, 1);
+}
+
+#[tokio::test]
+async fn expected_failures_are_not_counted_but_passes_are() {
+    let client = FakeClient::default();
+    client.script(
+        "health",
+        Reply::Fail(ClientError::NotFound { request_id: None }),
+    );
+    let tests = vec![
+        health_check("known bug").expected_failure("tracked"),
+        health_check("fixed bug").expected_failure("tracked"),
+    ];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::ExpectedFailure, TestStatus::UnexpectedPass]
+    );
+    assert_eq!(report.outcome, Outcome::TestsFailed);
+}
+
+#[tokio::test]
+async fn selection_filters_skip_without_running() {
+    let client = FakeClient::default();
+    let tests = vec![
+        health_check("matching test"),
+        health_check("other test"),
+        health_check("matching but slow").with_tags(&["slow"]),
+    ];
+    let config = RunnerConfig {
+        filter: Some("MATCHING".to_string()),
+        exclude_tags: vec!["slow".to_string()],
+        ..RunnerConfig::default()
+    };
+    let report = run(
+        config,
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::Passed, TestStatus::Skipped, TestStatus::Skipped]
+    );
+    assert_eq!(client.calls(), ["health"]);
+}
+
+#[tokio::test]
+async fn missing_capabilities_are_unsupported_unless_required() {
+    let mut capabilities = Capabilities::default();
+    capabilities.metrics = false;
+    let tests = || {
+        vec![
+            health_check("needs metrics").requires(Capability::Metrics),
+            health_check("needs nothing"),
+        ]
+    };
+
+    let client = FakeClient::default();
+    let report = run(
+        RunnerConfig {
+            capabilities,
+            ..RunnerConfig::default()
+        },
+        &client,
+        vec![("Group", tests(), GroupHooks::default())],
+    )
+    .await;
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::Unsupported, TestStatus::Passed]
+    );
+    assert_eq!(report.totals.unsupported, 1);
+    assert_eq!(report.totals.skipped, 0);
+    assert_eq!(report.outcome, Outcome::Passed);
+    assert_eq!(client.calls(), ["health"]);
+
+    let report = run(
+        RunnerConfig {
+            capabilities,
+            require_all: true,
+            ..RunnerConfig::default()
+        },
+        &FakeClient::default(),
+        vec![("Group", tests(), GroupHooks::default())],
+    )
+    .await;
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::Failed, TestStatus::Passed]
+    );
+    assert_eq!(report.outcome, Outcome::TestsFailed);
+}
+
+#[tokio::test]
+async fn an_exhausted_budget_stops_starting_tests() {
+    let client = FakeClient::default();
+    // The first test outlasts the budget but, already running, finishes.
+    client.script("health", Reply::Delay(Duration::from_millis(300)));
+    let tests = vec![
+        health_check("outlasts the budget"),
+        health_check("never starts"),
+    ];
+    let config = RunnerConfig {
+        max_duration: Some(Duration::from_millis(100)),
+        repeat: 2,
+        ..RunnerConfig::default()
+    };
+    let report = run(
+        config,
+        &client,
+        vec![
+            ("First", tests, GroupHooks::default()),
+            (
+                "Second",
+                vec![health_check("later group")],
+                GroupHooks::default(),
+            ),
+        ],
+    )
+    .await;
+
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::Passed, TestStatus::NotRun]
+    );
+    assert_eq!(
+        report.groups[0].tests[1].skip_reason.as_deref(),
+        Some("not run (budget exceeded)")
+    );
+    // Later groups and iterations are planned but never run.
+    assert_eq!(report.groups.len(), 4);
+    assert_eq!(report.totals.planned, 6);
+    assert_eq!(report.totals.executed, 1);
+    assert_eq!(report.outcome, Outcome::BudgetExceeded);
+    assert_eq!(report.exit_code, 4);
+    assert_eq!(client.calls(), ["health"]);
+}
+
+#[tokio::test]
+async fn fail_fast_stops_the_run() {
+    let client = FakeClient::default();
+    client.script("health", Reply::Fail(http_error(StatusCode::BAD_GATEWAY)));
+    let config = RunnerConfig {
+        fail_fast: true,
+        ..RunnerConfig::default()
+    };
+    let report = run(
+        config,
+        &client,
+        vec![
+            (
+                "First",
+                vec![health_check("fails"), health_check("after")],
+                GroupHooks::default(),
+            ),
+            ("Second", vec![health_check("later")], GroupHooks::default()),
+        ],
+    )
+    .await;
+
+    assert_eq!(
+        statuses(&report.groups[0]),
+        [TestStatus::Failed, TestStatus::NotRun]
+    );
+    assert_eq!(statuses(&report.groups[1]), [TestStatus::NotRun]);
+    assert_eq!(client.calls(), ["health"]);
+}
+
+#[tokio::test]
+async fn leaked_sessions_are_swept_and_reported() {
+    let client = FakeClient::default();
+    let tests = vec![TestCase::new("leaks", |client: &FakeClient| {
+        Box::pin(async move {
+            client.create_session(json!({"user": "leak"})).await?;
+            Ok(())
+        })
+    })];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    assert_eq!(report.leaked_sessions.len(), 1);
+    assert_eq!(report.leaked_sessions[0].id, "fake-0");
+    assert!(report.leaked_sessions[0].swept);
+    assert!(client.outstanding_sessions().is_empty());
+    // Swept leaks only fail the run with --strict-leaks.
+    assert_eq!(report.outcome, Outcome::Passed);
+}
+
+#[tokio::test]
+async fn shuffling_is_reproducible_from_the_seed() {
+    async fn order(seed: u64) -> Vec<String> {
+        let client = FakeClient::default();
+        let names = |prefix: &str| -> Vec<TestCase<FakeClient>> {
+            (0..8)
+                .map(|i| health_check(&format!("{prefix} {i}")))
+                .collect()
+        };
+        let config = RunnerConfig {
+            shuffle: true,
+            shuffle_groups: true,
+            seed,
+            ..RunnerConfig::default()
+        };
+        let mut runner = TestRunner::new(config);
+        runner.add_group("Shuffled", names("s"));
+        runner.add_group("Ordered", names("o"));
+        runner.add_group("Last", names("l"));
+        runner.keep_order("Ordered");
+        runner.run(&client, &Interrupt::never()).await.order
+    }
+
+    let first = order(7).await;
+    assert_eq!(first.len(), 24);
+    assert_eq!(first, order(7).await);
+    assert_ne!(first, order(8).await);
+
+    let ordered: Vec<&String> = first.iter().filter(|t| t.starts_with("Ordered")).collect();
+    let expected: Vec<String> = (0..8).map(|i| format!("Ordered / o {i}")).collect();
+    assert_eq!(ordered, expected.iter().collect::<Vec<_>>());
+    let shuffled: Vec<&String> = first.iter().filter(|t| t.starts_with("Shuffled")).collect();
+    assert!(
+        shuffled.windows(2).any(|pair| pair[0] > pair[1]),
+        "{shuffled:?}"
+    );
+}
+
+#[tokio::test]
+async fn run_first_tests_lead_the_run_and_run_alone() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let logged = |name: &'static str, pause: Duration| {
+        let events = events.clone();
+        TestCase::new(name, move |_: &FakeClient| {
+            let events = events.clone();
+            Box::pin(async move {
+                events.lock().unwrap().push(format!("{name} started"));
+                tokio::time::sleep(pause).await;
+                events.lock().unwrap().push(format!("{name} finished"));
+                Ok(())
+            })
+        })
+    };
+    let quick = Duration::from_millis(1);
+    let config = RunnerConfig {
+        parallel: 4,
+        shuffle: true,
+        shuffle_groups: true,
+        ..RunnerConfig::default()
+    };
+    let mut runner = TestRunner::new(config);
+    runner.add_group(
+        "Before",
+        vec![
+            logged("b0", quick),
+            logged("b1", quick),
+            logged("b2", quick),
+        ],
+    );
+    runner.add_group(
+        "Pinned",
+        vec![
+            logged("p0", quick),
+            logged("p1", quick),
+            logged("cold", Duration::from_millis(30)).run_first(),
+        ],
+    );
+    let report = runner
+        .run(&FakeClient::default(), &Interrupt::never())
+        .await;
+
+    assert_eq!(report.order.len(), 6);
+    assert_eq!(report.order[0], "Pinned / cold");
+    let events = events.lock().unwrap();
+    assert_eq!(events[..2], ["cold started", "cold finished"]);
+}
+
+#[tokio::test]
+async fn a_latency_budget_over_p95_fails_a_passing_run() {
+    let mut runner = TestRunner::new(RunnerConfig {
+        slo: [
+            ("health".to_string(), Duration::from_millis(20)),
+            ("create".to_string(), Duration::from_millis(20)),
+        ]
+        .into(),
+        ..RunnerConfig::default()
+    });
+    let client = FakeClient {
+        stats: runner.stats().clone(),
+        ..FakeClient::default()
+    };
+    client.script("health", Reply::Delay(Duration::from_millis(60)));
+    let tests = (0..4)
+        .map(|i| health_check(&format!("check {i}")))
+        .collect();
+    runner.add_group("Group", tests);
+    let report = runner.run(&client, &Interrupt::never()).await;
+
+    assert_eq!(report.totals.passed, 4);
+    assert_eq!(report.outcome, Outcome::SloExceeded);
+    assert_eq!(report.exit_code, 5);
+    // Only measured operations appear; `create` has a budget but no calls.
+    assert_eq!(report.latency.len(), 1);
+    let health = &report.latency[0];
+    assert_eq!((health.operation.as_str(), health.samples), ("health", 4));
+    assert!(health.p95_ms >= 60.0 && health.p50_ms < 20.0, "{health:?}");
+    assert_eq!(health.budget_p95_ms, Some(20.0));
+}
+
+#[tokio::test]
+async fn a_test_can_find_its_feature_unsupported() {
+    let client = FakeClient::default();
+    let tests = vec![
+        TestCase::new("probes", |client: &FakeClient| {
+            Box::pin(async move {
+                client.health().await?;
+                context::unsupported("orchestrator ignores the header");
+                Ok(())
+            })
+        }),
+        health_check("passes"),
+    ];
+    let report = run(
+        RunnerConfig::default(),
+        &client,
+        vec![("Group", tests, GroupHooks::default())],
+    )
+    .await;
+
+    let probes = &report.groups[0].tests[0];
+    assert_eq!(probes.status, TestStatus::Unsupported);
+    assert_eq!(
+        probes.skip_reason.as_deref(),
+        Some("orchestrator ignores the header")
+    );
+    assert_eq!(report.totals.unsupported, 1);
+    assert_eq!(report.outcome, Outcome::Passed);
+}
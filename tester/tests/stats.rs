@@ -0,0 +1,135 @@
+//! Run stats: which endpoint an exchange is counted under, how it is
+//! classified, that concurrent records all land, and that the client counts
+//! the body bytes that arrive.
+
+use reqwest::{Method, StatusCode};
+use std::time::Duration;
+use steel_tester::client::OrchestratorClient;
+use steel_tester::stats::{self, Reply, Stats};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+
+#[test]
+fn session_ids_share_one_endpoint() {
+    let cases = [
+        (Method::POST, "/sessions", "POST /sessions"),
+        (Method::GET, "/sessions/", "GET /sessions/"),
+        (Method::GET, "/sessions/3f2a", "GET /sessions/{id}"),
+        (
+            Method::DELETE,
+            "/sessions/not-a-uuid",
+            "DELETE /sessions/{id}",
+        ),
+        (Method::GET, "/sessions/3f2a/logs", "GET /sessions/{id}/…"),
+        (
+            Method::POST,
+            "/debug/crash-worker",
+            "POST /debug/crash-worker",
+        ),
+    ];
+    for (method, path, expected) in cases {
+        assert_eq!(stats::endpoint(&method, path), expected, "{path}");
+    }
+}
+
+#[test]
+fn exchanges_are_classified_and_only_answers_are_timed() {
+    let stats = Stats::default();
+    let ms = Duration::from_millis;
+    stats.record("GET /health", Reply::Status(StatusCode::OK), ms(10), 0, 2);
+    stats.record(
+        "GET /health",
+        Reply::Status(StatusCode::NOT_FOUND),
+        ms(20),
+        0,
+        9,
+    );
+    stats.record(
+        "GET /health",
+        Reply::Status(StatusCode::BAD_GATEWAY),
+        ms(30),
+        0,
+        0,
+    );
+    stats.record("GET /health", Reply::Timeout, ms(5000), 0, 0);
+    stats.record("POST /sessions", Reply::Connect, ms(1), 14, 0);
+    stats.record_retry("POST /sessions");
+
+    let snapshot = stats.snapshot();
+    let names: Vec<_> = snapshot.iter().map(|e| e.endpoint.as_str()).collect();
+    assert_eq!(names, ["GET /health", "POST /sessions"]);
+
+    let health = &snapshot[0];
+    assert_eq!(health.requests, 4);
+    assert_eq!(health.client_errors, 1);
+    assert_eq!(health.server_errors, 1);
+    assert_eq!(health.timeouts, 1);
+    assert_eq!(health.bytes_received, 11);
+    assert_eq!(health.errors(), 2);
+    assert_eq!(health.error_rate(), 0.5);
+    // The timeout got no answer, so p95 is the slowest response.
+    assert_eq!((health.p50_ms, health.p95_ms), (20.0, 30.0));
+
+    let create = &snapshot[1];
+    assert_eq!(create.connect_errors, 1);
+    assert_eq!(create.bytes_sent, 14);
+    assert_eq!(create.max_ms, 0.0);
+    assert_eq!(stats.retries(), 1);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+async fn concurrent_records_are_all_counted() {
+    const TASKS: u64 = 32;
+    const RECORDS: u64 = 500;
+    let stats = Stats::default();
+    let tasks: Vec<_> = (0..TASKS)
+        .map(|task| {
+            let stats = stats.clone();
+            tokio::spawn(async move {
+                for i in 0..RECORDS {
+                    let endpoint = if i % 2 == 0 { "GET /a" } else { "GET /b" };
+                    let latency = Duration::from_millis(task * RECORDS + i);
+                    stats.record(endpoint, Reply::Status(StatusCode::OK), latency, 1, 1);
+                }
+            })
+        })
+        .collect();
+    for task in tasks {
+        task.await.unwrap();
+    }
+
+    let snapshot = stats.snapshot();
+    assert_eq!(snapshot.len(), 2);
+    for endpoint in &snapshot {
+        assert_eq!(
+            endpoint.requests,
+            TASKS * RECORDS / 2,
+            "{}",
+            endpoint.endpoint
+        );
+        assert_eq!(endpoint.bytes_sent, TASKS * RECORDS / 2);
+    }
+    let slowest = (TASKS * RECORDS - 1) as f64;
+    assert_eq!(snapshot[1].max_ms, slowest);
+}
+
+#[tokio::test]
+async fn chunked_bodies_count_the_bytes_that_arrived() {
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let url = format!("http://{}", listener.local_addr().unwrap());
+    tokio::spawn(async move {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut buf = [0; 4096];
+        let _ = stream.read(&mut buf).await;
+        let reply = "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n\
+                     3\r\nok \r\n4\r\nhere\r\n0\r\n\r\n";
+        let _ = stream.write_all(reply.as_bytes()).await;
+    });
+
+    let stats = Stats::default();
+    let client = OrchestratorClient::new(&url).with_stats(stats.clone());
+    assert_eq!(client.health().await.unwrap(), "ok here");
+    let health = &stats.snapshot()[0];
+    assert_eq!(health.endpoint, "GET /health");
+    assert_eq!((health.requests, health.bytes_received), (1, 7));
+}
@@ -0,0 +1,106 @@
+//! `util::wait_for` and `util::poll_until`: when they check, when they give
+//! up, and what they return, on tokio's paused clock so the waits take no
+//! real time.
+
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use steel_tester::tests::util::{self, Check, WaitError};
+use tokio::time::Instant;
+
+const SECOND: Duration = Duration::from_secs(1);
+
+/// A condition that is not yet met for its first `misses` checks, counting
+/// every check in `checks`.
+fn done_after(misses: u32, checks: &AtomicU32) -> impl FnMut() -> std::future::Ready<Check> + '_ {
+    move || {
+        let n = checks.fetch_add(1, Ordering::SeqCst);
+        std::future::ready(if n < misses {
+            Check::NotYet(format!("miss {}", n + 1))
+        } else {
+            Check::Done
+        })
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn returns_how_long_the_condition_took() {
+    let checks = AtomicU32::new(0);
+    let waited = util::wait_for(Instant::now() + 10 * SECOND, SECOND, done_after(3, &checks)).await;
+    assert_eq!(waited, Ok(3 * SECOND));
+    assert_eq!(checks.load(Ordering::SeqCst), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn the_last_check_is_at_the_deadline() {
+    let checks = AtomicU32::new(0);
+    let start = Instant::now();
+    let waited = util::wait_for(start + SECOND * 5 / 2, SECOND, done_after(10, &checks)).await;
+    assert_eq!(
+        waited,
+        Err(WaitError::TimedOut {
+            waited: SECOND * 5 / 2,
+            checks: 4,
+            last: "miss 4".to_string(),
+        })
+    );
+    assert_eq!(start.elapsed(), SECOND * 5 / 2);
+    assert_eq!(
+        waited.unwrap_err().to_string(),
+        "gave up after 2.5s (4 checks), last: miss 4"
+    );
+}
+
+#[tokio::test(start_paused = true)]
+async fn a_fatal_check_stops_at_once() {
+    let start = Instant::now();
+    let waited = util::wait_for(start + 10 * SECOND, SECOND, || async {
+        Check::Fatal("never will".to_string())
+    })
+    .await;
+    assert_eq!(waited, Err(WaitError::Fatal("never will".to_string())));
+    assert_eq!(start.elapsed(), Duration::ZERO);
+}
+
+#[tokio::test(start_paused = true)]
+async fn sleeps_between_checks_until_time_advances() {
+    let checks = AtomicU32::new(0);
+    let waiting = util::wait_for(Instant::now() + 10 * SECOND, SECOND, done_after(1, &checks));
+    tokio::pin!(waiting);
+
+    // The first check misses, so the wait sleeps a full interval.
+    assert!(futures::poll!(waiting.as_mut()).is_pending());
+    assert_eq!(checks.load(Ordering::SeqCst), 1);
+    tokio::time::advance(SECOND / 2).await;
+    assert!(futures::poll!(waiting.as_mut()).is_pending());
+    assert_eq!(checks.load(Ordering::SeqCst), 1);
+
+    tokio::time::advance(SECOND / 2).await;
+    assert_eq!(waiting.await, Ok(SECOND));
+    assert_eq!(checks.load(Ordering::SeqCst), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn poll_until_returns_the_first_success() {
+    let mut attempts = 0;
+    let value = util::poll_until(10 * SECOND, SECOND, || {
+        attempts += 1;
+        std::future::ready(if attempts < 3 {
+            Err("not yet")
+        } else {
+            Ok(attempts)
+        })
+    })
+    .await;
+    assert_eq!(value, Ok(3));
+
+    let start = Instant::now();
+    let never = util::poll_until(SECOND * 5 / 2, SECOND, || {
+        std::future::ready(Err::<(), _>("refused"))
+    })
+    .await;
+    assert_eq!(
+        never,
+        Err("gave up after 2.5s (4 checks), last: refused".to_string())
+    );
+    assert_eq!(start.elapsed(), SECOND * 5 / 2);
+}